@@ -1,17 +1,64 @@
 use std::collections::HashMap;   // 存储表名（String）到表引用（TableRef）的映射，是 Catalog 结构体中表管理的核心。
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use arrow::datatypes::Schema;
+use sqlparser::ast::Query;
 
 // 这里指的是使用当前项目的crate，而不是外部的crate
 use crate::error::ErrorCode;
 use crate::logical_plan::plan::{LogicalPlan, TableScan};
+use crate::logical_plan::schema::NaiveSchema;
 use crate::logical_plan::DataFrame;
+use crate::sql::parser::DistinctOnKeys;
 use crate::{
-    datasource::{CsvConfig, CsvTable, TableRef},
+    datasource::{CsvConfig, CsvTable, FeatherTable, TableRef},
     error::Result,
 };
 
 #[derive(Default, Debug)]
 pub struct Catalog {
     pub tables: HashMap<String, TableRef>,
+    /// CREATE VIEW 定义的视图：视图名到定义视图的查询，被引用时重新规划
+    views: HashMap<String, View>,
+    /// csv文件推断出的schema缓存，文件修改时间或长度变化后失效
+    schema_cache: HashMap<SchemaCacheKey, (FileStamp, Schema)>,
+    /// 实际推断schema的次数 测试中用来确认缓存命中时没有重新推断
+    #[cfg(test)]
+    schema_inferences: usize,
+}
+
+/// 判断csv文件是否被修改：修改时间和文件长度
+/// 修改时间的精度取决于文件系统，在同一个时间戳内改写且长度不变的文件仍会使用缓存的schema
+type FileStamp = (SystemTime, u64);
+
+/// 视图的定义 以及其中 DISTINCT ON 的去重键
+#[derive(Debug, Clone)]
+pub struct View {
+    pub query: Query,
+    pub distinct_on: DistinctOnKeys,
+}
+
+/// schema缓存的键：文件路径以及会影响schema推断结果的配置项
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SchemaCacheKey {
+    path: String,
+    has_header: bool,
+    delimiter: u8,
+    max_read_records: Option<usize>,
+}
+
+impl SchemaCacheKey {
+    fn new(csv_file: &str, csv_conf: &CsvConfig) -> Self {
+        Self {
+            path: csv_file.to_string(),
+            has_header: csv_conf.has_header,
+            delimiter: csv_conf.delimiter,
+            max_read_records: csv_conf.max_read_records,
+        }
+    }
 }
 
 impl Catalog {
@@ -28,13 +75,84 @@ impl Catalog {
         csv_file: &str,      // 文件路径
         csv_conf: CsvConfig,  // 配置
     ) -> Result<()> {
+        let schema = self.csv_schema(csv_file, &csv_conf)?;
         let source = 
-        CsvTable::try_create(table, 
-            csv_file, csv_conf)?;
+        CsvTable::try_create_with_schema(table, 
+            csv_file, csv_conf, schema)?;
+        self.tables.insert(table.to_string(), source);
+        Ok(())
+    }
+
+    /// add csv table from every csv file in a directory, all files must share the same schema
+    pub fn add_csv_table_from_dir(
+        &mut self,
+        table: &str,
+        dir: &str,
+        csv_conf: CsvConfig,
+    ) -> Result<()> {
+        let source = CsvTable::try_create_from_dir(table, dir, csv_conf)?;
         self.tables.insert(table.to_string(), source);
         Ok(())
     }
 
+    /// 复制当前所有表的引用 表的数据通过 Arc 共享 之后对本 catalog 的修改不会影响副本
+    pub fn snapshot(&self) -> Catalog {
+        Catalog {
+            tables: self.tables.clone(),
+            views: self.views.clone(),
+            ..Catalog::default()
+        }
+    }
+
+    /// add csv table with an explicit schema, NOT NULL columns are validated on load
+    pub fn add_csv_table_with_schema(
+        &mut self,
+        table: &str,
+        csv_file: &str,
+        schema: Schema,
+        csv_conf: CsvConfig,
+    ) -> Result<()> {
+        let source = CsvTable::try_create_with_declared_schema(table, csv_file, csv_conf, schema)?;
+        CsvTable::check_not_null(&source.scan(None)?)?;
+        self.tables.insert(table.to_string(), source);
+        Ok(())
+    }
+
+    /// 重新读取表对应的文件 替换内存中缓存的数据 推断的schema同样使用缓存
+    pub fn reload_table(&mut self, table: &str) -> Result<()> {
+        let source = self
+            .get_table(table)?
+            .reload(&mut |csv_file, csv_conf| self.csv_schema(csv_file, csv_conf))?;
+        self.tables.insert(table.to_string(), source);
+        Ok(())
+    }
+
+    /// add arrow ipc (feather) table
+    pub fn add_feather_table(&mut self, table: &str, feather_file: &str) -> Result<()> {
+        let source = FeatherTable::try_create(table, feather_file)?;
+        self.tables.insert(table.to_string(), source);
+        Ok(())
+    }
+
+    // 获取csv文件的schema：文件未被修改时直接使用缓存，否则重新推断并更新缓存
+    fn csv_schema(&mut self, csv_file: &str, csv_conf: &CsvConfig) -> Result<Schema> {
+        let key = SchemaCacheKey::new(csv_file, csv_conf);
+        let metadata = fs::metadata(env::current_dir()?.join(Path::new(csv_file)))?;
+        let stamp = (metadata.modified()?, metadata.len());
+        if let Some((cached_stamp, schema)) = self.schema_cache.get(&key) {
+            if *cached_stamp == stamp {
+                return Ok(schema.clone());
+            }
+        }
+        let schema = CsvTable::infer_schema_from_csv(csv_file, csv_conf)?;
+        #[cfg(test)]
+        {
+            self.schema_inferences += 1;
+        }
+        self.schema_cache.insert(key, (stamp, schema.clone()));
+        Ok(schema)
+    }
+
 
     #[allow(unused)]
     pub fn add_new_table(
@@ -54,6 +172,29 @@ impl Catalog {
             .ok_or_else(|| ErrorCode::NoSuchTable(format!("No table name: {}", table)))
     }
 
+    /// 只获取表的 schema 不需要表的引用（例如只根据列名确定列的位置）
+    pub fn get_table_schema(&self, table: &str) -> Result<NaiveSchema> {
+        self.tables
+            .get(table)
+            .map(|source| source.schema().clone())
+            .ok_or_else(|| ErrorCode::NoSuchTable(format!("No table name: {}", table)))
+    }
+
+    /// 添加视图 同名的视图会被替换
+    pub fn add_view(&mut self, name: String, view: View) -> Option<View> {
+        self.views.insert(name, view)
+    }
+
+    /// 删除指定名称的视图
+    pub fn remove_view(&mut self, view: &str) -> Option<View> {
+        self.views.remove(view)
+    }
+
+    /// 获取定义视图的查询 不存在时返回 None
+    pub fn get_view(&self, view: &str) -> Option<View> {
+        self.views.get(view).cloned()
+    }
+
     #[allow(unused)]
     /// get dataframe by table name   获取数据帧以执行查询
     pub fn get_table_df(&self, table: &str) -> Result<DataFrame> {
@@ -62,10 +203,136 @@ impl Catalog {
             .get(table)
             .cloned()
             .ok_or_else(|| ErrorCode::NoSuchTable(format!("No table name: {}", table)))?;
-        let plan = LogicalPlan::TableScan(TableScan {
-            source,
-            projection: None,
-        });
+        let plan = LogicalPlan::TableScan(TableScan::new(source));
         Ok(DataFrame { plan })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+    use crate::datasource::test_utils::CountingTable;
+    use crate::sql::parser::SQLParser;
+    use crate::sql::planner::SQLPlanner;
+
+    #[test]
+    fn reloading_unchanged_csv_reuses_cached_schema() -> Result<()> {
+        let mut catalog = Catalog::default();
+        catalog.add_csv_table("employee", "data/employee.csv", CsvConfig::default())?;
+        catalog.add_csv_table("employee", "data/employee.csv", CsvConfig::default())?;
+        catalog.add_csv_table("staff", "data/employee.csv", CsvConfig::default())?;
+        catalog.reload_table("employee")?;
+        assert_eq!(catalog.schema_inferences, 1);
+
+        // 影响推断结果的配置不同 需要重新推断
+        let csv_conf = CsvConfig {
+            max_read_records: Some(1),
+            ..CsvConfig::default()
+        };
+        catalog.add_csv_table("employee", "data/employee.csv", csv_conf)?;
+        assert_eq!(catalog.schema_inferences, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn modified_csv_is_inferred_again() -> Result<()> {
+        let dir = env::temp_dir().join(format!("simple_db_schema_cache_{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("t.csv");
+        fs::write(&path, "id,name\n1,a\n")?;
+        let file = path.to_string_lossy().into_owned();
+
+        let mut catalog = Catalog::default();
+        catalog.add_csv_table("t", &file, CsvConfig::default())?;
+        catalog.add_csv_table("t", &file, CsvConfig::default())?;
+        assert_eq!(catalog.schema_inferences, 1);
+
+        fs::write(&path, "id,name,age\n1,a,3\n")?;
+        File::options()
+            .write(true)
+            .open(&path)?
+            .set_modified(SystemTime::now() + Duration::from_secs(60))?;
+        catalog.add_csv_table("t", &file, CsvConfig::default())?;
+        assert_eq!(catalog.schema_inferences, 2);
+        assert_eq!(catalog.get_table("t")?.schema().fields().len(), 3);
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn csv_with_the_same_mtime_but_new_length_is_inferred_again() -> Result<()> {
+        let dir = env::temp_dir().join(format!("simple_db_schema_length_{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("t.csv");
+        fs::write(&path, "id,name\n1,a\n")?;
+        let modified = fs::metadata(&path)?.modified()?;
+        let file = path.to_string_lossy().into_owned();
+
+        let mut catalog = Catalog::default();
+        catalog.add_csv_table("t", &file, CsvConfig::default())?;
+        assert_eq!(catalog.schema_inferences, 1);
+
+        // 改写文件后恢复原来的修改时间 只有长度不同
+        fs::write(&path, "id,name,age\n1,a,3\n")?;
+        File::options().write(true).open(&path)?.set_modified(modified)?;
+        catalog.add_csv_table("t", &file, CsvConfig::default())?;
+        assert_eq!(catalog.schema_inferences, 2);
+        assert_eq!(catalog.get_table("t")?.schema().fields().len(), 3);
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn reloading_modified_csv_infers_the_new_schema() -> Result<()> {
+        let dir = env::temp_dir().join(format!("simple_db_schema_reload_{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("t.csv");
+        fs::write(&path, "id,name\n1,a\n")?;
+        let file = path.to_string_lossy().into_owned();
+
+        let mut catalog = Catalog::default();
+        catalog.add_csv_table("t", &file, CsvConfig::default())?;
+        catalog.reload_table("t")?;
+        assert_eq!(catalog.schema_inferences, 1);
+
+        fs::write(&path, "id,name,age\n1,a,3\n2,b,4\n")?;
+        File::options()
+            .write(true)
+            .open(&path)?
+            .set_modified(SystemTime::now() + Duration::from_secs(60))?;
+        catalog.reload_table("t")?;
+        assert_eq!(catalog.schema_inferences, 2);
+        let source = catalog.get_table("t")?;
+        assert_eq!(source.schema().fields().len(), 3);
+        assert_eq!(source.scan(None)?[0].num_columns(), 3);
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn table_schema_is_read_without_scanning_the_table() -> Result<()> {
+        let source = CountingTable::create(3, 2)?;
+        let mut catalog = Catalog::default();
+        catalog.add_new_table("t".to_string(), source.clone())?;
+
+        let schema = catalog.get_table_schema("t")?;
+        assert_eq!(schema.fields().len(), 1);
+        assert_eq!(schema.field(0).name(), "id");
+        // 规划查询和 DROP 都只读取表的 schema
+        for sql in ["SELECT id FROM t WHERE id > 1", "DROP TABLE t"] {
+            let (statement, _) = SQLParser::parse(sql)?;
+            SQLPlanner::new(&catalog).statement_to_plan(statement)?;
+        }
+        assert_eq!(source.scans(), 0);
+
+        let err = catalog.get_table_schema("nope").unwrap_err();
+        assert!(format!("{:?}", err).starts_with("NoSuchTable"), "{:?}", err);
+        Ok(())
+    }
+}
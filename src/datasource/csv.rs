@@ -1,26 +1,22 @@
 use std::env;
-use std::fs::File;
+use std::fmt;
+use std::fs::{self, File};
 use std::iter::Iterator;
 use std::path::Path;
 use std::sync::Arc;
 
-use crate::error::Result;
+use crate::error::{ErrorCode, Result};
 use crate::logical_plan::schema::NaiveSchema;
 
 use arrow::csv;
 use arrow::datatypes::Schema;
 use arrow::record_batch::RecordBatch;
 
-use super::TableSource;
+use super::{delete_rows, project_batches, project_batches_stream, TableSource, UniqueIndex};
 use crate::datasource::TableRef;
-use arrow::datatypes::DataType;
-use arrow::array::{Array, BooleanArray, UInt64Array, Int64Array, Float64Array, StringArray};
-use arrow::array::StringBuilder;
-use arrow::array::BooleanBuilder;
-use arrow::array::UInt64Builder;
-use arrow::array::Int64Builder;
-use arrow::array::Float64Builder;
+use arrow::array::{Array, ArrayRef, StringArray};
 
+#[derive(Debug, Clone)]
 pub struct CsvConfig {
     pub has_header: bool,
     pub delimiter: u8,     // 字段之间的分隔符，默认是‘，’
@@ -28,6 +24,8 @@ pub struct CsvConfig {
     pub batch_size: usize,
     pub file_projection: Option<Vec<usize>>,
     pub datetime_format: Option<String>,
+    // 读取后去掉字符串列首尾的空白字符
+    pub trim: bool,
 }
 
 impl Default for CsvConfig {
@@ -39,133 +37,214 @@ impl Default for CsvConfig {
             batch_size: 1_000_000,
             file_projection: None,
             datetime_format: None,
+            trim: false,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct 
 CsvTable {
     pub schema: NaiveSchema,     // 表的模式 元数据 结构信息
     pub batches: Vec<RecordBatch>,   // 数据
+    // 重新加载时使用的表名、文件路径、原始schema和配置
+    table_name: String,
+    path: String,
+    file_schema: Schema,
+    config: CsvConfig,
+    unique: Vec<UniqueIndex>,
+    // schema 由用户显式指定 重新加载时沿用，否则按文件的内容重新推断
+    declared_schema: bool,
 }
 
 impl CsvTable {
-    #[allow(unused, clippy::iter_next_loop)]
+    #[allow(unused)]
     pub fn try_create(table_name: &str, filename: &str, csv_config: CsvConfig) -> Result<TableRef> {
         // 1. 读取csv文件，获取原始schema
         let orig_schema = Self::infer_schema_from_csv(filename, &csv_config)?;
-        let mut schema = NaiveSchema::from_unqualified(&orig_schema);
-        schema.fields[0].set_qualifier(Some(table_name.to_string()));
+        Self::try_create_with_schema(table_name, filename, csv_config, orig_schema)
+    }
 
-        // 2. 读取csv文件，获取原始数据，构建 RecordBatch
-        let mut file = File::open(env::current_dir()?.join(Path::new(filename)))?;
-        // 3. 使用 Arrow 提供的工具函数 read_csv，读取 CSV 文件，构建 RecordBatch。
-        let mut reader = csv::Reader::new(
+    // 把目录下所有的 .csv 文件作为一张表 所有文件推断出的schema必须与第一个文件相同
+    pub fn try_create_from_dir(table_name: &str, dir: &str, csv_config: CsvConfig) -> Result<TableRef> {
+        let files = Self::list_csv_files(dir)?;
+        let orig_schema = Self::infer_schema_from_csv(&files[0], &csv_config)?;
+        let batches = Self::read_dir(dir, &csv_config, &orig_schema)?;
+        Ok(Arc::new(Self::from_batches(table_name, dir, csv_config, orig_schema, batches)))
+    }
+
+    // 使用已知的schema创建表，跳过schema推断（例如schema已被catalog缓存）
+    pub fn try_create_with_schema(
+        table_name: &str,
+        filename: &str,
+        csv_config: CsvConfig,
+        orig_schema: Schema,
+    ) -> Result<TableRef> {
+        let batches = Self::read_file(filename, &csv_config, &orig_schema)?;
+        Ok(Arc::new(Self::from_batches(table_name, filename, csv_config, orig_schema, batches)))
+    }
+
+    // 使用用户显式指定的schema创建表 重新加载时不会重新推断schema
+    pub fn try_create_with_declared_schema(
+        table_name: &str,
+        filename: &str,
+        csv_config: CsvConfig,
+        schema: Schema,
+    ) -> Result<TableRef> {
+        let batches = Self::read_file(filename, &csv_config, &schema)?;
+        Ok(Arc::new(Self {
+            declared_schema: true,
+            ..Self::from_batches(table_name, filename, csv_config, schema, batches)
+        }))
+    }
+
+    fn from_batches(
+        table_name: &str,
+        path: &str,
+        csv_config: CsvConfig,
+        orig_schema: Schema,
+        batches: Vec<RecordBatch>,
+    ) -> Self {
+        Self {
+            schema: NaiveSchema::from_qualified(table_name, &orig_schema),
+            batches,
+            table_name: table_name.to_string(),
+            path: path.to_string(),
+            file_schema: orig_schema,
+            config: csv_config,
+            unique: vec![],
+            declared_schema: false,
+        }
+    }
+
+    // 依次读取目录下的每个 .csv 文件 每个文件的schema都要与给定的schema相同
+    fn read_dir(dir: &str, csv_config: &CsvConfig, orig_schema: &Schema) -> Result<Vec<RecordBatch>> {
+        let mut batches = vec![];
+        for file in Self::list_csv_files(dir)? {
+            let file_schema = Self::infer_schema_from_csv(&file, csv_config)?;
+            if &file_schema != orig_schema {
+                return Err(ErrorCode::LogicalError(format!(
+                    "schema of `{}` does not match the other csv files in `{}`",
+                    file, dir
+                )));
+            }
+            batches.extend(Self::read_file(&file, csv_config, orig_schema)?);
+        }
+        Ok(batches)
+    }
+
+    // 使用 Arrow 提供的 csv::Reader 按给定的schema逐批读取文件
+    fn read_file(filename: &str, csv_config: &CsvConfig, orig_schema: &Schema) -> Result<Vec<RecordBatch>> {
+        let file = File::open(env::current_dir()?.join(Path::new(filename)))?;
+        let reader = csv::Reader::new(
             file,
-            Arc::new(orig_schema),
+            Arc::new(orig_schema.clone()),
             csv_config.has_header,
             Some(csv_config.delimiter),
             csv_config.batch_size,
             None,
             csv_config.file_projection.clone(),
-            csv_config.datetime_format,
+            csv_config.datetime_format.clone(),
         );
-        // 4. 逐批读取数据
         let mut batches = vec![];
+        for record in reader {
+            let record = record?;
+            batches.push(if csv_config.trim { Self::trim_strings(&record)? } else { record });
+        }
+        Ok(batches)
+    }
 
-        // 5. 构造CsvTable并返回
-        for record in reader.by_ref() {
-            batches.push(record?);
+    // 目录下扩展名为 .csv 的文件 按文件名排序 保证每次读取的行顺序相同
+    fn list_csv_files(dir: &str) -> Result<Vec<String>> {
+        let mut files = vec![];
+        for entry in fs::read_dir(env::current_dir()?.join(Path::new(dir)))? {
+            let path = entry?.path();
+            let is_csv = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+            if is_csv && path.is_file() {
+                if let Some(name) = path.file_name() {
+                    files.push(Path::new(dir).join(name).to_string_lossy().into_owned());
+                }
+            }
         }
-        Ok(Arc::new(Self {schema, batches }))
-    }
-
-    // 删除指定位置的列
-    pub fn try_delete(table: TableRef, row_indices_to_delete: Vec<usize>) -> Result<Vec<RecordBatch>> {
-        // 获取原始的表格模式
-        let schema = table.schema().clone();
-        let mut batches = table.scan(None)?;
-
-        // 遍历每个 RecordBatch 进行删除
-        for batch in &mut batches {
-            let mut columns = vec![];
-            for col in batch.columns() {
-                let dt = col.data_type();
-                let column: Arc<dyn Array> = match dt {
-                    DataType::Boolean => {
-                        // 删除指定行的布尔列
-                        let array = col.as_any().downcast_ref::<BooleanArray>().unwrap();
-                        let mut builder = BooleanBuilder::new(array.len() - row_indices_to_delete.len());
-                        for (i, valid) in array.iter().enumerate() {
-                            if !row_indices_to_delete.contains(&i) {
-                                builder.append_option(valid)?;
-                            }
-                        }
-                        Arc::new(builder.finish())
-                    }
-                    DataType::UInt64 => {
-                        // 删除指定行的无符号 64 位整数列
-                        let array = col.as_any().downcast_ref::<UInt64Array>().unwrap();
-                        let mut builder = UInt64Builder::new(array.len() - row_indices_to_delete.len());
-                        for (i, val) in array.iter().enumerate() {
-                            if !row_indices_to_delete.contains(&i) {
-                                builder.append_option(val)?;
-                            }
-                        }
-                        Arc::new(builder.finish())
-                    }
-                    DataType::Int64 => {
-                        // 删除指定行的有符号 64 位整数列
-                        let array = col.as_any().downcast_ref::<Int64Array>().unwrap();
-                        let mut builder = Int64Builder::new(array.len() - row_indices_to_delete.len());
-                        for (i, val) in array.iter().enumerate() {
-                            if !row_indices_to_delete.contains(&i) {
-                                builder.append_option(val)?;
-                            }
-                        }
-                        Arc::new(builder.finish())
-                    }
-                    DataType::Float64 => {
-                        // 删除指定行的浮动 64 位列
-                        let array = col.as_any().downcast_ref::<Float64Array>().unwrap();
-                        let mut builder = Float64Builder::new(array.len() - row_indices_to_delete.len());
-                        for (i, val) in array.iter().enumerate() {
-                            if !row_indices_to_delete.contains(&i) {
-                                builder.append_option(val)?;
-                            }
-                        }
-                        Arc::new(builder.finish())
-                    }
-                    DataType::Utf8 => {
-                        // 删除指定行的字符串列
-                        let array = col.as_any().downcast_ref::<StringArray>().unwrap();
-                        let mut builder = StringBuilder::new(array.len() - row_indices_to_delete.len());
-                        for (i, val) in array.iter().enumerate() {
-                            if !row_indices_to_delete.contains(&i) {
-                                builder.append_option(val)?;
-                            }
-                        }
-                        Arc::new(builder.finish())
+        if files.is_empty() {
+            return Err(ErrorCode::LogicalError(format!(
+                "no csv files found in directory `{}`",
+                dir
+            )));
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    // 将 RecordBatch 写入 CSV 文件（包含表头），需要时字段会按 CSV 规则加引号和转义
+    pub fn write(filename: &str, batches: &[RecordBatch]) -> Result<()> {
+        let file = File::create(env::current_dir()?.join(Path::new(filename)))?;
+        let mut writer = csv::Writer::new(file);
+        for batch in batches {
+            writer.write(batch)?;
+        }
+        Ok(())
+    }
+
+    // 去掉字符串列中每个值首尾的空白字符 空值保持不变
+    fn trim_strings(batch: &RecordBatch) -> Result<RecordBatch> {
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|column| match column.as_any().downcast_ref::<StringArray>() {
+                Some(array) => {
+                    let trimmed: StringArray = array.iter().map(|v| v.map(str::trim)).collect();
+                    Arc::new(trimmed) as ArrayRef
+                }
+                None => column.clone(),
+            })
+            .collect();
+        Ok(RecordBatch::try_new(batch.schema(), columns)?)
+    }
+
+    // 检查声明为 NOT NULL 的列中没有空值 csv中的空字符串也视为空值
+    // 行号从1开始 不计表头
+    // 只用于显式指定的schema：推断出的schema只根据前几行判断是否可为空
+    pub fn check_not_null(batches: &[RecordBatch]) -> Result<()> {
+        let mut offset = 0;
+        for batch in batches {
+            for (field, column) in batch.schema().fields().iter().zip(batch.columns()) {
+                if field.is_nullable() {
+                    continue;
+                }
+                let empty_strings = column.as_any().downcast_ref::<StringArray>();
+                for row in 0..batch.num_rows() {
+                    let is_empty = column.is_null(row)
+                        || matches!(empty_strings, Some(array) if array.value(row).is_empty());
+                    if is_empty {
+                        return Err(ErrorCode::LogicalError(format!(
+                            "column `{}` is declared NOT NULL but row {} is null",
+                            field.name(),
+                            offset + row + 1
+                        )));
                     }
-                    _ => unimplemented!(),
-                };
-                columns.push(column);
+                }
             }
-            // 更新 RecordBatch，去除已删除的行
-            *batch = RecordBatch::try_new(Arc::new(schema.clone().into()), columns)?;
+            offset += batch.num_rows();
         }
-        Ok(batches)
-        // // 需要删除的表的名称
-        // let table_name = table.schema().fields[0].qualifier().unwrap().clone();
-        // println!("table_name: {}", table_name);
-        // // 返回删除后的表
-        // Ok(Arc::new(CsvTable {schema, batches }))
+        Ok(())
+    }
+
+    // 数据整体替换后重新检查 UNIQUE 约束
+    fn replace_batches(&self, batches: Vec<RecordBatch>) -> Result<TableRef> {
+        let unique = self
+            .unique
+            .iter()
+            .map(|index| index.rebuild(&self.schema, &batches))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(Self {
+            batches,
+            unique,
+            ..self.clone()
+        }))
     }
-    
 
-    
-    fn infer_schema_from_csv(filename: &str, csv_config: &CsvConfig) -> Result<Schema> {
+    pub fn infer_schema_from_csv(filename: &str, csv_config: &CsvConfig) -> Result<Schema> {
         // 1. 打开文件，读取第一行数据，获取原始schema
         // 2. 使用 Arrow 提供的工具函数 infer_reader_schema，分析 CSV 文件的前几行数据来确定模式。
         let mut file = File::open(env::current_dir()?.join(Path::new(filename)))?;
@@ -179,16 +258,117 @@ impl CsvTable {
     }
 }
 
+// 打印表名和文件路径来区分不同的表 不打印全部数据
+impl fmt::Debug for CsvTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CsvTable")
+            .field("table_name", &self.table_name)
+            .field("path", &self.path)
+            .field("rows", &self.batches.iter().map(|batch| batch.num_rows()).sum::<usize>())
+            .finish()
+    }
+}
+
 impl TableSource for CsvTable {
     fn schema(&self) -> &NaiveSchema {
         &self.schema
     }
     // 实现其对应的扫描操作
-    fn scan(&self, _projection: Option<Vec<usize>>) -> 
+    fn scan(&self, projection: Option<Vec<usize>>) -> 
         Result<Vec<RecordBatch>> {
-        Ok(self.batches.clone())
+        project_batches(&self.schema, &self.batches, projection)
     }
+
+    fn scan_stream(
+        &self,
+        projection: Option<Vec<usize>>,
+    ) -> Result<Box<dyn Iterator<Item = Result<RecordBatch>> + '_>> {
+        project_batches_stream(&self.schema, &self.batches, projection)
+    }
+    // 注册时的表名 出错时可以区分是哪一张csv表
     fn source_name(&self) -> String {
-        "CsvTable".into()
+        self.table_name.clone()
+    }
+
+    // 修改操作不会改动原表 而是生成一个新的表 之前拿到的快照仍然可以读取旧数据
+    fn insert(&self, batches: Vec<RecordBatch>) -> Result<TableRef> {
+        let mut unique = self.unique.clone();
+        for index in unique.iter_mut() {
+            index.insert(&self.schema, &batches)?;
+        }
+        let mut new_batches = self.batches.clone();
+        new_batches.extend(batches);
+        Ok(Arc::new(Self {
+            batches: new_batches,
+            unique,
+            ..self.clone()
+        }))
+    }
+
+    fn delete(&self, row_indices: Vec<usize>) -> Result<TableRef> {
+        self.replace_batches(delete_rows(self.batches.clone(), &row_indices)?)
+    }
+
+    fn add_unique_index(&self, columns: Vec<usize>) -> Result<TableRef> {
+        let mut unique = self.unique.clone();
+        unique.push(UniqueIndex::try_create(columns, &self.schema, &self.batches)?);
+        Ok(Arc::new(Self {
+            unique,
+            ..self.clone()
+        }))
+    }
+
+    // 按照注册时的文件路径和配置重新读取文件 之前对内存中数据的修改会被丢弃
+    // 推断出的schema通过 infer_schema 重新获取 文件被修改后会得到新的schema
+    fn reload(&self, infer_schema: &mut dyn FnMut(&str, &CsvConfig) -> Result<Schema>) -> Result<TableRef> {
+        let is_dir = env::current_dir()?.join(Path::new(&self.path)).is_dir();
+        let file_schema = if self.declared_schema || is_dir {
+            self.file_schema.clone()
+        } else {
+            infer_schema(&self.path, &self.config)?
+        };
+        let batches = if is_dir {
+            Self::read_dir(&self.path, &self.config, &file_schema)?
+        } else {
+            Self::read_file(&self.path, &self.config, &file_schema)?
+        };
+        let source: TableRef = Arc::new(Self {
+            declared_schema: self.declared_schema,
+            ..Self::from_batches(&self.table_name, &self.path, self.config.clone(), file_schema, batches)
+        });
+        Self::check_not_null(&source.scan(None)?)?;
+        // 重新加载的数据同样要满足已有的 UNIQUE 约束
+        let mut source = source;
+        for index in &self.unique {
+            source = source.add_unique_index(index.columns().to_vec())?;
+        }
+        Ok(source)
+    }
+
+    fn update(&self, batches: Vec<RecordBatch>) -> Result<TableRef> {
+        self.replace_batches(batches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::DataType;
+
+    #[test]
+    fn scan_returns_only_the_projected_columns() -> Result<()> {
+        let table = CsvTable::try_create("employee", "data/employee.csv", CsvConfig::default())?;
+        let batches = table.scan(Some(vec![3, 1]))?;
+        let schema = batches[0].schema();
+        let names = schema.fields().iter().map(|field| field.name().as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["employee.rank", "employee.name"]);
+        assert_eq!(batches[0].num_columns(), 2);
+        assert_eq!(batches[0].column(1).data_type(), &DataType::Utf8);
+
+        // 不投影时返回所有列
+        assert_eq!(table.scan(None)?[0].num_columns(), 4);
+        let err = table.scan(Some(vec![0, 4])).unwrap_err();
+        assert!(matches!(err, ErrorCode::PlanError(_)), "{:?}", err);
+        Ok(())
     }
 }
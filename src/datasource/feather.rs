@@ -0,0 +1,103 @@
+use std::env;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::logical_plan::schema::NaiveSchema;
+
+use arrow::datatypes::Schema;
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+
+use super::{delete_rows, project_batches, project_batches_stream, TableSource};
+use crate::datasource::TableRef;
+
+/// 从 Arrow IPC（Feather v2）文件读取的表
+#[derive(Debug, Clone)]
+pub struct FeatherTable {
+    pub schema: NaiveSchema,     // 表的模式
+    pub batches: Vec<RecordBatch>,   // 数据
+    table_name: String,
+}
+
+impl FeatherTable {
+    pub fn try_create(table_name: &str, filename: &str) -> Result<TableRef> {
+        // 1. 打开 IPC 文件，文件的 footer 中已经包含了 schema，无需推断
+        let file = File::open(env::current_dir()?.join(Path::new(filename)))?;
+        let reader = FileReader::try_new(file, None)?;
+        let schema = NaiveSchema::from_qualified(table_name, reader.schema().as_ref());
+
+        // 2. 逐批读取数据
+        let mut batches = vec![];
+        for batch in reader {
+            batches.push(batch?);
+        }
+        Ok(Arc::new(Self {
+            schema,
+            batches,
+            table_name: table_name.to_string(),
+        }))
+    }
+
+    // 将 RecordBatch 写入 Arrow IPC 文件
+    pub fn write(filename: &str, schema: &Schema, batches: &[RecordBatch]) -> Result<()> {
+        let file = File::create(env::current_dir()?.join(Path::new(filename)))?;
+        let mut writer = FileWriter::try_new(file, schema)?;
+        for batch in batches {
+            writer.write(batch)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+impl TableSource for FeatherTable {
+    fn schema(&self) -> &NaiveSchema {
+        &self.schema
+    }
+
+    fn scan(&self, projection: Option<Vec<usize>>) -> Result<Vec<RecordBatch>> {
+        project_batches(&self.schema, &self.batches, projection)
+    }
+
+    fn scan_stream(
+        &self,
+        projection: Option<Vec<usize>>,
+    ) -> Result<Box<dyn Iterator<Item = Result<RecordBatch>> + '_>> {
+        project_batches_stream(&self.schema, &self.batches, projection)
+    }
+
+    // 注册时的表名 出错时可以区分是哪一张表
+    fn source_name(&self) -> String {
+        self.table_name.clone()
+    }
+
+    // 修改只作用于内存中的数据 不会写回文件
+    fn insert(&self, batches: Vec<RecordBatch>) -> Result<TableRef> {
+        let mut new_batches = self.batches.clone();
+        new_batches.extend(batches);
+        Ok(Arc::new(Self {
+            schema: self.schema.clone(),
+            batches: new_batches,
+            table_name: self.table_name.clone(),
+        }))
+    }
+
+    fn delete(&self, row_indices: Vec<usize>) -> Result<TableRef> {
+        Ok(Arc::new(Self {
+            schema: self.schema.clone(),
+            batches: delete_rows(self.batches.clone(), &row_indices)?,
+            table_name: self.table_name.clone(),
+        }))
+    }
+
+    fn update(&self, batches: Vec<RecordBatch>) -> Result<TableRef> {
+        Ok(Arc::new(Self {
+            schema: self.schema.clone(),
+            batches,
+            table_name: self.table_name.clone(),
+        }))
+    }
+}
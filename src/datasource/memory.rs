@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::logical_plan::schema::NaiveSchema;
+
+use arrow::record_batch::RecordBatch;
+
+use super::{delete_rows, project_batches, project_batches_stream, Statistics, TableSource, UniqueIndex};
+use crate::datasource::TableRef;
+
+/// 数据只保存在内存中的表，CREATE TABLE 创建的表就是内存表
+#[derive(Debug, Clone)]
+pub struct MemTable {
+    pub schema: NaiveSchema,
+    pub batches: Vec<RecordBatch>,
+    unique: Vec<UniqueIndex>,
+}
+
+impl MemTable {
+    pub fn try_create(schema: NaiveSchema, batches: Vec<RecordBatch>) -> Result<TableRef> {
+        Ok(Arc::new(Self {
+            schema,
+            batches,
+            unique: vec![],
+        }))
+    }
+
+    // 数据整体替换后重新检查 UNIQUE 约束
+    fn replace_batches(&self, batches: Vec<RecordBatch>) -> Result<TableRef> {
+        let unique = self
+            .unique
+            .iter()
+            .map(|index| index.rebuild(&self.schema, &batches))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(Self {
+            schema: self.schema.clone(),
+            batches,
+            unique,
+        }))
+    }
+}
+
+impl TableSource for MemTable {
+    fn schema(&self) -> &NaiveSchema {
+        &self.schema
+    }
+
+    fn scan(&self, projection: Option<Vec<usize>>) -> Result<Vec<RecordBatch>> {
+        project_batches(&self.schema, &self.batches, projection)
+    }
+
+    fn scan_stream(
+        &self,
+        projection: Option<Vec<usize>>,
+    ) -> Result<Box<dyn Iterator<Item = Result<RecordBatch>> + '_>> {
+        project_batches_stream(&self.schema, &self.batches, projection)
+    }
+
+    fn source_name(&self) -> String {
+        "MemTable".into()
+    }
+
+    fn insert(&self, batches: Vec<RecordBatch>) -> Result<TableRef> {
+        let mut unique = self.unique.clone();
+        for index in unique.iter_mut() {
+            index.insert(&self.schema, &batches)?;
+        }
+        let mut new_batches = self.batches.clone();
+        new_batches.extend(batches);
+        Ok(Arc::new(Self {
+            schema: self.schema.clone(),
+            batches: new_batches,
+            unique,
+        }))
+    }
+
+    fn delete(&self, row_indices: Vec<usize>) -> Result<TableRef> {
+        self.replace_batches(delete_rows(self.batches.clone(), &row_indices)?)
+    }
+
+    fn add_unique_index(&self, columns: Vec<usize>) -> Result<TableRef> {
+        let mut unique = self.unique.clone();
+        unique.push(UniqueIndex::try_create(columns, &self.schema, &self.batches)?);
+        Ok(Arc::new(Self {
+            unique,
+            ..self.clone()
+        }))
+    }
+
+    fn update(&self, batches: Vec<RecordBatch>) -> Result<TableRef> {
+        self.replace_batches(batches)
+    }
+
+    // 数据都在内存中 arrow 数组自带空值的个数 不需要遍历数据
+    fn statistics(&self) -> Option<Statistics> {
+        Some(Statistics::from_batches(self.schema.fields().len(), &self.batches))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    use super::*;
+
+    #[test]
+    fn statistics_count_the_nulls_of_every_batch() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("note", DataType::Utf8, true),
+        ]));
+        let batch = |ids: Vec<Option<i64>>, notes: Vec<Option<&str>>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from(ids)) as ArrayRef,
+                    Arc::new(StringArray::from(notes)) as ArrayRef,
+                ],
+            )
+        };
+        let batches = vec![
+            batch(vec![Some(1), None], vec![None, None])?,
+            batch(vec![Some(3)], vec![Some("x")])?,
+        ];
+        let table = MemTable::try_create(NaiveSchema::from_qualified("t", &schema), batches)?;
+        let expected = Statistics {
+            num_rows: 3,
+            null_counts: vec![1, 2],
+        };
+        assert_eq!(table.statistics(), Some(expected));
+        Ok(())
+    }
+}
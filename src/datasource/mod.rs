@@ -1,24 +1,228 @@
 mod csv; 
+mod feather;
+mod memory;
+mod one_row;
+mod unique;
 
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::sync::Arc;
 
-use crate::error::Result;
+use crate::error::{ErrorCode, Result};
 use crate::logical_plan::schema::NaiveSchema;
+use arrow::array::BooleanArray;
+use arrow::compute::filter_record_batch;
+use arrow::datatypes::{Schema, SchemaRef};
 use arrow::record_batch::RecordBatch;
 
 // 类型别名，表示一个Arc（原子引用计数智能指针）持有的 TableSource trait 对象。
 // 动态大小，可以指向任何实现了TableSource trait的对象，CsvTable、MemTable 或 EmptyTable 等
 pub type TableRef = Arc<dyn TableSource>;  
 
-pub trait TableSource: Debug {     // 类似于一个接口，定义了一组方法的签名，但是不包含具体的实现。
+pub trait TableSource: Debug + Send + Sync {     // 类似于一个接口，定义了一组方法的签名，但是不包含具体的实现。
     fn schema(&self) -> &NaiveSchema;
 
     /// for scan
     fn scan(&self, projection: Option<Vec<usize>>) -> Result<Vec<RecordBatch>>;
 
+    /// 逐个 batch 扫描 只在取到某个 batch 时才对它做投影，调用方不再取数据时剩下的 batch 不会被处理
+    /// 默认先扫描整张表
+    fn scan_stream(
+        &self,
+        projection: Option<Vec<usize>>,
+    ) -> Result<Box<dyn Iterator<Item = Result<RecordBatch>> + '_>> {
+        Ok(Box::new(self.scan(projection)?.into_iter().map(Ok)))
+    }
+
     fn source_name(&self) -> String;
+
+    /// 追加新的行，返回插入后的新表，默认不支持写入
+    fn insert(&self, _batches: Vec<RecordBatch>) -> Result<TableRef> {
+        Err(ErrorCode::NotSupported(format!("{} does not support INSERT", self.source_name())))
+    }
+
+    /// 删除指定的行（按整张表的行号），返回删除后的新表
+    fn delete(&self, _row_indices: Vec<usize>) -> Result<TableRef> {
+        Err(ErrorCode::NotSupported(format!("{} does not support DELETE", self.source_name())))
+    }
+
+    /// 从数据源重新读取数据，返回重新加载后的新表
+    /// 需要重新推断 csv 文件的 schema 时调用 infer_schema（由 catalog 提供 带有缓存）
+    fn reload(&self, _infer_schema: &mut dyn FnMut(&str, &CsvConfig) -> Result<Schema>) -> Result<TableRef> {
+        Err(ErrorCode::NotSupported(format!("{} does not support RELOAD", self.source_name())))
+    }
+
+    /// 为指定的列（按列序号）加上 UNIQUE 约束，返回新表，已有的数据中有重复时报错
+    fn add_unique_index(&self, _columns: Vec<usize>) -> Result<TableRef> {
+        Err(ErrorCode::NotSupported(format!("{} does not support UNIQUE constraints", self.source_name())))
+    }
+
+    /// 用新的数据替换表的全部内容（UPDATE、DELETE 执行后的结果），返回新表
+    fn update(&self, _batches: Vec<RecordBatch>) -> Result<TableRef> {
+        Err(ErrorCode::NotSupported(format!("{} does not support UPDATE", self.source_name())))
+    }
+
+    /// 不读取数据就能得到的统计信息 优化器在规划时使用，默认没有统计信息
+    fn statistics(&self) -> Option<Statistics> {
+        None
+    }
+}
+
+/// 表的统计信息：总行数以及每一列的空值个数，优化器据此判断 IS [NOT] NULL 过滤条件是否多余。
+/// 每个 batch 的空值个数由 arrow 数组自己记录，执行过滤时按 batch 使用；列的最小值和最大值没有用到，不做统计
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statistics {
+    pub num_rows: usize,
+    pub null_counts: Vec<usize>,
+}
+
+impl Statistics {
+    pub fn from_batches(num_columns: usize, batches: &[RecordBatch]) -> Self {
+        let mut null_counts = vec![0; num_columns];
+        for batch in batches {
+            for (count, column) in null_counts.iter_mut().zip(batch.columns()) {
+                *count += column.null_count();
+            }
+        }
+        Self {
+            num_rows: batches.iter().map(|batch| batch.num_rows()).sum(),
+            null_counts,
+        }
+    }
 }
 
 pub use csv::CsvConfig;      // 将子模块的特定项公开到父模块的外部。
-pub use csv::CsvTable;
\ No newline at end of file
+pub use csv::CsvTable;
+pub use feather::FeatherTable;
+pub use memory::MemTable;
+pub use one_row::OneRowTable;
+pub(crate) use unique::UniqueIndex;
+
+// 只保留 projection 中的列 输出的列名带表名限定 projection 为 None 时返回全部数据
+pub(crate) fn project_batches(
+    schema: &NaiveSchema,
+    batches: &[RecordBatch],
+    projection: Option<Vec<usize>>,
+) -> Result<Vec<RecordBatch>> {
+    project_batches_stream(schema, batches, projection)?.collect()
+}
+
+// 与 project_batches 相同 但是每个 batch 在被取到时才做投影
+pub(crate) fn project_batches_stream<'a>(
+    schema: &NaiveSchema,
+    batches: &'a [RecordBatch],
+    projection: Option<Vec<usize>>,
+) -> Result<Box<dyn Iterator<Item = Result<RecordBatch>> + 'a>> {
+    let indices = match projection {
+        Some(indices) => indices,
+        None => return Ok(Box::new(batches.iter().cloned().map(Ok))),
+    };
+    let schema = SchemaRef::from(schema.project(&indices)?);
+    Ok(Box::new(batches.iter().map(move |batch| {
+        let columns = indices.iter().map(|i| batch.column(*i).clone()).collect();
+        Ok(RecordBatch::try_new(schema.clone(), columns)?)
+    })))
+}
+
+// 删除指定位置的行 供各个可写的数据源共用
+// row_indices_to_delete 是整张表中的行号，第 i 个 batch 的第 j 行在整张表中的行号为之前所有 batch 的行数加 j
+pub(crate) fn delete_rows(batches: Vec<RecordBatch>, row_indices_to_delete: &[usize]) -> Result<Vec<RecordBatch>> {
+    let to_delete: HashSet<usize> = row_indices_to_delete.iter().copied().collect();
+    let mut result = Vec::with_capacity(batches.len());
+    let mut offset = 0;
+    // 遍历每个 RecordBatch 进行删除
+    for batch in batches {
+        let keep = (offset..offset + batch.num_rows())
+            .map(|i| Some(!to_delete.contains(&i)))
+            .collect::<BooleanArray>();
+        offset += batch.num_rows();
+        // 去除已删除的行 沿用原batch的schema 保持表的结构不变
+        result.push(filter_record_batch(&batch, &keep)?);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int64Array};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+
+    use super::{MemTable, TableRef, TableSource};
+    use crate::logical_plan::schema::NaiveSchema;
+    use crate::Result;
+
+    /// 记录扫描次数和被取出的 batch 个数的表 t(id) 用来确认哪些操作读取了表的数据
+    #[derive(Debug)]
+    pub struct CountingTable {
+        inner: TableRef,
+        scans: AtomicUsize,
+        batches_pulled: AtomicUsize,
+    }
+
+    impl CountingTable {
+        /// 共 num_batches 个 batch 每个 batch 有 rows_per_batch 行 id 从 1 开始递增
+        pub fn create(num_batches: usize, rows_per_batch: usize) -> Result<Arc<Self>> {
+            let schema = Schema::new(vec![Field::new("id", DataType::Int64, false)]);
+            let batches = (0..num_batches)
+                .map(|i| {
+                    let ids = (0..rows_per_batch).map(|j| (i * rows_per_batch + j + 1) as i64);
+                    let column = Arc::new(Int64Array::from_iter_values(ids)) as ArrayRef;
+                    Ok(RecordBatch::try_new(Arc::new(schema.clone()), vec![column])?)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Arc::new(Self {
+                inner: MemTable::try_create(NaiveSchema::from_qualified("t", &schema), batches)?,
+                scans: AtomicUsize::new(0),
+                batches_pulled: AtomicUsize::new(0),
+            }))
+        }
+
+        /// scan 和 scan_stream 被调用的次数
+        pub fn scans(&self) -> usize {
+            self.scans.load(Ordering::SeqCst)
+        }
+
+        /// 已经从表中取出的 batch 个数
+        pub fn batches_pulled(&self) -> usize {
+            self.batches_pulled.load(Ordering::SeqCst)
+        }
+    }
+
+    impl TableSource for CountingTable {
+        fn schema(&self) -> &NaiveSchema {
+            self.inner.schema()
+        }
+
+        fn scan(&self, projection: Option<Vec<usize>>) -> Result<Vec<RecordBatch>> {
+            self.scan_stream(projection)?.collect()
+        }
+
+        fn scan_stream(
+            &self,
+            projection: Option<Vec<usize>>,
+        ) -> Result<Box<dyn Iterator<Item = Result<RecordBatch>> + '_>> {
+            self.scans.fetch_add(1, Ordering::SeqCst);
+            let batches = self.inner.scan_stream(projection)?;
+            Ok(Box::new(batches.inspect(move |_| {
+                self.batches_pulled.fetch_add(1, Ordering::SeqCst);
+            })))
+        }
+
+        // 更新、删除交给内部的表 返回的新表不再计数
+        fn update(&self, batches: Vec<RecordBatch>) -> Result<TableRef> {
+            self.inner.update(batches)
+        }
+
+        fn delete(&self, row_indices: Vec<usize>) -> Result<TableRef> {
+            self.inner.delete(row_indices)
+        }
+
+        fn source_name(&self) -> String {
+            "CountingTable".to_string()
+        }
+    }
+}
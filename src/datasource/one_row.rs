@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::logical_plan::schema::NaiveSchema;
+
+use arrow::datatypes::Schema;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+
+use super::TableSource;
+use crate::datasource::TableRef;
+
+/// 只有一行、没有列的表，作为没有 FROM 的 SELECT 的输入，投影中的表达式只计算一次
+#[derive(Debug)]
+pub struct OneRowTable {
+    schema: NaiveSchema,
+}
+
+impl OneRowTable {
+    pub fn create() -> TableRef {
+        Arc::new(Self {
+            schema: NaiveSchema::new(vec![]),
+        })
+    }
+}
+
+impl TableSource for OneRowTable {
+    fn schema(&self) -> &NaiveSchema {
+        &self.schema
+    }
+
+    // 没有列可以投影 直接返回只有一行的空 RecordBatch
+    fn scan(&self, _projection: Option<Vec<usize>>) -> Result<Vec<RecordBatch>> {
+        let mut options = RecordBatchOptions::default();
+        options.row_count = Some(1);
+        let batch = RecordBatch::try_new_with_options(Arc::new(Schema::empty()), vec![], &options)?;
+        Ok(vec![batch])
+    }
+
+    fn source_name(&self) -> String {
+        "OneRowTable".into()
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::HashSet;
+
+use arrow::array::Array;
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::array_value_to_string;
+
+use crate::error::{ErrorCode, Result};
+use crate::logical_plan::schema::NaiveSchema;
+
+/// UNIQUE 约束：记录表中已有的键（一列或多列的值），插入重复的键时报错 错误信息中的列名带上表名
+/// 含有 NULL 的键不参与比较，可以出现多次
+#[derive(Debug, Clone)]
+pub(crate) struct UniqueIndex {
+    columns: Vec<usize>,
+    keys: HashSet<Vec<String>>,
+}
+
+impl UniqueIndex {
+    /// 根据表中已有的数据建立约束 已有的数据中有重复时报错
+    pub fn try_create(columns: Vec<usize>, schema: &NaiveSchema, batches: &[RecordBatch]) -> Result<Self> {
+        let mut index = Self {
+            columns,
+            keys: HashSet::new(),
+        };
+        index.insert(schema, batches)?;
+        Ok(index)
+    }
+
+    /// 加入新的行 与已有的行或者新行之间出现重复时报错
+    pub fn insert(&mut self, schema: &NaiveSchema, batches: &[RecordBatch]) -> Result<()> {
+        for batch in batches {
+            'rows: for row in 0..batch.num_rows() {
+                let mut key = Vec::with_capacity(self.columns.len());
+                for col in &self.columns {
+                    let array = batch.column(*col);
+                    if array.is_null(row) {
+                        continue 'rows;
+                    }
+                    key.push(array_value_to_string(array, row)?);
+                }
+                if self.keys.contains(&key) {
+                    let columns = self
+                        .columns
+                        .iter()
+                        .map(|col| schema.field(*col).qualified_name())
+                        .collect::<Vec<_>>();
+                    return Err(ErrorCode::LogicalError(format!(
+                        "unique constraint violation: duplicate value ({}) for column ({})",
+                        key.join(", "),
+                        columns.join(", ")
+                    )));
+                }
+                self.keys.insert(key);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn columns(&self) -> &[usize] {
+        &self.columns
+    }
+
+    /// 表的数据整体发生变化（UPDATE、DELETE）后重新建立约束
+    pub fn rebuild(&self, schema: &NaiveSchema, batches: &[RecordBatch]) -> Result<Self> {
+        Self::try_create(self.columns.clone(), schema, batches)
+    }
+}
@@ -1,9 +1,69 @@
+use std::sync::Arc;
+
 use arrow::{
-    array::{Array, ArrayRef},
+    array::{Array, ArrayRef, DecimalBuilder},
+    compute::cast,
     datatypes::DataType,
 };
 
+use crate::error::ErrorCode;
 use crate::logical_plan::expression::ScalarValue;
+use crate::logical_plan::schema::NaiveField;
+use crate::Result;
+
+/// 指定类型的全空数组。arrow 的 `new_null_array` 不支持 Decimal（会 panic），Decimal 使用 DecimalBuilder 构造
+pub fn new_null_array(data_type: &DataType, len: usize) -> Result<ArrayRef> {
+    match data_type {
+        DataType::Decimal(precision, scale) => {
+            let mut builder = DecimalBuilder::new(len, *precision, *scale);
+            for _ in 0..len {
+                builder.append_null()?;
+            }
+            Ok(Arc::new(builder.finish()))
+        }
+        _ => Ok(arrow::array::new_null_array(data_type, len)),
+    }
+}
+
+/// INSERT 和 UPDATE 的类型规则：整数可以存入任意数值列，浮点数只能存入浮点数和小数列（不会截断小数部分），
+/// 其他类型必须与列的类型相同
+pub fn can_store(from: &DataType, to: &DataType) -> bool {
+    let is_float = |t: &DataType| matches!(t, DataType::Float32 | DataType::Float64);
+    let to_decimal = matches!(to, DataType::Decimal(_, _));
+    if from == to {
+        true
+    } else if is_float(from) {
+        is_float(to) || to_decimal
+    } else {
+        DataType::is_numeric(from) && (DataType::is_numeric(to) || to_decimal)
+    }
+}
+
+/// 按 `can_store` 的规则将一个值转换为列的类型
+pub fn value_to_column_data(value: ArrayRef, field: &NaiveField) -> Result<ArrayRef> {
+    let from = value.data_type().clone();
+    let to = field.data_type();
+    if &from == to {
+        return Ok(value);
+    }
+    let mismatch = || {
+        ErrorCode::LogicalError(format!(
+            "cannot store a {} value in column `{}` of type {}",
+            from,
+            field.name(),
+            to
+        ))
+    };
+    if !can_store(&from, to) {
+        return Err(mismatch());
+    }
+    // 超出列类型范围的值在转换后变为空值
+    let converted = cast(&value, to)?;
+    if converted.null_count() > value.null_count() {
+        return Err(mismatch());
+    }
+    Ok(converted)
+}
 
 #[derive(Debug, Clone)]
 pub enum ColumnValue {
@@ -27,4 +87,12 @@ impl ColumnValue {
             ColumnValue::Const(scalar, num_rows) => scalar.into_array(num_rows),
         }
     }
+
+    /// 转换为指定类型的数组，NULL 常量会生成对应类型的全空数组
+    pub fn into_array_of_type(self, data_type: &DataType) -> Result<ArrayRef> {
+        match self {
+            ColumnValue::Const(ScalarValue::Null, num_rows) => new_null_array(data_type, num_rows),
+            _ => Ok(self.into_array()),
+        }
+    }
 }
@@ -1,51 +1,99 @@
+use arrow::array::{StringArray, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use arrow::record_batch::RecordBatch;
 
-use crate::catalog::Catalog;
+use crate::catalog::{Catalog, View};
 use crate::datasource::CsvConfig;
 use crate::error::Result;
+use crate::logical_plan::bind::{bind_placeholders, placeholder_count};
+use crate::logical_plan::expression::ScalarValue;
+use crate::logical_plan::schema::NaiveSchema;
 
 use crate::optimizer::Optimizer;
+use crate::physical_plan::{DeletePlan, PhysicalPlanRef};
 use crate::planner::QueryPlanner;
-use crate::sql::parser::SQLParser;
+use crate::sql::parser::{DistinctOnKeys, SQLParser};
 use crate::sql::planner::SQLPlanner;
+use std::fs;
 use std::sync::Arc;
-use sqlparser::ast::{ObjectName, Statement};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use sqlparser::ast::{Expr, Ident, ObjectName, ObjectType, OrderByExpr, Statement};
 use crate::datasource::CsvTable;
+use crate::datasource::FeatherTable;
+use crate::datasource::MemTable;
+use crate::error::ErrorCode;
+use crate::utils::batches_to_strings;
 
 #[derive(Default, Debug)]    // 自动生成一个默认实现，当调用 NaiveDB::default() 时，会创建一个默认的 NaiveDB 实例，其中 catalog 也会使用其默认值。
 pub struct SimpleDB {   // 表示数据库的目录，用于存储表的元信息（如表名、字段、存储位置等）。Catalog 是一个数据结构，具体实现可能包含各种管理表和模式的功能。
     pub catalog: Catalog,
+    // 执行算子使用的线程池 为 None 时使用 rayon 默认的全局线程池（线程数等于 CPU 核数）
+    pool: Option<Arc<ThreadPool>>,
+    // 分组聚合最多允许的分组数 为 0 时使用默认值
+    max_groups: usize,
+    // 两个整数之间的 `/` 是否使用整数除法 默认为 false，即结果为 Float64（7 / 2 = 3.5）
+    integer_division: bool,
 }
 
 impl SimpleDB {
-    // 执行一个sql语句 返回结果/错误 这里来回移动所有权 会造成错误
+    // 执行一个sql语句 返回结果/错误 不支持或错误的输入返回 ErrorCode 而不是 panic
     pub fn run_sql(&mut self, sql: &str) -> Result<Vec<RecordBatch>> {
+        // RELOAD TABLE 不是标准 sql 解析器无法识别 在解析前单独处理
+        if let Some(table) = parse_reload_table(sql) {
+            self.reload_csv_table(&table)?;
+            return Ok(vec![]);
+        }
+        // EXPLAIN (FORMAT JSON) 同样不被解析器支持 去掉前缀后解析剩下的语句
+        if let Some(sql) = parse_explain_json(sql) {
+            let (statement, distinct_on) = SQLParser::parse(sql)?;
+            return self.explain_json(statement, distinct_on);
+        }
         // 1. sql -> statement
-        let statement1 = SQLParser::parse(sql)?;   // ? 操作符会在解析失败时提前返回错误，表示遇到没定义的语句。
+        let (statement, distinct_on) = SQLParser::parse(sql)?;   // ? 操作符会在解析失败时提前返回错误，表示遇到没定义的语句。
+        // EXPLAIN 只生成并返回优化后的逻辑计划 不执行语句
+        if let Statement::Explain { statement, .. } = statement {
+            return self.explain(*statement, distinct_on);
+        }
+        if let Statement::CreateIndex { table_name, columns, unique, .. } = statement {
+            return self.create_index(table_name, columns, unique);
+        }
+        // 视图只修改 catalog 不需要执行
+        if let Statement::CreateView { or_replace, materialized, name, columns, query, .. } = statement {
+            let view = View { query: *query, distinct_on };
+            return self.create_view(name, columns, view, or_replace, materialized);
+        }
+        if let Statement::Drop { object_type: ObjectType::View, if_exists, names, .. } = statement {
+            return self.drop_view(names, if_exists);
+        }
         // 2. statement -> logical plan
-        let sql_planner = SQLPlanner::new(&self.catalog); // 创建一个SQL查询计划，使用数据库的catalog来检查表和列的元数据。
-        let logical_plan = sql_planner.statement_to_plan(statement1)?;  // ? 表示statement无法解析成计划，在执行update的时候出现这个问题，因为没定义
+        let sql_planner = self.sql_planner(&self.catalog).with_distinct_on(distinct_on); // 创建一个SQL查询计划，使用数据库的catalog来检查表和列的元数据。
+        // 规划会消耗语句 之后还要根据语句的类型更新catalog 所以规划一份拷贝
+        let logical_plan = sql_planner.statement_to_plan(statement.clone())?;  // ? 表示statement无法解析成计划，在执行update的时候出现这个问题，因为没定义
         // println!("{:?}", logical_plan);    // 打印出逻辑计划
         // 3. optimize
         let optimizer = Optimizer::default();
         let logical_plan = optimizer.optimize(logical_plan); 
+        // DELETE 由数据源删除行 直接安装删除后的新表
+        if let Statement::Delete { table_name, .. } = statement {
+            let delete_plan = self.query_planner().create_delete_plan(&logical_plan)?;
+            return self.delete(table_name, delete_plan);
+        }
         // 4. logical plan -> physical plan
-        let physical_plan = QueryPlanner::create_physical_plan(&logical_plan)?;
-        // 5. execute
-        let new_table = physical_plan.execute();
+        let physical_plan = self.query_planner().create_physical_plan(&logical_plan)?;
+        // 5. execute 只执行一次 更新表和返回结果都使用这一次的结果
+        let batches = self.execute_plan(&physical_plan)?;
 
         // 对于除了select以外的操作，涉及到表的修改，需要进行额外的处理
-        let statement2 = SQLParser::parse(sql)?;
-        let new_table2 = physical_plan.execute();
-        match statement2 {      // match匹配语句
+        match statement {      // match匹配语句
             Statement::Query(_query) => {      // 明确的匹配模式
             }
-            Statement::CreateTable{or_replace:_,temporary:_, external:_, if_not_exists:_, name,columns:_,constraints:_, hive_distribution:_, hive_formats:_, table_properties:_, with_options:_, file_format:_, location:_, query:_, without_rowid:_, like:_} => {
+            Statement::CreateTable{or_replace:_,temporary:_, external:_, if_not_exists:_, name,columns,constraints, hive_distribution:_, hive_formats:_, table_properties:_, with_options:_, file_format:_, location:_, query:_, without_rowid:_, like:_} => {
                 let table_name = self.name_convert(name);
                 let schema = physical_plan.schema();
-                let batches = Vec::<RecordBatch>::new();
-                let table_csv = CsvTable{schema: schema.clone(), batches};
-                let source = Arc::new(table_csv);
+                let mut source = MemTable::try_create(schema.clone(), vec![])?;
+                for unique in SQLPlanner::unique_constraints(&columns, &constraints) {
+                    source = source.add_unique_index(column_indices(schema, &unique)?)?;
+                }
                 let _ = self.catalog.add_new_table(table_name, source);
             }
             Statement::Drop{object_type:_, if_exists:_, names, cascade:_, purge:_} => {   
@@ -58,46 +106,171 @@ impl SimpleDB {
                 let old_table = self.name_convert(table_name);
                 let table_ref = self.catalog.get_table(old_table.as_str())?;
 
-                let schema = table_ref.schema();
-                let batches = new_table?;
-                let table_csv = CsvTable{schema: schema.clone(), batches};
-                let source = Arc::new(table_csv);
-                // self.catalog.                   
+                let source = table_ref.update(batches.clone())?;
                 self.catalog.remove_table(&old_table);
                 let _ = self.catalog.add_new_table(old_table, source);
-                
             }
             Statement::Insert{or:_, table_name, columns:_, overwrite:_, source:_, partitioned:_, after_columns:_, table:_} => {
                 let old_table = self.name_convert(table_name);
                 let table_ref = self.catalog.get_table(old_table.as_str())?;
 
-                let schema = table_ref.schema();
-                let batches = new_table?;
-                let table_csv = CsvTable{schema: schema.clone(), batches};
-                let source = Arc::new(table_csv);
-                // self.catalog.                   
+                // 新插入的行交给数据源追加 生成新的表
+                let source = table_ref.insert(batches.clone())?;
                 self.catalog.remove_table(&old_table);
                 let _ = self.catalog.add_new_table(old_table, source);
             }
-            Statement::Delete{table_name, selection: _} => {
-                let old_table = self.name_convert(table_name);
-                let table_ref = self.catalog.get_table(old_table.as_str())?;
+            // 通配符匹配模式，捕获所有不属于上述的statement值 SQLPlanner 已经对这些语句返回了错误
+            other => {
+                return Err(ErrorCode::NotSupported(format!(
+                    "statement is not supported: {}",
+                    other
+                )))
+            }
+        }
+        Ok(batches)     // 最后的返回值 对于select一类的操作是有意义的
+    }
 
-                let schema = table_ref.schema();
-                let batches = new_table?;
-                let table_csv = CsvTable{schema: schema.clone(), batches};
-                let source = Arc::new(table_csv);
-                // self.catalog.                   
-                self.catalog.remove_table(&old_table);
-                let _ = self.catalog.add_new_table(old_table, source);
+    // 执行 DELETE 用删除后的新表替换原来的表 返回被删除的行数
+    fn delete(&mut self, table_name: ObjectName, delete_plan: Arc<DeletePlan>) -> Result<Vec<RecordBatch>> {
+        let (source, deleted) = match &self.pool {
+            Some(pool) => pool.install(|| delete_plan.delete())?,
+            None => delete_plan.delete()?,
+        };
+        let table_name = self.name_convert(table_name);
+        self.catalog.remove_table(&table_name);
+        let _ = self.catalog.add_new_table(table_name, source);
+        Ok(vec![rows_affected_batch("deleted", deleted)?])
+    }
+
+    // CREATE UNIQUE INDEX 为表加上 UNIQUE 约束 之后插入重复的值会报错
+    fn create_index(
+        &mut self,
+        table_name: ObjectName,
+        columns: Vec<OrderByExpr>,
+        unique: bool,
+    ) -> Result<Vec<RecordBatch>> {
+        if !unique {
+            return Err(ErrorCode::NotSupported(
+                "only UNIQUE indexes are supported".to_string(),
+            ));
+        }
+        let table_name = self.name_convert(table_name);
+        let schema = self.catalog.get_table_schema(&table_name)?;
+        let mut names = vec![];
+        for column in columns {
+            match column.expr {
+                Expr::Identifier(ident) => names.push(ident.value),
+                other => {
+                    return Err(ErrorCode::NotSupported(format!(
+                        "index on expression is not supported: {}",
+                        other
+                    )))
+                }
+            }
+        }
+        let indices = column_indices(&schema, &names)?;
+        let source = self.catalog.get_table(&table_name)?.add_unique_index(indices)?;
+        self.catalog.add_new_table(table_name, source)?;
+        Ok(vec![])
+    }
+
+    // CREATE VIEW 只保存定义视图的查询，引用视图时再展开为它的逻辑计划
+    fn create_view(
+        &mut self,
+        name: ObjectName,
+        columns: Vec<Ident>,
+        view: View,
+        or_replace: bool,
+        materialized: bool,
+    ) -> Result<Vec<RecordBatch>> {
+        if materialized {
+            return Err(ErrorCode::NotSupported(
+                "materialized views are not supported".to_string(),
+            ));
+        }
+        if !columns.is_empty() {
+            return Err(ErrorCode::NotSupported(
+                "column list in CREATE VIEW is not supported".to_string(),
+            ));
+        }
+        let view_name = self.name_convert(name);
+        if self.catalog.get_table_schema(&view_name).is_ok() {
+            return Err(ErrorCode::PlanError(format!(
+                "table `{}` already exists",
+                view_name
+            )));
+        }
+        if !or_replace && self.catalog.get_view(&view_name).is_some() {
+            return Err(ErrorCode::PlanError(format!(
+                "view `{}` already exists",
+                view_name
+            )));
+        }
+        // 先加入 catalog 再规划一次，检查定义是否有效（例如引用了不存在的列或者引用了自身），无效时恢复原来的视图
+        let previous = self.catalog.add_view(view_name.clone(), view.clone());
+        let checked = self
+            .sql_planner(&self.catalog)
+            .with_distinct_on(view.distinct_on)
+            .statement_to_plan(Statement::Query(Box::new(view.query)));
+        if let Err(e) = checked {
+            match previous {
+                Some(previous) => self.catalog.add_view(view_name, previous),
+                None => self.catalog.remove_view(&view_name),
+            };
+            return Err(e);
+        }
+        Ok(vec![])
+    }
+
+    fn drop_view(&mut self, names: Vec<ObjectName>, if_exists: bool) -> Result<Vec<RecordBatch>> {
+        for name in names {
+            let view_name = self.name_convert(name);
+            if self.catalog.remove_view(&view_name).is_none() && !if_exists {
+                return Err(ErrorCode::PlanError(format!(
+                    "view `{}` does not exist",
+                    view_name
+                )));
             }
+        }
+        Ok(vec![])
+    }
+
+    // 以文本形式返回语句优化后的逻辑计划 每行输出一行计划
+    fn explain(&self, statement: Statement, distinct_on: DistinctOnKeys) -> Result<Vec<RecordBatch>> {
+        let logical_plan = self
+            .sql_planner(&self.catalog)
+            .with_distinct_on(distinct_on)
+            .statement_to_plan(statement)?;
+        let logical_plan = Optimizer::default().optimize(logical_plan);
+        let text = format!("{}", logical_plan);
+        plan_batch(text.lines().collect())
+    }
 
-            _ => unimplemented!(),    // 通配符匹配模式，最初用来捕获所有不属于 Statement::Query的statement值 即如果不是Select语句调用这个位置
+    // 以 JSON 形式返回语句优化后的逻辑计划 只输出一行
+    fn explain_json(&self, statement: Statement, distinct_on: DistinctOnKeys) -> Result<Vec<RecordBatch>> {
+        let logical_plan = self
+            .sql_planner(&self.catalog)
+            .with_distinct_on(distinct_on)
+            .statement_to_plan(statement)?;
+        let logical_plan = Optimizer::default().optimize(logical_plan);
+        plan_batch(vec![logical_plan.to_json().as_str()])
+    }
+
+    // 依次执行 .sql 脚本中的每条语句 遇到错误时停止并报告语句所在的行号
+    pub fn execute_file(&mut self, path: &str) -> Result<()> {
+        let script = fs::read_to_string(path)?;
+        for (line, sql) in SQLParser::split_statements(&script) {
+            self.run_sql(&sql).map_err(|e| {
+                ErrorCode::LogicalError(format!("error at line {}: {:?}", line, e))
+            })?;
         }
-        // 作为返回值 所以需要重新再生成一个
-        
-        
-        new_table2     // 最后的返回值 对于select一类的操作是有意义的
+        Ok(())
+    }
+
+    // 执行sql并将结果的每个单元格转换为字符串 便于脚本和测试中使用
+    pub fn query(&mut self, sql: &str) -> Result<Vec<Vec<String>>> {
+        let batches = self.run_sql(sql)?;
+        batches_to_strings(&batches)
     }
 
     pub fn name_convert(&mut self, table_name: ObjectName) -> String {
@@ -120,6 +293,188 @@ impl SimpleDB {
             csv_file, csv_conf)
     }
 
+    // 将目录下所有的CSV文件注册为一张表 例如按分区存放的数据 各文件的schema必须相同
+    pub fn create_csv_table_from_dir(
+        &mut self,
+        table: &str,
+        dir: &str,
+        csv_conf: CsvConfig,
+    ) -> Result<()> {
+        self.catalog.add_csv_table_from_dir(table, dir, csv_conf)
+    }
+
+    // 使用显式指定的schema注册CSV表 不进行类型推断 不可为空的列在加载时会检查空值
+    pub fn create_csv_table_with_schema(
+        &mut self,
+        table: &str,
+        csv_file: &str,
+        schema: Schema,
+        csv_conf: CsvConfig,
+    ) -> Result<()> {
+        self.catalog
+            .add_csv_table_with_schema(table, csv_file, schema, csv_conf)
+    }
+
+    // 按注册时的路径和配置重新读取CSV文件 用于文件在外部被修改之后
+    pub fn reload_csv_table(&mut self, table: &str) -> Result<()> {
+        self.catalog.reload_table(table)
+    }
+
+    // 将 Arrow IPC（Feather）文件注册为数据库中的表
+    pub fn create_feather_table(&mut self, table: &str, feather_file: &str) -> Result<()> {
+        self.catalog.add_feather_table(table, feather_file)
+    }
+
+    // 执行查询并将结果写入 Arrow IPC（Feather）文件 只支持查询语句
+    pub fn export_query_to_feather(&self, sql: &str, path: &str) -> Result<()> {
+        let (schema, batches) = self.query_for_export(sql)?;
+        FeatherTable::write(path, &schema, &batches)
+    }
+
+    // 执行查询并将结果写入 CSV 文件 包含表头 含有分隔符、引号或换行的字段会被加上引号
+    pub fn export_query_to_csv(&self, sql: &str, path: &str) -> Result<()> {
+        let (_, batches) = self.query_for_export(sql)?;
+        CsvTable::write(path, &batches)
+    }
+
+    // 执行用于导出的查询 返回不带表名限定的schema以及统一使用该schema的结果
+    fn query_for_export(&self, sql: &str) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+        let (physical_plan, batches) = self.execute_query(&self.catalog, sql)?;
+
+        // 导出的列名不带表名限定 便于其他工具读取 列类型以实际数据为准
+        let fields = physical_plan
+            .schema()
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let data_type = batches
+                    .first()
+                    .map(|batch| batch.column(i).data_type().clone())
+                    .unwrap_or_else(|| field.data_type().clone());
+                Field::new(field.name(), data_type, true)
+            })
+            .collect();
+        let schema = Arc::new(Schema::new(fields));
+        let batches = batches
+            .iter()
+            .map(|batch| RecordBatch::try_new(schema.clone(), batch.columns().to_vec()))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok((schema, batches))
+    }
+
+    // 记录当前所有表的快照 之后对数据库的修改不会影响在快照上执行的查询
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            catalog: self.catalog.snapshot(),
+        }
+    }
+
+    // 在快照上执行查询 快照是只读的 只支持查询语句
+    pub fn run_sql_on_snapshot(
+        &self,
+        snapshot: &Snapshot,
+        sql: &str,
+    ) -> Result<Vec<RecordBatch>> {
+        let (_, batches) = self.execute_query(&snapshot.catalog, sql)?;
+        Ok(batches)
+    }
+
+    // 只生成查询的逻辑计划 返回查询结果的 schema，不执行查询也不读取表中的数据
+    pub fn query_schema(&self, sql: &str) -> Result<NaiveSchema> {
+        let (statement, distinct_on) = SQLParser::parse(sql)?;
+        if !matches!(statement, Statement::Query(_)) {
+            return Err(ErrorCode::NotSupported(
+                "only queries are supported here".to_string(),
+            ));
+        }
+        let plan = self
+            .sql_planner(&self.catalog)
+            .with_distinct_on(distinct_on)
+            .statement_to_plan(statement)?;
+        Ok(plan.schema().clone())
+    }
+
+    // 解析带参数占位符（`?` 或 `$1`）的查询 之后可以用不同的参数多次执行
+    // 只支持查询语句 占位符只能出现在 WHERE 中；prepare 时规划一次用于检查语句和统计占位符的个数
+    pub fn prepare(&self, sql: &str) -> Result<PreparedStatement> {
+        let (statement, distinct_on) = SQLParser::parse_with_placeholders(sql)?;
+        if !matches!(statement, Statement::Query(_)) {
+            return Err(ErrorCode::NotSupported(
+                "only queries can be prepared".to_string(),
+            ));
+        }
+        let plan = self
+            .sql_planner(&self.catalog)
+            .with_distinct_on(distinct_on.clone())
+            .statement_to_plan(statement.clone())?;
+        let param_count = placeholder_count(&plan)?;
+        Ok(PreparedStatement {
+            statement,
+            distinct_on,
+            param_count,
+        })
+    }
+
+    // 设置并行算子使用的线程数 n 为 0 时恢复为默认的线程池
+    // 并行的算子：投影和过滤按 batch 并行，分组聚合按分组并行，没有 GROUP BY 的聚合按聚合函数并行；
+    // 其他算子（连接、排序等）仍在调用线程中顺序执行
+    pub fn set_num_threads(&mut self, n: usize) -> Result<()> {
+        if n == 0 {
+            self.pool = None;
+            return Ok(());
+        }
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(n)
+            .build()
+            .map_err(|e| ErrorCode::LogicalError(format!("failed to build thread pool: {}", e)))?;
+        self.pool = Some(Arc::new(pool));
+        Ok(())
+    }
+
+    // 设置分组聚合最多允许的分组数 超过时查询返回错误 n 为 0 时恢复为默认值
+    pub fn set_max_groups(&mut self, n: usize) {
+        self.max_groups = n;
+    }
+
+    // 设置两个整数之间的 `/` 的语义：true 时为整数除法，结果截断为整数（7 / 2 = 3）；
+    // false（默认）时为浮点数除法，结果为 Float64（7 / 2 = 3.5）。只要有一侧是浮点数，结果总是 Float64
+    pub fn set_integer_division(&mut self, enabled: bool) {
+        self.integer_division = enabled;
+    }
+
+    fn sql_planner<'a>(&self, catalog: &'a Catalog) -> SQLPlanner<'a> {
+        SQLPlanner::new(catalog).with_integer_division(self.integer_division)
+    }
+
+    fn query_planner(&self) -> QueryPlanner {
+        query_planner(self.max_groups)
+    }
+
+    fn execute_plan(&self, physical_plan: &PhysicalPlanRef) -> Result<Vec<RecordBatch>> {
+        execute_in_pool(&self.pool, physical_plan)
+    }
+
+    // 在给定的catalog上规划并执行一条查询语句 不修改任何表
+    fn execute_query(
+        &self,
+        catalog: &Catalog,
+        sql: &str,
+    ) -> Result<(PhysicalPlanRef, Vec<RecordBatch>)> {
+        let (statement, distinct_on) = SQLParser::parse(sql)?;
+        if !matches!(statement, Statement::Query(_)) {
+            return Err(ErrorCode::NotSupported(
+                "only queries are supported here".to_string(),
+            ));
+        }
+        let sql_planner = self.sql_planner(catalog).with_distinct_on(distinct_on);
+        let logical_plan = sql_planner.statement_to_plan(statement)?;
+        let logical_plan = Optimizer::default().optimize(logical_plan);
+        let physical_plan = self.query_planner().create_physical_plan(&logical_plan)?;
+        let batches = self.execute_plan(&physical_plan)?;
+        Ok((physical_plan, batches))
+    }
+
     // 实现修改指定的CSV表 传入的参数是
     // pub fn update_csv_table
     // 插入一个新的元组到CSV表中
@@ -127,3 +482,150 @@ impl SimpleDB {
     // pub fn delete_csv_table
 
 }
+
+/// 数据库在某一时刻的只读快照，由 `SimpleDB::snapshot` 创建
+#[derive(Debug)]
+pub struct Snapshot {
+    catalog: Catalog,
+}
+
+/// 预处理语句，由 `SimpleDB::prepare` 创建，执行时按顺序把参数绑定到 `$1`、`$2`……
+///
+/// 不借用 `SimpleDB`，执行时传入数据库：每次执行都在数据库当前的 catalog 上重新规划，
+/// 所以总能看到之后的修改（插入、删除、重新加载等），分组数上限和线程池也使用执行时的设置
+#[derive(Debug)]
+pub struct PreparedStatement {
+    statement: Statement,
+    distinct_on: DistinctOnKeys,
+    param_count: usize,
+}
+
+impl PreparedStatement {
+    pub fn param_count(&self) -> usize {
+        self.param_count
+    }
+
+    // 在 db 当前的表上规划 绑定参数后再优化和生成物理计划 参数个数必须与占位符的个数相同
+    pub fn execute(&self, db: &SimpleDB, params: &[ScalarValue]) -> Result<Vec<RecordBatch>> {
+        if params.len() != self.param_count {
+            return Err(ErrorCode::PlanError(format!(
+                "expected {} parameters, got {}",
+                self.param_count,
+                params.len()
+            )));
+        }
+        let plan = db
+            .sql_planner(&db.catalog)
+            .with_distinct_on(self.distinct_on.clone())
+            .statement_to_plan(self.statement.clone())?;
+        let logical_plan = bind_placeholders(&plan, params)?;
+        let logical_plan = Optimizer::default().optimize(logical_plan);
+        let physical_plan = db.query_planner().create_physical_plan(&logical_plan)?;
+        db.execute_plan(&physical_plan)
+    }
+}
+
+// max_groups 为 0 时使用默认的分组数上限
+fn query_planner(max_groups: usize) -> QueryPlanner {
+    match max_groups {
+        0 => QueryPlanner::default(),
+        n => QueryPlanner::with_max_groups(n),
+    }
+}
+
+// 在线程池中执行物理计划 算子内部的并行计算都使用这个线程池
+fn execute_in_pool(pool: &Option<Arc<ThreadPool>>, physical_plan: &PhysicalPlanRef) -> Result<Vec<RecordBatch>> {
+    match pool {
+        Some(pool) => pool.install(|| physical_plan.execute()),
+        None => physical_plan.execute(),
+    }
+}
+
+// 把列名转换为表中的列序号
+fn column_indices(schema: &NaiveSchema, names: &[String]) -> Result<Vec<usize>> {
+    names
+        .iter()
+        .map(|name| schema.index_of_column(None, name))
+        .collect()
+}
+
+// 识别 `RELOAD TABLE name` 语句 返回表名
+fn parse_reload_table(sql: &str) -> Option<String> {
+    let words = sql.trim().trim_end_matches(';').split_whitespace().collect::<Vec<_>>();
+    match words.as_slice() {
+        [reload, table, name]
+            if reload.eq_ignore_ascii_case("reload") && table.eq_ignore_ascii_case("table") =>
+        {
+            Some(name.to_string())
+        }
+        _ => None,
+    }
+}
+
+// 识别 `EXPLAIN (FORMAT JSON) <statement>`，返回被解释的语句
+fn parse_explain_json(sql: &str) -> Option<&str> {
+    let sql = sql.trim_start();
+    let (explain, rest) = sql.split_at(sql.find(char::is_whitespace).unwrap_or(sql.len()));
+    if !explain.eq_ignore_ascii_case("explain") {
+        return None;
+    }
+    let rest = rest.trim_start().strip_prefix('(')?;
+    let (options, statement) = rest.split_at(rest.find(')')?);
+    match options.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [format, json] if format.eq_ignore_ascii_case("format") && json.eq_ignore_ascii_case("json") => {
+            Some(&statement[1..])
+        }
+        _ => None,
+    }
+}
+
+// EXPLAIN 的输出 每个元素一行
+fn plan_batch(lines: Vec<&str>) -> Result<Vec<RecordBatch>> {
+    let schema = Schema::new(vec![Field::new("plan", DataType::Utf8, false)]);
+    let array = StringArray::from(lines);
+    Ok(vec![RecordBatch::try_new(Arc::new(schema), vec![Arc::new(array)])?])
+}
+
+// 构造只有一行一列的结果 用于返回受影响的行数
+fn rows_affected_batch(name: &str, n: usize) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![Field::new(name, DataType::UInt64, false)]);
+    let array = UInt64Array::from(vec![n as u64]);
+    Ok(RecordBatch::try_new(Arc::new(schema), vec![Arc::new(array)])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::test_utils::CountingTable;
+
+    #[test]
+    fn update_executes_the_plan_once() -> Result<()> {
+        let mut db = SimpleDB::default();
+        let source = CountingTable::create(2, 3)?;
+        db.catalog.add_new_table("t".to_string(), source.clone())?;
+
+        db.run_sql("UPDATE t SET id = id + 10 WHERE id < 3")?;
+        // 只扫描了一次表 每个被更新的行只加了一次 10
+        assert_eq!(source.scans(), 1);
+        let ids = db.query("SELECT id FROM t ORDER BY id")?;
+        let expected = ["3", "4", "5", "6", "11", "12"];
+        assert_eq!(ids, expected.iter().map(|id| vec![id.to_string()]).collect::<Vec<_>>());
+        Ok(())
+    }
+
+    #[test]
+    fn delete_executes_the_plan_once() -> Result<()> {
+        let mut db = SimpleDB::default();
+        let source = CountingTable::create(2, 3)?;
+        db.catalog.add_new_table("t".to_string(), source.clone())?;
+
+        let deleted = db.query("DELETE FROM t WHERE id % 2 = 0")?;
+        // 只扫描了一次表 删除的行数由删除计划直接给出
+        assert_eq!(source.scans(), 1);
+        assert_eq!(deleted, vec![vec!["3".to_string()]]);
+        let ids = db.query("SELECT id FROM t ORDER BY id")?;
+        let expected = ["1", "3", "5"];
+        assert_eq!(ids, expected.iter().map(|id| vec![id.to_string()]).collect::<Vec<_>>());
+        Ok(())
+    }
+}
@@ -11,6 +11,8 @@ mod sql;
 mod utils;
 
 pub use datasource::CsvConfig;
-pub use db::SimpleDB;
+pub use db::{PreparedStatement, SimpleDB, Snapshot};
 pub use error::Result;
+pub use logical_plan::expression::ScalarValue;
+pub use logical_plan::schema::{NaiveField, NaiveSchema};
 pub use utils::*;
\ No newline at end of file
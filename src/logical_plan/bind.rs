@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use crate::error::{ErrorCode, Result};
+use crate::logical_plan::expression::{
+    BinaryExpr, InSubquery, LogicalExpr, ScalarFunction, ScalarValue,
+};
+use crate::logical_plan::plan::{Filter, LogicalPlan};
+use crate::optimizer::with_new_inputs;
+
+/// 计划中 WHERE 条件用到的参数个数，即占位符的最大编号
+pub fn placeholder_count(plan: &LogicalPlan) -> Result<usize> {
+    let mut count = 0;
+    rewrite_plan(plan, &mut |param| {
+        count = count.max(param);
+        Ok(LogicalExpr::Placeholder(param))
+    })?;
+    Ok(count)
+}
+
+/// 把 WHERE 条件中的占位符 `$n` 替换为常量 params[n - 1]
+pub fn bind_placeholders(plan: &LogicalPlan, params: &[ScalarValue]) -> Result<LogicalPlan> {
+    rewrite_plan(plan, &mut |param| match params.get(param - 1) {
+        Some(value) => Ok(LogicalExpr::Literal(value.clone())),
+        None => Err(ErrorCode::PlanError(format!(
+            "no value bound for parameter ${}",
+            param
+        ))),
+    })
+}
+
+type PlaceholderFn<'a> = dyn FnMut(usize) -> Result<LogicalExpr> + 'a;
+
+// 只改写 Filter 的条件 其余节点保持不变
+fn rewrite_plan(plan: &LogicalPlan, f: &mut PlaceholderFn) -> Result<LogicalPlan> {
+    let mut inputs = vec![];
+    for child in plan.children() {
+        inputs.push(Arc::new(rewrite_plan(&child, f)?));
+    }
+    match with_new_inputs(plan, inputs) {
+        LogicalPlan::Filter(filter) => Ok(LogicalPlan::Filter(Filter {
+            predicate: rewrite_expr(&filter.predicate, f)?,
+            input: filter.input,
+        })),
+        plan => Ok(plan),
+    }
+}
+
+fn rewrite_expr(expr: &LogicalExpr, f: &mut PlaceholderFn) -> Result<LogicalExpr> {
+    let expr = match expr {
+        LogicalExpr::Placeholder(param) => f(*param)?,
+        LogicalExpr::Alias(expr, alias) => {
+            LogicalExpr::Alias(Box::new(rewrite_expr(expr, f)?), alias.clone())
+        }
+        LogicalExpr::BinaryExpr(BinaryExpr { left, op, right }) => {
+            LogicalExpr::BinaryExpr(BinaryExpr {
+                left: Box::new(rewrite_expr(left, f)?),
+                op: op.clone(),
+                right: Box::new(rewrite_expr(right, f)?),
+            })
+        }
+        LogicalExpr::IsNull(expr) => LogicalExpr::IsNull(Box::new(rewrite_expr(expr, f)?)),
+        LogicalExpr::IsNotNull(expr) => LogicalExpr::IsNotNull(Box::new(rewrite_expr(expr, f)?)),
+        LogicalExpr::Not(expr) => LogicalExpr::Not(Box::new(rewrite_expr(expr, f)?)),
+        // 子查询中的 WHERE 条件同样可以使用参数
+        LogicalExpr::InSubquery(in_subquery) => LogicalExpr::InSubquery(InSubquery {
+            expr: Box::new(rewrite_expr(&in_subquery.expr, f)?),
+            subquery: Arc::new(rewrite_plan(&in_subquery.subquery, f)?),
+            negated: in_subquery.negated,
+        }),
+        LogicalExpr::ScalarFunction(func) => LogicalExpr::ScalarFunction(ScalarFunction {
+            fun: func.fun,
+            args: func
+                .args
+                .iter()
+                .map(|arg| rewrite_expr(arg, f))
+                .collect::<Result<Vec<_>>>()?,
+        }),
+        LogicalExpr::Case { when_then, else_expr } => LogicalExpr::Case {
+            when_then: when_then
+                .iter()
+                .map(|(when, then)| Ok((rewrite_expr(when, f)?, rewrite_expr(then, f)?)))
+                .collect::<Result<Vec<_>>>()?,
+            else_expr: match else_expr {
+                Some(expr) => Some(Box::new(rewrite_expr(expr, f)?)),
+                None => None,
+            },
+        },
+        LogicalExpr::Column(_)
+        | LogicalExpr::Literal(_)
+        | LogicalExpr::AggregateFunction(_)
+        | LogicalExpr::Wildcard => expr.clone(),
+    };
+    Ok(expr)
+}
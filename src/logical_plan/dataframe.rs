@@ -1,13 +1,17 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 
-use crate::logical_plan::expression::LogicalExpr;
+use arrow::datatypes::DataType;
+
+use crate::logical_plan::expression::{LogicalExpr, ScalarValue};
 use crate::logical_plan::plan::{Aggregate, Filter, LogicalPlan, Projection, Update, Delete, CreateTable};   // lyx 增加了一个update
-use sqlparser::ast::{Assignment, Ident, SetExpr}; 
+use sqlparser::ast::Ident;
 use super::expression::{AggregateFunction, Column};
-use super::plan::{Insert, Join, JoinType, Limit, Offset};
-use super::schema::NaiveSchema;
+use super::plan::{DistinctOn, Insert, Join, JoinType, Limit, Offset, Sort, SortExpr};
+use super::schema::{NaiveField, NaiveSchema};
 use crate::error::{ErrorCode, Result};
 use crate::datasource::TableRef;
+use crate::datatype::can_store;
 
 #[derive(Clone)]
 pub struct DataFrame {
@@ -27,8 +31,31 @@ impl DataFrame {
     pub fn project(self, exprs: Vec<LogicalExpr>) -> Result<Self> {
         // TODO(veeupup): Ambiguous reference of field
         let mut fields = vec![];
+        // 重复的输出列（例如 SELECT id, id）在列名后加上序号，保证结果的列名唯一
+        let mut seen = HashSet::new();
         for expr in &exprs {
-            fields.push(expr.data_field(&self.plan)?);
+            let field = computed_field(expr, &self.plan)?;
+            let mut unique = field.clone();
+            let mut n = 0;
+            while !seen.insert(unique.qualified_name()) {
+                n += 1;
+                let name = format!("{}_{}", field.name(), n);
+                unique = match field.source_expr() {
+                    Some(source_expr) => NaiveField::new_computed(
+                        &name,
+                        field.data_type().clone(),
+                        field.is_nullable(),
+                        source_expr,
+                    ),
+                    None => NaiveField::new(
+                        field.qualifier().map(|q| q.as_str()),
+                        &name,
+                        field.data_type().clone(),
+                        field.is_nullable(),
+                    ),
+                };
+            }
+            fields.push(unique);
         }
         let schema = NaiveSchema::new(fields);
         Ok(Self {
@@ -40,15 +67,39 @@ impl DataFrame {
         })
     }
 
+    // alias 为当前结果的所有列设置新的限定名（例如 CTE 的名字），通过一个只包含列的投影实现
+    pub fn alias(self, alias: &str) -> Self {
+        let exprs = self
+            .plan
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| LogicalExpr::column(field.qualifier().cloned(), field.name().to_string()))
+            .collect();
+        let mut schema = self.plan.schema().clone();
+        for field in schema.fields.iter_mut() {
+            field.set_qualifier(Some(alias.to_string()));
+        }
+        Self {
+            plan: LogicalPlan::Projection(Projection {
+                input: Arc::new(self.plan),
+                exprs,
+                schema,
+            }),
+        }
+    }
+
     // filter 方法用于进行 过滤 操作，即对数据进行条件筛选。它接受一个 LogicalExpr 表达式，表示过滤条件。
     // 该方法返回一个新的 DataFrame，其中的 plan 被更新为 LogicalPlan::Filter，表示执行过滤操作。
-    pub fn filter(self, expr: LogicalExpr) -> Self {
-        Self {
+    // 过滤条件必须是 Boolean 类型（NULL 常量视为不成立），否则在构建计划时就返回错误
+    pub fn filter(self, expr: LogicalExpr) -> Result<Self> {
+        check_predicate(&expr, &self.plan)?;
+        Ok(Self {
             plan: LogicalPlan::Filter(Filter {
                 input: Arc::new(self.plan),
                 predicate: expr,
             }),
-        }
+        })
     }
 
     pub fn create_table(self, table_name: String, schema: NaiveSchema) -> Result<Self> {
@@ -61,7 +112,22 @@ impl DataFrame {
     }
 
     // update方法执行 更新操作 的一个dataframe
-    pub fn update(self, conditions: LogicalExpr, assignments: Vec<Assignment> ) -> Result<Self> {
+    // 每个赋值的新值可以是任意表达式（例如另一列），类型规则与 INSERT 相同
+    pub fn update(self, conditions: LogicalExpr, assignments: Vec<(String, LogicalExpr)>) -> Result<Self> {
+        check_predicate(&conditions, &self.plan)?;
+        let schema = self.plan.schema();
+        for (column, value) in &assignments {
+            let target = schema.field(schema.index_of_column(None, column)?).data_type().clone();
+            let data_type = value.data_field(&self.plan)?.data_type().clone();
+            let compatible = can_store(&data_type, &target)
+                || matches!(value, LogicalExpr::Literal(ScalarValue::Null));
+            if !compatible {
+                return Err(ErrorCode::PlanError(format!(
+                    "cannot assign a value of type {} to column `{}` of type {}",
+                    data_type, column, target
+                )));
+            }
+        }
         Ok(Self {
             plan: LogicalPlan::Update(Update {
                 input: Arc::new(self.plan),
@@ -71,17 +137,39 @@ impl DataFrame {
         })
     }
     // insert方法执行 插入操作 的一个dataframe
-    pub fn insert(self, columns: Vec<Ident>,source: SetExpr ) -> Result<Self> {
+    // 显式指定的插入列必须都存在且不重复，VALUES 中每一行值的个数要与指定的列数一致
+    pub fn insert(self, columns: Vec<Ident>, values: Vec<Vec<LogicalExpr>>) -> Result<Self> {
+        let schema = self.plan.schema();
+        let mut seen = HashSet::new();
+        for column in &columns {
+            if schema.index_of_column(None, &column.value).is_err() {
+                return Err(ErrorCode::PlanError(format!(
+                    "column `{}` does not exist in the INSERT target table",
+                    column.value
+                )));
+            }
+            if !seen.insert(&column.value) {
+                return Err(ErrorCode::PlanError(format!(
+                    "column `{}` is specified more than once in INSERT",
+                    column.value
+                )));
+            }
+        }
+        // 没有显式指定插入列时按表的全部列插入
+        let expected = if columns.is_empty() { schema.fields().len() } else { columns.len() };
+        let row_lens = values.iter().map(|row| row.len()).collect::<Vec<_>>();
+        check_values_arity(&row_lens, Some(expected))?;
         Ok(Self {
             plan: LogicalPlan::Insert(Insert {
                 input: Arc::new(self.plan),
                 columns,
-                source,
+                values,
             }),
         })
     }   
 
     pub fn delete(self, source: TableRef, conditions: LogicalExpr) -> Result<Self> {
+        check_predicate(&conditions, &self.plan)?;
         Ok(Self {
             plan: LogicalPlan::Delete(Delete {
                 input: Arc::new(self.plan),
@@ -102,25 +190,26 @@ impl DataFrame {
         self,
         group_expr: Vec<LogicalExpr>,
         aggr_expr: Vec<AggregateFunction>,
-    ) -> Self {
+    ) -> Result<Self> {
+        // 分组表达式或聚合函数的参数中引用了不存在的列时报错
         let mut group_fields = group_expr
             .iter()
-            .map(|expr| expr.data_field(&self.plan).unwrap())
-            .collect::<Vec<_>>();
+            .map(|expr| expr.data_field(&self.plan))
+            .collect::<Result<Vec<_>>>()?;
         let mut aggr_fields = aggr_expr
             .iter()
-            .map(|expr| expr.data_field(&self.plan).unwrap())
-            .collect::<Vec<_>>();
+            .map(|expr| expr.data_field(&self.plan))
+            .collect::<Result<Vec<_>>>()?;
         group_fields.append(&mut aggr_fields);
         let schema = NaiveSchema::new(group_fields);
-        Self {
+        Ok(Self {
             plan: LogicalPlan::Aggregate(Aggregate {
                 input: Arc::new(self.plan),
                 group_expr,
                 aggr_expr,
                 schema,
             }),
-        }
+        })
     }
 
     pub fn limit(self, n: usize) -> DataFrame {
@@ -141,6 +230,37 @@ impl DataFrame {
         }
     }
 
+    pub fn sort(self, order_by: Vec<SortExpr>) -> DataFrame {
+        Self {
+            plan: LogicalPlan::Sort(Sort {
+                input: Arc::new(self.plan),
+                order_by,
+            }),
+        }
+    }
+
+    // distinct_on 保留每个去重键的第一行 输入需要事先排好序
+    pub fn distinct_on(self, on_expr: Vec<LogicalExpr>) -> DataFrame {
+        Self {
+            plan: LogicalPlan::DistinctOn(DistinctOn {
+                input: Arc::new(self.plan),
+                on_expr,
+            }),
+        }
+    }
+
+    // distinct 对全部输出列去重
+    pub fn distinct(self) -> DataFrame {
+        let on_expr = self
+            .plan
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| LogicalExpr::column(None, field.name().to_string()))
+            .collect();
+        self.distinct_on(on_expr)
+    }
+
     // join 方法用于执行 连接 操作。它接受三个参数：
     // right：右侧表的 LogicalPlan。
     // join_type：连接类型（如 INNER, LEFT OUTER 等）。
@@ -161,7 +281,7 @@ impl DataFrame {
         }
 
         let (left_keys, right_keys) = join_keys;
-        let on: Vec<(_, _)> = left_keys.into_iter().zip(right_keys.into_iter()).collect();
+        let on: Vec<(_, _)> = left_keys.into_iter().zip(right_keys).collect();
 
         let left_schema = self.plan.schema();
         let join_schema = left_schema.join(right.schema());
@@ -171,6 +291,7 @@ impl DataFrame {
                 left: Arc::new(self.plan.clone()),
                 right: Arc::new(right.clone()),
                 on,
+                filter: None,
                 join_type,
                 schema: join_schema,
             })));
@@ -179,11 +300,33 @@ impl DataFrame {
             left: Arc::new(self.plan.clone()),
             right: Arc::new(right.clone()),
             on,
+            filter: None,
             join_type,
             schema: join_schema,
         })))
     }
 
+    // 连接条件中除了等值连接键还有其他条件（例如 `a.x < b.y`）时，连接键相等且 filter 为 true 的两行才匹配
+    // 外连接中没有匹配的行仍然保留；没有连接键时对两侧的每一对行计算 filter
+    pub fn join_on(
+        &self,
+        right: &LogicalPlan,
+        join_type: JoinType,
+        join_keys: (Vec<Column>, Vec<Column>),
+        filter: LogicalExpr,
+    ) -> Result<DataFrame> {
+        let plan = self.join(right, join_type, join_keys)?.logical_plan();
+        check_predicate(&filter, &plan)?;
+        let join = match plan {
+            LogicalPlan::Join(join) | LogicalPlan::CrossJoin(join) => join,
+            _ => unreachable!(),
+        };
+        Ok(Self::new(LogicalPlan::Join(Join {
+            filter: Some(filter),
+            ..join
+        })))
+    }
+
     // schema 方法返回当前 DataFrame 的数据模式。是一个 NaiveSchema 类型的引用。
     #[allow(unused)]
     pub fn schema(&self) -> &NaiveSchema {
@@ -193,4 +336,84 @@ impl DataFrame {
     pub fn logical_plan(self) -> LogicalPlan {
         self.plan
     }
-}
\ No newline at end of file
+}
+
+// 投影输出的字段 计算列（例如 `a + b AS total`）会记录它来自的表达式 直接引用的列保持原样
+fn computed_field(expr: &LogicalExpr, input: &LogicalPlan) -> Result<NaiveField> {
+    let field = expr.data_field(input)?;
+    let source = match expr {
+        LogicalExpr::Alias(expr, _) => expr.as_ref(),
+        expr => expr,
+    };
+    match source {
+        LogicalExpr::Column(_) => Ok(field),
+        source => Ok(NaiveField::new_computed(
+            field.name(),
+            field.data_type().clone(),
+            field.is_nullable(),
+            source.data_field(input)?.name(),
+        )),
+    }
+}
+
+// INSERT 中 VALUES 的行数和每一行值的个数的唯一检查：至少有一行，每一行不为空并且值的个数等于 expected。
+// 解析时还不知道目标表的列数 expected 为 None，只检查 sqlparser 无法解析的没有行和空行两种情况
+pub(crate) fn check_values_arity(row_lens: &[usize], expected: Option<usize>) -> Result<()> {
+    if row_lens.is_empty() {
+        return Err(ErrorCode::PlanError(
+            "INSERT requires at least one VALUES row".to_string(),
+        ));
+    }
+    for (i, len) in row_lens.iter().enumerate() {
+        let mismatch = match expected {
+            Some(expected) => *len != expected,
+            None => *len == 0,
+        };
+        if mismatch {
+            return Err(ErrorCode::PlanError(match expected {
+                Some(expected) => format!("INSERT row {} has {} values, expected {}", i + 1, len, expected),
+                None => format!("INSERT row {} has no values", i + 1),
+            }));
+        }
+    }
+    Ok(())
+}
+
+// WHERE、HAVING 等过滤条件的类型必须是 Boolean，没有类型的 NULL 常量也可以作为条件
+fn check_predicate(expr: &LogicalExpr, input: &LogicalPlan) -> Result<()> {
+    let data_type = expr.data_field(input)?.data_type().clone();
+    if data_type != DataType::Boolean && !matches!(expr, LogicalExpr::Literal(ScalarValue::Null)) {
+        return Err(ErrorCode::PlanError(format!(
+            "filter predicate must be a boolean, found {}",
+            data_type
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::expression::{BinaryExpr, Operator};
+    use crate::logical_plan::literal::lit;
+    use crate::optimizer::test_utils::{column, scan};
+
+    #[test]
+    fn explain_shows_the_source_expr_of_computed_fields() -> Result<()> {
+        let next = LogicalExpr::BinaryExpr(BinaryExpr {
+            left: Box::new(column("id")),
+            op: Operator::Plus,
+            right: Box::new(lit(1_i64)),
+        });
+        let plan = DataFrame::new(scan()?)
+            .project(vec![column("id"), LogicalExpr::Alias(Box::new(next), "next".to_string())])?
+            .logical_plan();
+
+        let fields = plan.schema().fields();
+        assert_eq!(fields[0].source_expr(), None);
+        assert_eq!(fields[1].name(), "next");
+        assert_eq!(fields[1].source_expr().map(String::as_str), Some("id + 1"));
+        assert!(format!("{}", plan).contains(r#"source_expr: Some("id + 1")"#));
+        Ok(())
+    }
+}
@@ -1,9 +1,8 @@
-use std::iter::repeat;
-
 use arrow::array::StringArray;
 use arrow::array::{new_null_array, ArrayRef, BooleanArray, Float64Array, Int64Array, UInt64Array};
 
 use arrow::datatypes::DataType;
+use std::fmt;
 use std::sync::Arc;
 
 use crate::error::{ErrorCode, Result};
@@ -24,6 +23,23 @@ pub enum LogicalExpr {
     Literal(ScalarValue),
     // 二元表达式
     BinaryExpr(BinaryExpr),
+    // expr IS NULL
+    IsNull(Box<LogicalExpr>),
+    // expr IS NOT NULL
+    IsNotNull(Box<LogicalExpr>),
+    // NOT expr
+    Not(Box<LogicalExpr>),
+    // expr [NOT] IN (subquery)
+    InSubquery(InSubquery),
+    // 标量函数 对每一行分别计算
+    ScalarFunction(ScalarFunction),
+    // 预处理语句中的参数占位符 从 1 开始编号，执行前被替换为常量
+    Placeholder(usize),
+    // CASE WHEN c1 THEN r1 [WHEN ...] [ELSE r] END，取第一个成立的条件对应的结果，都不成立时取 ELSE 或 NULL
+    Case {
+        when_then: Vec<(LogicalExpr, LogicalExpr)>,
+        else_expr: Option<Box<LogicalExpr>>,
+    },
 
     #[allow(unused)]
     // 聚合函数
@@ -56,6 +72,48 @@ impl LogicalExpr {
             },
             LogicalExpr::Literal(scalar_val) => Ok(scalar_val.data_field()),
             LogicalExpr::BinaryExpr(expr) => expr.data_field(input),
+            LogicalExpr::IsNull(expr) => Ok(NaiveField::new(
+                None,
+                format!("{} is null", expr.data_field(input)?.name()).as_str(),
+                DataType::Boolean,
+                false,
+            )),
+            LogicalExpr::IsNotNull(expr) => Ok(NaiveField::new(
+                None,
+                format!("{} is not null", expr.data_field(input)?.name()).as_str(),
+                DataType::Boolean,
+                false,
+            )),
+            LogicalExpr::Not(expr) => {
+                let field = expr.data_field(input)?;
+                Ok(NaiveField::new(
+                    None,
+                    format!("not {}", field.name()).as_str(),
+                    DataType::Boolean,
+                    field.is_nullable(),
+                ))
+            }
+            LogicalExpr::InSubquery(in_subquery) => {
+                let field = in_subquery.expr.data_field(input)?;
+                let op = if in_subquery.negated { "not in" } else { "in" };
+                Ok(NaiveField::new(
+                    None,
+                    format!("{} {} (subquery)", field.name(), op).as_str(),
+                    DataType::Boolean,
+                    true,
+                ))
+            }
+            LogicalExpr::ScalarFunction(func) => func.data_field(input),
+            // 绑定参数之前不知道类型 与没有类型的 NULL 一样按字符串处理
+            LogicalExpr::Placeholder(param) => Ok(NaiveField::new(
+                None,
+                format!("${}", param).as_str(),
+                DataType::Utf8,
+                true,
+            )),
+            LogicalExpr::Case { when_then, else_expr } => {
+                case_data_field(when_then, else_expr.as_deref(), input)
+            }
             LogicalExpr::AggregateFunction(aggr_func) => aggr_func.data_field(input),
             LogicalExpr::Wildcard => Err(ErrorCode::IntervalError(
                 "Wildcard not supported in logical plan".to_string(),
@@ -68,10 +126,57 @@ impl LogicalExpr {
         binary_expr(self, Operator::And, other)
     }
 
+    // 创建标量函数 支持字符串函数 upper、lower、length、substring、trim、ltrim、rtrim
+    // 以及数学函数 abs、round、ceil、floor、sqrt，函数名不区分大小写
+    pub fn try_create_scalar_func(func_name: &str, exprs: &[LogicalExpr]) -> Result<LogicalExpr> {
+        let fun = match func_name.to_lowercase().as_str() {
+            "upper" => ScalarFunc::Upper,
+            "lower" => ScalarFunc::Lower,
+            "length" => ScalarFunc::Length,
+            "substring" | "substr" => ScalarFunc::Substring,
+            "trim" => ScalarFunc::Trim,
+            "ltrim" => ScalarFunc::LTrim,
+            "rtrim" => ScalarFunc::RTrim,
+            "abs" => ScalarFunc::Abs,
+            "round" => ScalarFunc::Round,
+            "ceil" | "ceiling" => ScalarFunc::Ceil,
+            "floor" => ScalarFunc::Floor,
+            "sqrt" => ScalarFunc::Sqrt,
+            _ => {
+                return Err(ErrorCode::NoMatchFunction(format!(
+                    "Not match scalar func: {}",
+                    func_name
+                )))
+            }
+        };
+        let (min_args, max_args) = fun.arg_count();
+        if exprs.len() < min_args || exprs.len() > max_args {
+            let expected = if min_args == max_args {
+                min_args.to_string()
+            } else {
+                format!("{} to {}", min_args, max_args)
+            };
+            let noun = if max_args == 1 { "argument" } else { "arguments" };
+            return Err(ErrorCode::PlanError(format!(
+                "{} expects {} {}, found {}",
+                fun,
+                expected,
+                noun,
+                exprs.len()
+            )));
+        }
+        Ok(LogicalExpr::ScalarFunction(ScalarFunction {
+            fun,
+            args: exprs.to_vec(),
+        }))
+    }
+
     // 🌟创建聚合函数 支持 count、sum、avg、min、max 
+    // distinct 为 true 时只计算不同的值，目前只有 count 支持
     pub fn try_create_aggregate_func(
         func_name: &str,    
         exprs: &[LogicalExpr],
+        distinct: bool,
     ) -> Result<LogicalExpr> {
         if exprs.len() != 1 {
             return Err(ErrorCode::PlanError(
@@ -82,29 +187,32 @@ impl LogicalExpr {
             "count" => Ok(LogicalExpr::AggregateFunction(AggregateFunction {
                 fun: AggregateFunc::Count,
                 args: Box::new(exprs[0].clone()),
+                distinct,
             })),
             "sum" => Ok(LogicalExpr::AggregateFunction(AggregateFunction {
                 fun: AggregateFunc::Sum,
                 args: Box::new(exprs[0].clone()),
+                distinct,
             })),
             "avg" => Ok(LogicalExpr::AggregateFunction(AggregateFunction {
                 fun: AggregateFunc::Avg,
                 args: Box::new(exprs[0].clone()),
+                distinct,
             })),
             "min" => Ok(LogicalExpr::AggregateFunction(AggregateFunction {
                 fun: AggregateFunc::Min,
                 args: Box::new(exprs[0].clone()),
+                distinct,
             })),
             "max" => Ok(LogicalExpr::AggregateFunction(AggregateFunction {
                 fun: AggregateFunc::Max,
                 args: Box::new(exprs[0].clone()),
+                distinct,
             })),
-            _ => {
-                return Err(ErrorCode::NoMatchFunction(format!(
-                    "Not match aggregate func: {}",
-                    func_name
-                )));
-            }
+            _ => Err(ErrorCode::NoMatchFunction(format!(
+                "Not match aggregate func: {}",
+                func_name
+            ))),
         }
     }
 }
@@ -148,7 +256,8 @@ macro_rules! build_array_from_option {
 impl ScalarValue {
     pub fn data_field(&self) -> NaiveField {
         match self {
-            ScalarValue::Null => NaiveField::new(None, "Null", DataType::Null, true),
+            // 没有类型信息的 NULL 按字符串处理，保证投影出来的列是有类型的
+            ScalarValue::Null => NaiveField::new(None, "Null", DataType::Utf8, true),
             ScalarValue::Boolean(_) => NaiveField::new(None, "bool", DataType::Boolean, true),
             ScalarValue::Float64(_) => NaiveField::new(None, "f64", DataType::Float64, true),
             ScalarValue::Int64(_) => NaiveField::new(None, "i64", DataType::Int64, true),
@@ -165,13 +274,21 @@ impl ScalarValue {
             ScalarValue::Int64(e) => build_array_from_option!(Int64, Int64Array, e, size),
             ScalarValue::UInt64(e) => build_array_from_option!(UInt64, UInt64Array, e, size),
             ScalarValue::Utf8(e) => match e {
-                Some(value) => Arc::new(StringArray::from_iter_values(repeat(value).take(size))),
+                Some(value) => Arc::new(StringArray::from_iter_values(vec![value; size])),
                 None => new_null_array(&DataType::Utf8, size),
             },
         }
     }
 }
 
+// 子查询只能返回一列 子查询的结果中有 NULL 且没有匹配时结果为 NULL
+#[derive(Debug, Clone)]
+pub struct InSubquery {
+    pub expr: Box<LogicalExpr>,
+    pub subquery: Arc<LogicalPlan>,
+    pub negated: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct BinaryExpr {
     pub left: Box<LogicalExpr>,
@@ -180,102 +297,139 @@ pub struct BinaryExpr {
 }
 
 // 二元表达式
+// 二元表达式中操作数的名字 常量直接使用它的值
+fn operand_name(expr: &LogicalExpr, input: &LogicalPlan) -> Result<String> {
+    let name = match expr {
+        LogicalExpr::Literal(scalar_val) => match scalar_val {
+            ScalarValue::Boolean(Some(val)) => val.to_string(),
+            ScalarValue::Int64(Some(val)) => val.to_string(),
+            ScalarValue::UInt64(Some(val)) => val.to_string(),
+            ScalarValue::Float64(Some(val)) => val.to_string(),
+            ScalarValue::Utf8(Some(val)) => val.to_string(),
+            _ => "null".to_string(),
+        },
+        _ => expr.data_field(input)?.name().clone(),
+    };
+    Ok(name)
+}
+
+fn is_null_literal(expr: &LogicalExpr) -> bool {
+    matches!(expr, LogicalExpr::Literal(ScalarValue::Null))
+}
+
+// CASE 各分支结果的公共类型：数值之间有 Float64 时为 Float64，Int64 与 UInt64 混合时为 Int64
+fn case_result_type(left: &DataType, right: &DataType) -> Result<DataType> {
+    let is_numeric = |dt: &DataType| matches!(dt, DataType::Int64 | DataType::UInt64 | DataType::Float64);
+    match (left, right) {
+        _ if left == right => Ok(left.clone()),
+        (DataType::Float64, _) | (_, DataType::Float64) if is_numeric(left) && is_numeric(right) => {
+            Ok(DataType::Float64)
+        }
+        _ if is_numeric(left) && is_numeric(right) => Ok(DataType::Int64),
+        _ => Err(ErrorCode::PlanError(format!(
+            "CASE branches have incompatible types: {} and {}",
+            left, right
+        ))),
+    }
+}
+
+// 字段名为 `case when c then r ... else r end`，条件必须是 Boolean，
+// 各分支的结果统一为同一类型 NULL 常量可以与任何类型统一，全部为 NULL 时按字符串处理
+fn case_data_field(
+    when_then: &[(LogicalExpr, LogicalExpr)],
+    else_expr: Option<&LogicalExpr>,
+    input: &LogicalPlan,
+) -> Result<NaiveField> {
+    let mut name = "case".to_string();
+    for (when, then) in when_then {
+        let when_type = when.data_field(input)?.data_type().clone();
+        if when_type != DataType::Boolean && !is_null_literal(when) {
+            return Err(ErrorCode::PlanError(format!(
+                "CASE WHEN condition must be a boolean, found {}",
+                when_type
+            )));
+        }
+        name.push_str(&format!(
+            " when {} then {}",
+            operand_name(when, input)?,
+            operand_name(then, input)?
+        ));
+    }
+    if let Some(expr) = else_expr {
+        name.push_str(&format!(" else {}", operand_name(expr, input)?));
+    }
+    name.push_str(" end");
+
+    let mut data_type: Option<DataType> = None;
+    let results = when_then.iter().map(|(_, then)| then).chain(else_expr);
+    for result in results.filter(|result| !is_null_literal(result)) {
+        let result_type = result.data_field(input)?.data_type().clone();
+        data_type = Some(match data_type {
+            Some(current) => case_result_type(&current, &result_type)?,
+            None => result_type,
+        });
+    }
+    Ok(NaiveField::new(
+        None,
+        name.as_str(),
+        data_type.unwrap_or(DataType::Utf8),
+        true,
+    ))
+}
+
 impl BinaryExpr {
+    // 算术运算结果的类型 与物理计划中的类型转换保持一致：
+    // 整数常量会先转换为另一侧的类型，之后有 Float64 时结果为 Float64，Int64 与 UInt64 混合时为 Int64
+    fn arithmetic_type(&self, input: &LogicalPlan) -> Result<DataType> {
+        let left = self.left.data_field(input)?.data_type().clone();
+        let right = self.right.data_field(input)?.data_type().clone();
+        let is_unsigned_literal = |expr: &LogicalExpr| {
+            matches!(expr, LogicalExpr::Literal(ScalarValue::Int64(Some(v))) if *v >= 0)
+        };
+        let dt = match (&left, &right) {
+            _ if left == right => left,
+            (DataType::Float64, DataType::Int64 | DataType::UInt64)
+            | (DataType::Int64 | DataType::UInt64, DataType::Float64) => DataType::Float64,
+            (DataType::UInt64, DataType::Int64) if is_unsigned_literal(&self.right) => DataType::UInt64,
+            (DataType::Int64, DataType::UInt64) if is_unsigned_literal(&self.left) => DataType::UInt64,
+            (DataType::Int64, DataType::UInt64) | (DataType::UInt64, DataType::Int64) => DataType::Int64,
+            _ => left,
+        };
+        Ok(dt)
+    }
+
+    // 输出字段名为 `left op right`，比较、逻辑运算和模式匹配的结果为 Boolean，算术运算的结果为数值
     pub fn data_field(&self, input: &LogicalPlan) -> Result<NaiveField> {
-        let left = self.left.data_field(input)?;
-        let left = left.name();
-        let right = match &*self.right {
-            LogicalExpr::Literal(scalar_val) => match scalar_val {
-                ScalarValue::Boolean(Some(val)) => val.to_string(),
-                ScalarValue::Int64(Some(val)) => val.to_string(),
-                ScalarValue::UInt64(Some(val)) => val.to_string(),
-                ScalarValue::Float64(Some(val)) => val.to_string(),
-                ScalarValue::Utf8(Some(val)) => val.to_string(),
-                _ => "null".to_string(),
+        let left = operand_name(&self.left, input)?;
+        let right = operand_name(&self.right, input)?;
+        let data_type = match self.op {
+            Operator::Eq
+            | Operator::NotEq
+            | Operator::Lt
+            | Operator::LtEq
+            | Operator::Gt
+            | Operator::GtEq
+            | Operator::And
+            | Operator::Or
+            | Operator::Like
+            | Operator::NotLike => DataType::Boolean,
+            Operator::Plus
+            | Operator::Minus
+            | Operator::Multiply
+            | Operator::IntegerDivide
+            | Operator::Modulos => self.arithmetic_type(input)?,
+            // 两个整数相除的结果也是 Float64
+            Operator::Divide => match self.arithmetic_type(input)? {
+                DataType::Int64 | DataType::UInt64 => DataType::Float64,
+                other => other,
             },
-            _ => self.right.data_field(input)?.name().clone(),
-        };
-        let field = match self.op {
-            Operator::Eq => NaiveField::new(
-                None,
-                format!("{} = {}", left, right).as_str(),
-                DataType::Boolean,
-                true,
-            ),
-            Operator::NotEq => NaiveField::new(
-                None,
-                format!("{} != {}", left, right).as_str(),
-                DataType::Boolean,
-                true,
-            ),
-            Operator::Lt => NaiveField::new(
-                None,
-                format!("{} < {}", left, right).as_str(),
-                DataType::Boolean,
-                true,
-            ),
-            Operator::LtEq => NaiveField::new(
-                None,
-                format!("{} <= {}", left, right).as_str(),
-                DataType::Boolean,
-                true,
-            ),
-            Operator::Gt => NaiveField::new(
-                None,
-                format!("{} > {}", left, right).as_str(),
-                DataType::Boolean,
-                true,
-            ),
-            Operator::GtEq => NaiveField::new(
-                None,
-                format!("{} >= {}", left, right).as_str(),
-                DataType::Boolean,
-                true,
-            ),
-            Operator::Plus => NaiveField::new(
-                None,
-                format!("{} + {}", left, right).as_str(),
-                self.left.data_field(input)?.data_type().clone(),
-                true,
-            ),
-            Operator::Minus => NaiveField::new(
-                None,
-                format!("{} - {}", left, right).as_str(),
-                self.left.data_field(input)?.data_type().clone(),
-                true,
-            ),
-            Operator::Multiply => NaiveField::new(
-                None,
-                format!("{} * {}", left, right).as_str(),
-                self.left.data_field(input)?.data_type().clone(),
-                true,
-            ),
-            Operator::Divide => NaiveField::new(
-                None,
-                format!("{} / {}", left, right).as_str(),
-                self.left.data_field(input)?.data_type().clone(),
-                true,
-            ),
-            Operator::Modulos => NaiveField::new(
-                None,
-                format!("{} % {}", left, right).as_str(),
-                self.left.data_field(input)?.data_type().clone(),
-                true,
-            ),
-            Operator::And => NaiveField::new(
-                None,
-                format!("{} and {}", left, right).as_str(),
-                DataType::Boolean,
-                true,
-            ),
-            Operator::Or => NaiveField::new(
-                None,
-                format!("{} or {}", left, right).as_str(),
-                DataType::Boolean,
-                true,
-            ),
         };
-        Ok(field)
+        Ok(NaiveField::new(
+            None,
+            format!("{} {} {}", left, self.op, right).as_str(),
+            data_type,
+            true,
+        ))
     }
 }
 
@@ -299,16 +453,62 @@ pub enum Operator {
     Minus,
     /// Multiplication operator, like `*`
     Multiply,
-    /// Division operator, like `/`
+    /// Division operator, like `/`, two integers are divided as Float64
     Divide,
+    /// Integer division, `/` when integer division is enabled, the result of two integers is truncated
+    IntegerDivide,
     /// Remainder operator, like `%`
     Modulos,
     /// Logical AND, like `&&`
     And,
     /// Logical OR, like `||`
     Or,
+    /// Matches a wildcard pattern, like `name LIKE 'A%'`
+    Like,
+    /// Does not match a wildcard pattern, like `name NOT LIKE 'A%'`
+    NotLike,
 }
 
+// 运算符在输出字段名中的写法
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Operator::Eq => "=",
+            Operator::NotEq => "!=",
+            Operator::Lt => "<",
+            Operator::LtEq => "<=",
+            Operator::Gt => ">",
+            Operator::GtEq => ">=",
+            Operator::Plus => "+",
+            Operator::Minus => "-",
+            Operator::Multiply => "*",
+            Operator::Divide | Operator::IntegerDivide => "/",
+            Operator::Modulos => "%",
+            Operator::And => "and",
+            Operator::Or => "or",
+            Operator::Like => "like",
+            Operator::NotLike => "not like",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+
+// 整数列求和的结果仍然是整数
+fn sum_type(data_type: &DataType) -> DataType {
+    match data_type {
+        DataType::Int64 | DataType::UInt64 => data_type.clone(),
+        _ => DataType::Float64,
+    }
+}
+
+// min/max 对字符串按字典序比较，结果仍然是字符串
+fn min_max_type(data_type: &DataType) -> DataType {
+    match data_type {
+        DataType::Utf8 => DataType::Utf8,
+        _ => DataType::Float64,
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct AggregateFunction {
@@ -316,40 +516,54 @@ pub struct AggregateFunction {
     pub fun: AggregateFunc,
     /// List of expressions to feed to the functions as arguments
     pub args: Box<LogicalExpr>,
+    /// Whether only distinct values are aggregated, like `count(DISTINCT a)`
+    pub distinct: bool,
 }
 
 impl AggregateFunction {
     pub fn data_field(&self, input: &LogicalPlan) -> Result<NaiveField> {
-        let dt = self.args.data_field(input)?;
+        let dt = match self.args.as_ref() {
+            // count(*)
+            LogicalExpr::Wildcard => NaiveField::new(None, "*", DataType::Int64, false),
+            args => args.data_field(input)?,
+        };
+        // 结果类型与物理聚合算子的输出保持一致：count 为 Int64 且不会为空，
+        // 整数的 sum 保持原来的类型，字符串的 min/max 为 Utf8，其余为 Float64
         let field = match self.fun {
+            AggregateFunc::Count if self.distinct => NaiveField::new(
+                None,
+                format!("count(distinct {})", dt.name()).as_str(),
+                DataType::Int64,
+                false,
+            ),
             AggregateFunc::Count => NaiveField::new(
                 None,
                 format!("count({})", dt.name()).as_str(),
-                dt.data_type().clone(),
-                true,
+                DataType::Int64,
+                false,
             ),
             AggregateFunc::Sum => NaiveField::new(
                 None,
                 format!("sum({})", dt.name()).as_str(),
-                dt.data_type().clone(),
+                sum_type(dt.data_type()),
                 true,
             ),
             AggregateFunc::Min => NaiveField::new(
                 None,
                 format!("min({})", dt.name()).as_str(),
-                dt.data_type().clone(),
+                min_max_type(dt.data_type()),
                 true,
             ),
             AggregateFunc::Max => NaiveField::new(
                 None,
                 format!("max({})", dt.name()).as_str(),
-                dt.data_type().clone(),
+                min_max_type(dt.data_type()),
                 true,
             ),
             AggregateFunc::Avg => NaiveField::new(
                 None,
                 format!("avg({})", dt.name()).as_str(),
-                dt.data_type().clone(),
+                DataType::Float64,
                 true,
             ),
         };
@@ -357,6 +571,131 @@ impl AggregateFunction {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct ScalarFunction {
+    pub fun: ScalarFunc,
+    pub args: Vec<LogicalExpr>,
+}
+
+impl ScalarFunction {
+    // 字段名为 `fun(arg, ...)`，参数的类型不符合时报错
+    pub fn data_field(&self, input: &LogicalPlan) -> Result<NaiveField> {
+        let mut names = vec![];
+        let mut arg_types = vec![];
+        for arg in &self.args {
+            names.push(operand_name(arg, input)?);
+            // NULL 常量的字段类型是 Utf8，这里按 Null 检查 数学函数也可以接受 NULL
+            if is_null_literal(arg) {
+                arg_types.push(DataType::Null);
+            } else {
+                arg_types.push(arg.data_field(input)?.data_type().clone());
+            }
+        }
+        Ok(NaiveField::new(
+            None,
+            format!("{}({})", self.fun, names.join(", ")).as_str(),
+            self.fun.return_type(&arg_types)?,
+            true,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalarFunc {
+    Upper,
+    Lower,
+    Length,
+    // substring(str, start [, len])，start 从 1 开始
+    Substring,
+    Trim,
+    LTrim,
+    RTrim,
+    Abs,
+    // round(x [, precision])
+    Round,
+    Ceil,
+    Floor,
+    // 负数的平方根为 NULL
+    Sqrt,
+}
+
+impl ScalarFunc {
+    // 参数个数的范围 (最少, 最多)
+    pub fn arg_count(&self) -> (usize, usize) {
+        match self {
+            ScalarFunc::Substring => (2, 3),
+            ScalarFunc::Round => (1, 2),
+            _ => (1, 1),
+        }
+    }
+
+    // 数学函数的第一个参数是数值，其余函数的第一个参数是字符串
+    fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            ScalarFunc::Abs | ScalarFunc::Round | ScalarFunc::Ceil | ScalarFunc::Floor | ScalarFunc::Sqrt
+        )
+    }
+
+    // 检查参数类型并返回结果类型 除第一个参数以外的参数（substring 的位置、round 的精度）都是整数
+    // 参数为 NULL 常量时结果为 NULL
+    pub fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
+        let is_integer = |dt: &DataType| matches!(dt, DataType::Int64 | DataType::UInt64);
+        for (i, arg_type) in arg_types.iter().enumerate() {
+            let valid = match (i, arg_type) {
+                (_, DataType::Null) => true,
+                (0, dt) if self.is_numeric() => is_integer(dt) || dt == &DataType::Float64,
+                (0, dt) => dt == &DataType::Utf8,
+                (_, dt) => is_integer(dt),
+            };
+            if !valid {
+                let expected = match i {
+                    0 if self.is_numeric() => "a number",
+                    0 => "a string",
+                    _ => "an integer",
+                };
+                return Err(ErrorCode::PlanError(format!(
+                    "{} expects {} as argument {}, found {}",
+                    self,
+                    expected,
+                    i + 1,
+                    arg_type
+                )));
+            }
+        }
+        match self {
+            ScalarFunc::Length => Ok(DataType::Int64),
+            // 整数的 abs、ceil、floor 仍然是原来的类型
+            ScalarFunc::Abs | ScalarFunc::Ceil | ScalarFunc::Floor => match &arg_types[0] {
+                dt if is_integer(dt) => Ok(dt.clone()),
+                _ => Ok(DataType::Float64),
+            },
+            ScalarFunc::Round | ScalarFunc::Sqrt => Ok(DataType::Float64),
+            _ => Ok(DataType::Utf8),
+        }
+    }
+}
+
+impl fmt::Display for ScalarFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ScalarFunc::Upper => "upper",
+            ScalarFunc::Lower => "lower",
+            ScalarFunc::Length => "length",
+            ScalarFunc::Substring => "substring",
+            ScalarFunc::Trim => "trim",
+            ScalarFunc::LTrim => "ltrim",
+            ScalarFunc::RTrim => "rtrim",
+            ScalarFunc::Abs => "abs",
+            ScalarFunc::Round => "round",
+            ScalarFunc::Ceil => "ceil",
+            ScalarFunc::Floor => "floor",
+            ScalarFunc::Sqrt => "sqrt",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum AggregateFunc {
     #[allow(unused)]
@@ -370,3 +709,47 @@ pub enum AggregateFunc {
     #[allow(unused)]
     Avg,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::literal::lit;
+    use crate::optimizer::test_utils::{column, scan};
+
+    fn binary(left: LogicalExpr, op: Operator, right: LogicalExpr) -> LogicalExpr {
+        LogicalExpr::BinaryExpr(BinaryExpr {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        })
+    }
+
+    #[test]
+    fn every_operator_has_a_field_name_and_type() -> Result<()> {
+        let plan = scan()?;
+        let cases = vec![
+            (binary(column("id"), Operator::Eq, lit(1_i64)), "id = 1", DataType::Boolean),
+            (binary(column("id"), Operator::NotEq, lit(1_i64)), "id != 1", DataType::Boolean),
+            (binary(column("id"), Operator::Lt, lit(1_i64)), "id < 1", DataType::Boolean),
+            (binary(column("id"), Operator::LtEq, lit(1_i64)), "id <= 1", DataType::Boolean),
+            (binary(column("id"), Operator::Gt, lit(1_i64)), "id > 1", DataType::Boolean),
+            (binary(column("id"), Operator::GtEq, lit(1_i64)), "id >= 1", DataType::Boolean),
+            (binary(column("id"), Operator::Plus, lit(1_i64)), "id + 1", DataType::Int64),
+            (binary(column("id"), Operator::Minus, lit(1.5_f64)), "id - 1.5", DataType::Float64),
+            (binary(column("id"), Operator::Multiply, lit(2_i64)), "id * 2", DataType::Int64),
+            // 两个整数相除的结果为 Float64 整数除法保持整数
+            (binary(column("id"), Operator::Divide, lit(2_i64)), "id / 2", DataType::Float64),
+            (binary(column("id"), Operator::IntegerDivide, lit(2_i64)), "id / 2", DataType::Int64),
+            (binary(column("id"), Operator::Modulos, lit(2_i64)), "id % 2", DataType::Int64),
+            (binary(lit(true), Operator::And, lit(false)), "true and false", DataType::Boolean),
+            (binary(lit(true), Operator::Or, lit(false)), "true or false", DataType::Boolean),
+            (binary(column("name"), Operator::Like, lit("a%")), "name like a%", DataType::Boolean),
+            (binary(column("name"), Operator::NotLike, lit("a%")), "name not like a%", DataType::Boolean),
+        ];
+        for (expr, name, data_type) in cases {
+            let field = expr.data_field(&plan)?;
+            assert_eq!((field.name().as_str(), field.data_type()), (name, &data_type));
+        }
+        Ok(())
+    }
+}
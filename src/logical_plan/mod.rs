@@ -1,7 +1,9 @@
 mod dataframe;
+pub mod bind;
 pub mod expression;
 pub mod literal;
 pub mod plan;
 pub mod schema;
 
 pub use dataframe::DataFrame;
+pub(crate) use dataframe::check_values_arity;
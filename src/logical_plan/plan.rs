@@ -1,7 +1,9 @@
-use sqlparser::ast::{Assignment, SetExpr, Ident};
+use sqlparser::ast::Ident;
 
 use crate::datasource::TableRef;
-use crate::logical_plan::expression::{Column, LogicalExpr};
+use crate::logical_plan::expression::{
+    AggregateFunc, BinaryExpr, Column, InSubquery, LogicalExpr, ScalarFunction, ScalarValue,
+};
 
 use std::fmt::{Debug, Display, Formatter, Result};
 use std::sync::Arc;
@@ -10,6 +12,7 @@ use super::expression::AggregateFunction;
 use super::schema::NaiveSchema;
 
 #[derive(Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum LogicalPlan {
     Projection(Projection),
 
@@ -30,6 +33,12 @@ pub enum LogicalPlan {
     /// Adjusts the starting point at which the rest of the expressions begin to effect.
     Offset(Offset),
 
+    /// Sorts its input according to a list of sort expressions.
+    Sort(Sort),
+
+    /// Keeps the first row of each distinct key, e.g. `SELECT DISTINCT ON (a) ...`
+    DistinctOn(DistinctOn),
+
     /// Produces rows from a table provider by reference or from the context
     TableScan(TableScan),
 
@@ -54,7 +63,9 @@ impl LogicalPlan {
             LogicalPlan::Join(Join { schema, .. }) => schema,
             LogicalPlan::Limit(Limit { input, .. }) => input.schema(),
             LogicalPlan::Offset(Offset { input, .. }) => input.schema(),
-            LogicalPlan::TableScan(TableScan { source, .. }) => source.schema(),
+            LogicalPlan::Sort(Sort { input, .. }) => input.schema(),
+            LogicalPlan::DistinctOn(DistinctOn { input, .. }) => input.schema(),
+            LogicalPlan::TableScan(TableScan { schema, .. }) => schema,
             LogicalPlan::CrossJoin(Join { schema, .. }) => schema,
             LogicalPlan::Update(Update { input, .. }) => input.schema(),
             LogicalPlan::Insert(Insert { input, .. }) => input.schema(),
@@ -73,6 +84,8 @@ impl LogicalPlan {
             LogicalPlan::Join(Join { left, right, .. }) => vec![left.clone(), right.clone()],
             LogicalPlan::Limit(Limit { input, .. }) => vec![input.clone()],
             LogicalPlan::Offset(Offset { input, .. }) => vec![input.clone()],
+            LogicalPlan::Sort(Sort { input, .. }) => vec![input.clone()],
+            LogicalPlan::DistinctOn(DistinctOn { input, .. }) => vec![input.clone()],
             LogicalPlan::TableScan(_) => vec![],
             LogicalPlan::CrossJoin(Join { left, right, .. }) => vec![left.clone(), right.clone()],
             LogicalPlan::Update(Update { input, .. }) => vec![input.clone()],
@@ -120,13 +133,27 @@ pub struct TableScan {
     pub source: TableRef,
     /// Optional column indices to use as a projection 可选的列索引投影
     pub projection: Option<Vec<usize>>,                // Option<T> 是一个枚举，用于表示一个值可能存在或者不存在。它有两个变体：Some(T) 和 None。Some(T) 表示有一个值，而 None 表示没有值。
+    /// The schema after the projection is applied
+    pub schema: NaiveSchema,
+}
+
+impl TableScan {
+    /// 扫描整张表
+    pub fn new(source: TableRef) -> Self {
+        let schema = source.schema().clone();
+        Self {
+            source,
+            projection: None,
+            schema,
+        }
+    }
 }
 
 // lyx 新增 逻辑计划 三个都不需要schema，是因为update、Insert和Delete操作不会改变表的结构，所以不需要schema。
 #[derive(Debug, Clone)]
 pub struct Update {     // 因为在Filter中已经实现了过滤，所以这里就不需要了
     /// The set of expressions to update (column, value)
-    pub assignments: Vec<Assignment>,  // 要更新的列和值
+    pub assignments: Vec<(String, LogicalExpr)>,  // 要更新的列名和新值的表达式 新值按更新之前的数据计算
     /// 前面的计划 即一个扫描的
     pub input: Arc<LogicalPlan>,
     pub conditions: LogicalExpr,
@@ -136,8 +163,8 @@ pub struct Update {     // 因为在Filter中已经实现了过滤，所以这
 #[derive(Debug, Clone)]
 pub struct Insert {
     pub columns: Vec<Ident>,
-    /// The list of expressions representing the values to be inserted
-    pub source: SetExpr,  // Values for the new tuple(s)
+    /// VALUES 中的每一行 每个值都是一个表达式，执行时计算
+    pub values: Vec<Vec<LogicalExpr>>,
     /// 前面的计划
     pub input: Arc<LogicalPlan>,
 }
@@ -189,6 +216,8 @@ pub struct Join {
     pub right: Arc<LogicalPlan>,
     /// Equijoin clause expressed as pairs of (left, right) join columns, cross join don't have on conditions 连接条件
     pub on: Vec<(Column, Column)>,
+    /// 不是等值连接键的连接条件 对两侧的每一对行计算，为 true 时两行匹配
+    pub filter: Option<LogicalExpr>,
     /// Join type
     pub join_type: JoinType,   // 连接类型 内连接，左连接，右连接。。。
     /// The output schema, containing fields from the left and right inputs
@@ -213,6 +242,266 @@ pub struct Offset {
     pub input: Arc<LogicalPlan>,
 }
 
+/// Sorts its input according to a list of sort expressions.
+#[derive(Debug, Clone)]
+pub struct Sort {
+    /// The sort expressions, the first one is the most significant 排序键
+    pub order_by: Vec<SortExpr>,
+    /// The logical plan
+    pub input: Arc<LogicalPlan>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SortExpr {
+    pub expr: LogicalExpr,
+    /// 升序 ORDER BY 默认为升序
+    pub asc: bool,
+    /// 空值排在最前面
+    pub nulls_first: bool,
+}
+
+/// Keeps the first row of each distinct `on_expr` key, the input is expected to be sorted already.
+/// A plain `DISTINCT` is a `DistinctOn` over every output column.
+#[derive(Debug, Clone)]
+pub struct DistinctOn {
+    /// The distinct key expressions 去重的键
+    pub on_expr: Vec<LogicalExpr>,
+    /// The logical plan
+    pub input: Arc<LogicalPlan>,
+}
+
+impl LogicalPlan {
+    /// 以 JSON 对象树的形式输出计划：node 为节点类型，inputs 为子计划，表达式同样输出为 JSON 对象
+    pub fn to_json(&self) -> String {
+        let mut fields = vec![];
+        let node = match self {
+            LogicalPlan::Projection(Projection { exprs, .. }) => {
+                fields.push(("exprs", json_array(exprs.iter().map(json_expr))));
+                "Projection"
+            }
+            LogicalPlan::Filter(Filter { predicate, .. }) => {
+                fields.push(("predicate", json_expr(predicate)));
+                "Filter"
+            }
+            LogicalPlan::Aggregate(Aggregate { group_expr, aggr_expr, .. }) => {
+                fields.push(("group_expr", json_array(group_expr.iter().map(json_expr))));
+                fields.push(("aggr_expr", json_array(aggr_expr.iter().map(json_aggregate))));
+                "Aggregate"
+            }
+            LogicalPlan::Join(Join { on, filter, join_type, .. }) => {
+                fields.push(("join_type", json_debug(join_type)));
+                fields.push((
+                    "on",
+                    json_array(on.iter().map(|(l, r)| json_array([json_column(l), json_column(r)]))),
+                ));
+                let filter = match filter {
+                    Some(filter) => json_expr(filter),
+                    None => "null".to_string(),
+                };
+                fields.push(("filter", filter));
+                "Join"
+            }
+            LogicalPlan::CrossJoin(Join { join_type, .. }) => {
+                fields.push(("join_type", json_debug(join_type)));
+                "CrossJoin"
+            }
+            LogicalPlan::Limit(Limit { n, .. }) => {
+                fields.push(("n", n.to_string()));
+                "Limit"
+            }
+            LogicalPlan::Offset(Offset { n, .. }) => {
+                fields.push(("n", n.to_string()));
+                "Offset"
+            }
+            LogicalPlan::Sort(Sort { order_by, .. }) => {
+                let order_by = order_by.iter().map(|sort| {
+                    format!(
+                        "{{\"expr\":{},\"asc\":{},\"nulls_first\":{}}}",
+                        json_expr(&sort.expr),
+                        sort.asc,
+                        sort.nulls_first
+                    )
+                });
+                fields.push(("order_by", json_array(order_by)));
+                "Sort"
+            }
+            LogicalPlan::DistinctOn(DistinctOn { on_expr, .. }) => {
+                fields.push(("on_expr", json_array(on_expr.iter().map(json_expr))));
+                "DistinctOn"
+            }
+            LogicalPlan::TableScan(TableScan { source, projection, .. }) => {
+                fields.push(("source", json_string(&source.source_name())));
+                let projection = match projection {
+                    Some(indices) => json_array(indices.iter().map(|i| i.to_string())),
+                    None => "null".to_string(),
+                };
+                fields.push(("projection", projection));
+                "TableScan"
+            }
+            LogicalPlan::Update(Update { assignments, conditions, .. }) => {
+                fields.push((
+                    "assignments",
+                    json_array(assignments.iter().map(|(column, value)| json_array([json_string(column), json_expr(value)]))),
+                ));
+                fields.push(("conditions", json_expr(conditions)));
+                "Update"
+            }
+            LogicalPlan::Insert(Insert { columns, values, .. }) => {
+                fields.push(("columns", json_array(columns.iter().map(|c| json_string(&c.value)))));
+                fields.push(("values", json_array(values.iter().map(|row| json_array(row.iter().map(json_expr))))));
+                "Insert"
+            }
+            LogicalPlan::Delete(Delete { source, conditions, .. }) => {
+                fields.push(("source", json_string(&source.source_name())));
+                fields.push(("conditions", json_expr(conditions)));
+                "Delete"
+            }
+            LogicalPlan::CreateTable(CreateTable { table_name, schema }) => {
+                fields.push(("table_name", json_string(table_name)));
+                fields.push((
+                    "columns",
+                    json_array(schema.fields().iter().map(|field| json_string(field.name()))),
+                ));
+                "CreateTable"
+            }
+        };
+        fields.push(("inputs", json_array(self.children().iter().map(|child| child.to_json()))));
+
+        fields.insert(0, ("node", json_string(node)));
+        json_object(fields)
+    }
+}
+
+// 转义后加上引号的 JSON 字符串
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_debug<T: Debug>(value: &T) -> String {
+    json_string(&format!("{:?}", value))
+}
+
+fn json_array(items: impl IntoIterator<Item = String>) -> String {
+    format!("[{}]", items.into_iter().collect::<Vec<_>>().join(","))
+}
+
+fn json_object(fields: Vec<(&str, String)>) -> String {
+    let fields = fields
+        .into_iter()
+        .map(|(key, value)| format!("{}:{}", json_string(key), value))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", fields.join(","))
+}
+
+// 表达式输出为只有一个键的对象 键为表达式的种类，例如 {"column":{"name":"id"}}、{"binary":{"op":">",...}}
+fn json_expr(expr: &LogicalExpr) -> String {
+    let (kind, value) = match expr {
+        LogicalExpr::Alias(expr, name) => (
+            "alias",
+            json_object(vec![("name", json_string(name)), ("expr", json_expr(expr))]),
+        ),
+        LogicalExpr::Column(column) => return json_column(column),
+        LogicalExpr::Literal(value) => ("literal", json_scalar(value)),
+        LogicalExpr::BinaryExpr(BinaryExpr { left, op, right }) => (
+            "binary",
+            json_object(vec![
+                ("op", json_string(&op.to_string())),
+                ("left", json_expr(left)),
+                ("right", json_expr(right)),
+            ]),
+        ),
+        LogicalExpr::IsNull(expr) => ("is_null", json_expr(expr)),
+        LogicalExpr::IsNotNull(expr) => ("is_not_null", json_expr(expr)),
+        LogicalExpr::Not(expr) => ("not", json_expr(expr)),
+        LogicalExpr::InSubquery(InSubquery { expr, subquery, negated }) => (
+            "in_subquery",
+            json_object(vec![
+                ("expr", json_expr(expr)),
+                ("negated", negated.to_string()),
+                ("subquery", subquery.to_json()),
+            ]),
+        ),
+        LogicalExpr::ScalarFunction(ScalarFunction { fun, args }) => (
+            "function",
+            json_object(vec![
+                ("name", json_string(&fun.to_string())),
+                ("args", json_array(args.iter().map(json_expr))),
+            ]),
+        ),
+        LogicalExpr::Placeholder(n) => ("placeholder", n.to_string()),
+        LogicalExpr::Case { when_then, else_expr } => {
+            let when_then = when_then.iter().map(|(when, then)| {
+                json_object(vec![("when", json_expr(when)), ("then", json_expr(then))])
+            });
+            let else_expr = match else_expr {
+                Some(expr) => json_expr(expr),
+                None => "null".to_string(),
+            };
+            ("case", json_object(vec![("when_then", json_array(when_then)), ("else", else_expr)]))
+        }
+        LogicalExpr::AggregateFunction(aggr) => return json_aggregate(aggr),
+        LogicalExpr::Wildcard => ("wildcard", "true".to_string()),
+    };
+    json_object(vec![(kind, value)])
+}
+
+fn json_column(column: &Column) -> String {
+    let table = match &column.table {
+        Some(table) => json_string(table),
+        None => "null".to_string(),
+    };
+    let value = json_object(vec![("table", table), ("name", json_string(&column.name))]);
+    json_object(vec![("column", value)])
+}
+
+fn json_aggregate(aggr: &AggregateFunction) -> String {
+    let name = match aggr.fun {
+        AggregateFunc::Count => "count",
+        AggregateFunc::Sum => "sum",
+        AggregateFunc::Min => "min",
+        AggregateFunc::Max => "max",
+        AggregateFunc::Avg => "avg",
+    };
+    let value = json_object(vec![
+        ("name", json_string(name)),
+        ("distinct", aggr.distinct.to_string()),
+        ("arg", json_expr(&aggr.args)),
+    ]);
+    json_object(vec![("aggregate", value)])
+}
+
+// 常量的值 NaN 和无穷大不是合法的 JSON 数字 按字符串输出
+fn json_scalar(value: &ScalarValue) -> String {
+    match value {
+        ScalarValue::Null
+        | ScalarValue::Boolean(None)
+        | ScalarValue::Float64(None)
+        | ScalarValue::Int64(None)
+        | ScalarValue::UInt64(None)
+        | ScalarValue::Utf8(None) => "null".to_string(),
+        ScalarValue::Boolean(Some(v)) => v.to_string(),
+        ScalarValue::Float64(Some(v)) if v.is_finite() => format!("{:?}", v),
+        ScalarValue::Float64(Some(v)) => json_string(&v.to_string()),
+        ScalarValue::Int64(Some(v)) => v.to_string(),
+        ScalarValue::UInt64(Some(v)) => v.to_string(),
+        ScalarValue::Utf8(Some(v)) => json_string(v),
+    }
+}
+
 // 通过递归调用来打印每个操作的详细信息，并根据不同的操作类型格式化输出。
 fn do_pretty_print(plan: &LogicalPlan, f: &mut Formatter<'_>, depth: usize) -> Result {
     write!(f, "{}", "  ".repeat(depth))?;
@@ -270,25 +559,18 @@ fn do_pretty_print(plan: &LogicalPlan, f: &mut Formatter<'_>, depth: usize) -> R
         }
         LogicalPlan::Insert(Insert {
             columns,
-            source,
+            values,
             input,
         }) => {
             writeln!(f, "Insert:")?;
             write!(f, "{}", "  ".repeat(depth + 1))?;
             writeln!(f, "columns: {:?}", columns)?;
 
-            // Print source (values or query)
-            write!(f, "{}", "  ".repeat(depth + 1))?;
-            writeln!(f, "source:")?;
-            match source {
-                SetExpr::Values(values) => {
-                    write!(f, "{}", "  ".repeat(depth + 2))?;
-                    writeln!(f, "Values: {:?}", values)?;
-                }
-                _ => {
-                    write!(f, "{}", "  ".repeat(depth + 2))?;
-                    writeln!(f, "Other source type: {:?}", source)?;
-                }
+            write!(f, "{}", "  ".repeat(depth + 1))?;
+            writeln!(f, "values:")?;
+            for row in values {
+                write!(f, "{}", "  ".repeat(depth + 2))?;
+                writeln!(f, "{:?}", row)?;
             }
 
             // Print the input plan (previous logical plan)
@@ -305,9 +587,9 @@ fn do_pretty_print(plan: &LogicalPlan, f: &mut Formatter<'_>, depth: usize) -> R
             // Print assignments (columns and their new values)
             write!(f, "{}", "  ".repeat(depth + 1))?;
             writeln!(f, "assignments:")?;
-            for assignment in assignments {
+            for (column, value) in assignments {
                 write!(f, "{}", "  ".repeat(depth + 2))?;
-                writeln!(f, "column: {:?}, value: {:?}", assignment.id, assignment.value)?;
+                writeln!(f, "column: {:?}, value: {:?}", column, value)?;
             }
 
             write!(f, "{}", "  ".repeat(depth + 1))?;
@@ -353,6 +635,7 @@ fn do_pretty_print(plan: &LogicalPlan, f: &mut Formatter<'_>, depth: usize) -> R
             left,
             right,
             on,
+            filter,
             join_type,
             schema,
         }) => {
@@ -369,6 +652,11 @@ fn do_pretty_print(plan: &LogicalPlan, f: &mut Formatter<'_>, depth: usize) -> R
             write!(f, "{}", "  ".repeat(depth + 1))?;
             writeln!(f, "on: {:?}", on)?;
 
+            if let Some(filter) = filter {
+                write!(f, "{}", "  ".repeat(depth + 1))?;
+                writeln!(f, "filter: {:?}", filter)?;
+            }
+
             write!(f, "{}", "  ".repeat(depth + 1))?;
             writeln!(f, "join_type: {:?}", join_type)?;
 
@@ -395,7 +683,27 @@ fn do_pretty_print(plan: &LogicalPlan, f: &mut Formatter<'_>, depth: usize) -> R
             writeln!(f, "input:")?;
             do_pretty_print(input.as_ref(), f, depth + 2)
         }
-        LogicalPlan::TableScan(TableScan { source, projection }) => {
+        LogicalPlan::Sort(Sort { order_by, input }) => {
+            writeln!(f, "Sort:")?;
+
+            write!(f, "{}", "  ".repeat(depth + 1))?;
+            writeln!(f, "order_by: {:?}", order_by)?;
+
+            write!(f, "{}", "  ".repeat(depth + 1))?;
+            writeln!(f, "input:")?;
+            do_pretty_print(input.as_ref(), f, depth + 2)
+        }
+        LogicalPlan::DistinctOn(DistinctOn { on_expr, input }) => {
+            writeln!(f, "DistinctOn:")?;
+
+            write!(f, "{}", "  ".repeat(depth + 1))?;
+            writeln!(f, "on_expr: {:?}", on_expr)?;
+
+            write!(f, "{}", "  ".repeat(depth + 1))?;
+            writeln!(f, "input:")?;
+            do_pretty_print(input.as_ref(), f, depth + 2)
+        }
+        LogicalPlan::TableScan(TableScan { source, projection, .. }) => {
             writeln!(f, "TableScan:")?;
 
             write!(f, "{}", "  ".repeat(depth + 1))?;
@@ -408,6 +716,7 @@ fn do_pretty_print(plan: &LogicalPlan, f: &mut Formatter<'_>, depth: usize) -> R
             left,
             right,
             on: _,
+            filter: _,
             join_type,
             schema,
         }) => {
@@ -4,7 +4,7 @@ use arrow::datatypes::{Field, Schema};
 use crate::error::ErrorCode;
 use crate::error::Result;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NaiveSchema {
     pub fields: Vec<NaiveField>,
 }
@@ -26,10 +26,7 @@ impl NaiveSchema {
             schema
                 .fields()
                 .iter()
-                .map(|field| NaiveField {
-                    field: field.clone(),
-                    qualifier: Some(qualifier.to_owned()),
-                })
+                .map(|field| NaiveField::from_qualified(qualifier, field.clone()))
                 .collect(),
         )
     }
@@ -39,14 +36,28 @@ impl NaiveSchema {
             schema
                 .fields()
                 .iter()
-                .map(|field| NaiveField {
-                    field: field.clone(),
-                    qualifier: None,
-                })
+                .map(|field| NaiveField::from(field.clone()))
                 .collect(),
         )
     }
 
+    /// 按列序号选出部分字段，序号超出范围时报错
+    pub fn project(&self, indices: &[usize]) -> Result<Self> {
+        let fields = indices
+            .iter()
+            .map(|i| {
+                self.fields.get(*i).cloned().ok_or_else(|| {
+                    ErrorCode::PlanError(format!(
+                        "projection index {} is out of range, the schema has {} columns",
+                        i,
+                        self.fields.len()
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::new(fields))
+    }
+
     /// join two schema
     pub fn join(&self, schema: &NaiveSchema) -> Self {
         let mut fields = self.fields.clone();
@@ -73,6 +84,29 @@ impl NaiveSchema {
         Err(ErrorCode::NoSuchField)
     }
 
+    /// Find the index of the field named `name`, when `relation_name` is given
+    /// the field's qualifier must match it as well. More than one match is an error
+    pub fn index_of_column(&self, relation_name: Option<&str>, name: &str) -> Result<usize> {
+        let matches_column = |field: &NaiveField| {
+            field.name() == name
+                && match relation_name {
+                    Some(relation) => field.qualifier().map(|q| q.as_str()) == Some(relation),
+                    None => true,
+                }
+        };
+        let matches = self.fields.iter().filter(|field| matches_column(field)).collect::<Vec<_>>();
+        check_ambiguous(name, &matches)?;
+        self.fields
+            .iter()
+            .position(matches_column)
+            .ok_or_else(|| match relation_name {
+                Some(relation) => {
+                    ErrorCode::ColumnNotExists(format!("column `{}.{}` not exists", relation, name))
+                }
+                None => ErrorCode::ColumnNotExists(format!("column `{}` not exists", name)),
+            })
+    }
+
     #[allow(unused)]
     /// Find the field with the given name
     pub fn field_with_name(&self, relation_name: Option<&str>, name: &str) -> Result<NaiveField> {
@@ -91,12 +125,10 @@ impl NaiveSchema {
             .collect::<Vec<_>>();
         match matches.len() {
             0 => Err(ErrorCode::PlanError(format!("No field named '{}'", name))),
-            _ => Ok(matches[0].to_owned()),
-            // TODO(veeupup): multi same name, and we need to return Error
-            // _ => Err(ErrorCode::PlanError(format!(
-            //     "Ambiguous reference to field named '{}'",
-            //     name
-            // ))),
+            _ => {
+                check_ambiguous(name, &matches)?;
+                Ok(matches[0].to_owned())
+            }
         }
     }
 
@@ -110,16 +142,32 @@ impl NaiveSchema {
             .collect::<Vec<_>>();
         match matches.len() {
             0 => Err(ErrorCode::PlanError(format!("No field named '{}'", name))),
-            _ => Ok(matches[0].to_owned()),
-            // TODO(veeupup): multi same name, and we need to return Error
-            // _ => Err(ErrorCode::PlanError(format!(
-            //     "Ambiguous reference to field named '{}'",
-            //     name
-            // ))),
+            _ => {
+                check_ambiguous(name, &matches)?;
+                Ok(matches[0].to_owned())
+            }
         }
     }
 }
 
+// 列名匹配到多个列时报错：不带表名时例如连接后两边都有 id，需要用 `t.id` 指明；
+// 带表名时例如子查询 `(SELECT e.id, d.id ...) x` 的两列在别名 x 下同名，无法区分
+fn check_ambiguous(name: &str, matches: &[&NaiveField]) -> Result<()> {
+    let ambiguous = matches.len() > 1;
+    if ambiguous {
+        let candidates = matches
+            .iter()
+            .map(|field| field.qualified_name())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(ErrorCode::PlanError(format!(
+            "column reference `{}` is ambiguous, it could refer to {}",
+            name, candidates
+        )));
+    }
+    Ok(())
+}
+
 impl From<NaiveSchema> for Schema {
     fn from(schema: NaiveSchema) -> Self {
         Schema::new(
@@ -157,6 +205,9 @@ pub struct NaiveField {
     qualifier: Option<String>,
     /// Arrow field definition
     field: Field,
+    /// The expression a computed column (like `a + b`) is evaluated from,
+    /// `None` for columns read from a table
+    source_expr: Option<String>,
 }
 
 impl NaiveField {
@@ -164,6 +215,17 @@ impl NaiveField {
         Self {
             qualifier: qualifier.map(|s| s.to_owned()),
             field: Field::new(name, data_type, nullable),
+            source_expr: None,
+        }
+    }
+
+    /// Create an unqualified field for a computed column, remembering the
+    /// expression it comes from
+    pub fn new_computed(name: &str, data_type: DataType, nullable: bool, source_expr: &str) -> Self {
+        Self {
+            qualifier: None,
+            field: Field::new(name, data_type, nullable),
+            source_expr: Some(source_expr.to_owned()),
         }
     }
 
@@ -172,6 +234,7 @@ impl NaiveField {
         Self {
             qualifier: None,
             field,
+            source_expr: None,
         }
     }
 
@@ -180,6 +243,7 @@ impl NaiveField {
         Self {
             qualifier: Some(qualifier.to_owned()),
             field,
+            source_expr: None,
         }
     }
 
@@ -218,6 +282,11 @@ impl NaiveField {
     pub fn qualifier(&self) -> Option<&String> {
         self.qualifier.as_ref()
     }
+
+    /// The expression this computed column is evaluated from
+    pub fn source_expr(&self) -> Option<&String> {
+        self.source_expr.as_ref()
+    }
 }
 
 impl From<NaiveField> for Field {
@@ -1,4 +1,4 @@
-use simple_db::print_result;
+use simple_db::print_result_limited;
 use simple_db::CsvConfig;
 use simple_db::SimpleDB;
 use simple_db::Result;
@@ -12,6 +12,9 @@ fn run_sql_on_db(db_arc: Arc<Mutex<SimpleDB>>, sql: &str) -> Result<Vec<RecordBa
     db.run_sql(sql)
 }
 
+// 默认最多显示的行数 可以通过 `.maxrows N` 修改，0 表示不限制
+const DEFAULT_MAX_ROWS: usize = 100;
+
 fn main() -> Result<()> {
     // 创建数据库
     let mut db = SimpleDB::default();
@@ -26,6 +29,7 @@ fn main() -> Result<()> {
 
     // 创建数据库的引用
     let db_arc = Arc::new(Mutex::new(db));
+    let mut max_rows = DEFAULT_MAX_ROWS;
     // 进入一个命令行交互模式
     loop {
         // 提示用户输入 SQL 查询
@@ -45,10 +49,19 @@ fn main() -> Result<()> {
             break;
         }
 
+        // `.maxrows N` 设置结果最多显示的行数
+        if let Some(arg) = sql.strip_prefix(".maxrows") {
+            match arg.trim().parse::<usize>() {
+                Ok(n) => max_rows = n,
+                Err(_) => println!("usage: .maxrows N (0 for no limit), current: {}", max_rows),
+            }
+            continue;
+        }
+
         // 执行查询并输出结果
         match run_sql_on_db(db_arc.clone(), sql) {
             Ok(result) => {
-                print_result(&result)?;
+                print_result_limited(&result, max_rows)?;
             }
             Err(e) => {
                 println!("Error executing query '{}': {:?}", sql, e);
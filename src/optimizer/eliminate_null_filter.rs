@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use super::OptimizerRule;
+use crate::logical_plan::expression::{BinaryExpr, Column, LogicalExpr, Operator, ScalarValue};
+use crate::logical_plan::plan::{Filter, LogicalPlan, TableScan};
+
+/// 根据表的统计信息化简直接作用于表扫描的 IS NULL / IS NOT NULL 条件：
+/// 列中没有空值时 IS NOT NULL 恒为 true、IS NULL 恒为 false，列中全是空值时相反。
+/// 恒为 true 的条件从谓词中去掉，全部去掉后不再需要 Filter；恒为 false 的条件替换为 false。
+/// 这里只能根据整张表判断：只有部分 batch 没有空值时过滤条件保留在计划中，
+/// 执行时由 PhysicalIsNullExpr 根据每个 batch 的空值个数跳过没有空值或者全是空值的 batch
+pub struct EliminateNullFilter;
+
+impl OptimizerRule for EliminateNullFilter {
+    fn optimize(&self, plan: &LogicalPlan) -> LogicalPlan {
+        let filter = match plan {
+            LogicalPlan::Filter(filter) => filter,
+            _ => return plan.clone(),
+        };
+        let scan = match filter.input.as_ref() {
+            LogicalPlan::TableScan(scan) => scan,
+            _ => return plan.clone(),
+        };
+
+        let mut conjuncts = vec![];
+        split_conjunction(&filter.predicate, &mut conjuncts);
+        // 只有 IS [NOT] NULL 条件需要统计信息
+        let has_null_test = conjuncts
+            .iter()
+            .any(|conjunct| matches!(conjunct, LogicalExpr::IsNull(_) | LogicalExpr::IsNotNull(_)));
+        if !has_null_test {
+            return plan.clone();
+        }
+        let statistics = match scan.source.statistics() {
+            Some(statistics) => statistics,
+            None => return plan.clone(),
+        };
+        let mut remaining = vec![];
+        for conjunct in conjuncts {
+            let (column, is_null) = match conjunct {
+                LogicalExpr::IsNull(expr) => (expr.as_ref(), true),
+                LogicalExpr::IsNotNull(expr) => (expr.as_ref(), false),
+                _ => {
+                    remaining.push(conjunct.clone());
+                    continue;
+                }
+            };
+            let null_count = match column {
+                LogicalExpr::Column(column) => source_column(scan, column)
+                    .and_then(|idx| statistics.null_counts.get(idx).copied()),
+                _ => None,
+            };
+            // 空表中两种条件都不会选出任何行 不需要化简
+            let all_valid = null_count == Some(0) && statistics.num_rows > 0;
+            let all_null = null_count == Some(statistics.num_rows) && statistics.num_rows > 0;
+            if (all_valid && !is_null) || (all_null && is_null) {
+                continue;
+            }
+            if (all_valid && is_null) || (all_null && !is_null) {
+                remaining.push(LogicalExpr::Literal(ScalarValue::Boolean(Some(false))));
+                continue;
+            }
+            remaining.push(conjunct.clone());
+        }
+
+        let mut remaining = remaining.into_iter();
+        match remaining.next() {
+            None => filter.input.as_ref().clone(),
+            Some(first) => LogicalPlan::Filter(Filter {
+                predicate: remaining.fold(first, |predicate, expr| predicate.and(expr)),
+                input: Arc::new(LogicalPlan::TableScan(scan.clone())),
+            }),
+        }
+    }
+}
+
+// 把用 AND 连接的谓词拆分为多个条件
+fn split_conjunction<'a>(expr: &'a LogicalExpr, conjuncts: &mut Vec<&'a LogicalExpr>) {
+    match expr {
+        LogicalExpr::BinaryExpr(BinaryExpr { left, op: Operator::And, right }) => {
+            split_conjunction(left, conjuncts);
+            split_conjunction(right, conjuncts);
+        }
+        _ => conjuncts.push(expr),
+    }
+}
+
+// 列在表的全部列中的序号 表扫描带有投影时按投影换算
+fn source_column(scan: &TableScan, column: &Column) -> Option<usize> {
+    let idx = scan.schema.index_of_column(column.table.as_deref(), &column.name).ok()?;
+    match &scan.projection {
+        Some(projection) => projection.get(idx).copied(),
+        None => Some(idx),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+
+    use super::*;
+    use crate::datasource::test_utils::CountingTable;
+    use crate::logical_plan::DataFrame;
+    use crate::optimizer::test_utils::{column, scan_batches};
+    use crate::planner::QueryPlanner;
+    use crate::Result;
+
+    // id 没有空值，note 全是空值
+    fn scan() -> Result<LogicalPlan> {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("note", DataType::Utf8, true),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef,
+                Arc::new(StringArray::from(vec![None::<&str>, None, None])) as ArrayRef,
+            ],
+        )?;
+        scan_batches(vec![batch])
+    }
+
+    #[test]
+    fn redundant_null_filters_are_removed() -> Result<()> {
+        let predicate = LogicalExpr::IsNotNull(Box::new(column("id")))
+            .and(LogicalExpr::IsNull(Box::new(column("note"))));
+        let plan = DataFrame::new(scan()?).filter(predicate)?.logical_plan();
+
+        let optimized = EliminateNullFilter.optimize(&plan);
+        assert!(matches!(optimized, LogicalPlan::TableScan(_)));
+        let batches = QueryPlanner::default().create_physical_plan(&optimized)?.execute()?;
+        assert_eq!(batches.iter().map(|batch| batch.num_rows()).sum::<usize>(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn impossible_null_filter_becomes_false() -> Result<()> {
+        let plan = DataFrame::new(scan()?)
+            .filter(LogicalExpr::IsNull(Box::new(column("id"))))?
+            .logical_plan();
+
+        let optimized = EliminateNullFilter.optimize(&plan);
+        match &optimized {
+            LogicalPlan::Filter(filter) => assert!(matches!(
+                filter.predicate,
+                LogicalExpr::Literal(ScalarValue::Boolean(Some(false)))
+            )),
+            other => panic!("expected a filter, got {:?}", other),
+        }
+        let batches = QueryPlanner::default().create_physical_plan(&optimized)?.execute()?;
+        assert_eq!(batches.iter().map(|batch| batch.num_rows()).sum::<usize>(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn null_filters_short_circuit_per_batch() -> Result<()> {
+        // 只有第一个 batch 没有空值 整张表的统计信息不能证明过滤条件多余
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true)]));
+        let batch = |ids: Vec<Option<i64>>| {
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(ids)) as ArrayRef])
+        };
+        let no_nulls = batch(vec![Some(1), Some(2)])?;
+        let batches = vec![no_nulls.clone(), batch(vec![Some(3), None])?, batch(vec![None, None])?];
+        let plan = DataFrame::new(scan_batches(batches)?)
+            .filter(LogicalExpr::IsNotNull(Box::new(column("id"))))?
+            .logical_plan();
+
+        let optimized = EliminateNullFilter.optimize(&plan);
+        assert!(matches!(optimized, LogicalPlan::Filter(_)));
+        let batches = QueryPlanner::default().create_physical_plan(&optimized)?.execute()?;
+        let num_rows = batches.iter().map(|batch| batch.num_rows()).collect::<Vec<_>>();
+        assert_eq!(num_rows, [2, 1, 0]);
+        // 没有空值的 batch 原样输出 不需要复制数据
+        assert!(Arc::ptr_eq(batches[0].column(0), no_nulls.column(0)));
+        Ok(())
+    }
+
+    #[test]
+    fn sources_without_statistics_keep_null_filters_and_are_not_scanned() -> Result<()> {
+        // CountingTable 没有统计信息 优化时不能读取表的数据
+        let source = CountingTable::create(3, 2)?;
+        let scan = LogicalPlan::TableScan(TableScan::new(source.clone()));
+        let plan = DataFrame::new(scan).filter(LogicalExpr::IsNotNull(Box::new(column("id"))))?.logical_plan();
+        assert!(matches!(EliminateNullFilter.optimize(&plan), LogicalPlan::Filter(_)));
+        assert_eq!(source.scans(), 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use super::OptimizerRule;
+use crate::logical_plan::expression::{Column, LogicalExpr};
+use crate::logical_plan::plan::{LogicalPlan, Projection};
+
+/// 去掉按原顺序选出输入全部列的投影（例如 `SELECT * FROM t`），直接返回输入计划
+pub struct EliminateProjection;
+
+impl OptimizerRule for EliminateProjection {
+    fn optimize(&self, plan: &LogicalPlan) -> LogicalPlan {
+        match plan {
+            LogicalPlan::Projection(projection) if is_redundant(projection) => {
+                projection.input.as_ref().clone()
+            }
+            _ => plan.clone(),
+        }
+    }
+}
+
+// 投影的每个表达式都是输入的第 i 列，且输出的 schema 与输入完全相同（别名会改变限定名）
+fn is_redundant(projection: &Projection) -> bool {
+    let input_schema = projection.input.schema();
+    if projection.exprs.len() != input_schema.fields().len() || projection.schema != *input_schema {
+        return false;
+    }
+    projection.exprs.iter().enumerate().all(|(i, expr)| match expr {
+        LogicalExpr::Column(Column { table, name }) => {
+            matches!(input_schema.index_of_column(table.as_deref(), name), Ok(idx) if idx == i)
+        }
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::DataFrame;
+    use crate::optimizer::test_utils::{column, scan};
+    use crate::Result;
+
+    #[test]
+    fn projection_of_every_column_in_order_is_removed() -> Result<()> {
+        // SELECT * FROM t
+        let plan = DataFrame::new(scan()?)
+            .project(vec![column("id"), column("name")])?
+            .logical_plan();
+        assert!(matches!(EliminateProjection.optimize(&plan), LogicalPlan::TableScan(_)));
+
+        // 列的顺序改变或者只选出部分列时保留投影
+        for exprs in [vec![column("name"), column("id")], vec![column("id")]] {
+            let plan = DataFrame::new(scan()?).project(exprs)?.logical_plan();
+            assert!(matches!(EliminateProjection.optimize(&plan), LogicalPlan::Projection(_)));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use super::OptimizerRule;
+use crate::logical_plan::expression::{BinaryExpr, Column, LogicalExpr};
+use crate::logical_plan::plan::{Filter, LogicalPlan, Projection};
+
+/// 把投影之上的 Filter 移到投影之下，先过滤再计算投影
+/// 谓词引用的每一列都必须是投影直接选出的输入列，否则保持原有的计划
+pub struct FilterPushDown;
+
+impl OptimizerRule for FilterPushDown {
+    fn optimize(&self, plan: &LogicalPlan) -> LogicalPlan {
+        match plan {
+            LogicalPlan::Filter(filter) => match filter.input.as_ref() {
+                LogicalPlan::Projection(projection) if can_push_down(&filter.predicate, projection) => {
+                    LogicalPlan::Projection(Projection {
+                        input: Arc::new(LogicalPlan::Filter(Filter {
+                            predicate: filter.predicate.clone(),
+                            input: projection.input.clone(),
+                        })),
+                        ..projection.clone()
+                    })
+                }
+                _ => plan.clone(),
+            },
+            _ => plan.clone(),
+        }
+    }
+}
+
+// 谓词中的列在投影的输出中对应的表达式也是同一个输入列（而不是同名的别名）
+fn can_push_down(predicate: &LogicalExpr, projection: &Projection) -> bool {
+    let mut columns = vec![];
+    if !collect_columns(predicate, &mut columns) {
+        return false;
+    }
+    let input_schema = projection.input.schema();
+    columns.iter().all(|Column { table, name }| {
+        let idx = match projection.schema.index_of_column(table.as_deref(), name) {
+            Ok(idx) => idx,
+            Err(_) => return false,
+        };
+        let input_idx = match input_schema.index_of_column(table.as_deref(), name) {
+            Ok(idx) => idx,
+            Err(_) => return false,
+        };
+        match &projection.exprs[idx] {
+            LogicalExpr::Column(Column { table, name }) => {
+                matches!(input_schema.index_of_column(table.as_deref(), name), Ok(i) if i == input_idx)
+            }
+            _ => false,
+        }
+    })
+}
+
+// 收集表达式中引用的列 含有聚合函数或通配符时返回 false
+fn collect_columns(expr: &LogicalExpr, columns: &mut Vec<Column>) -> bool {
+    match expr {
+        LogicalExpr::Column(column) => {
+            columns.push(column.clone());
+            true
+        }
+        LogicalExpr::Literal(_) | LogicalExpr::Placeholder(_) => true,
+        LogicalExpr::Alias(expr, _)
+        | LogicalExpr::IsNull(expr)
+        | LogicalExpr::IsNotNull(expr)
+        | LogicalExpr::Not(expr) => collect_columns(expr, columns),
+        LogicalExpr::BinaryExpr(BinaryExpr { left, right, .. }) => {
+            collect_columns(left, columns) && collect_columns(right, columns)
+        }
+        LogicalExpr::InSubquery(in_subquery) => collect_columns(&in_subquery.expr, columns),
+        LogicalExpr::ScalarFunction(func) => func.args.iter().all(|arg| collect_columns(arg, columns)),
+        LogicalExpr::Case { when_then, else_expr } => {
+            when_then
+                .iter()
+                .all(|(when, then)| collect_columns(when, columns) && collect_columns(then, columns))
+                && else_expr.as_deref().is_none_or(|expr| collect_columns(expr, columns))
+        }
+        LogicalExpr::AggregateFunction(_) | LogicalExpr::Wildcard => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::expression::Operator;
+    use crate::logical_plan::literal::lit;
+    use crate::logical_plan::DataFrame;
+    use crate::optimizer::test_utils::{column, scan};
+    use crate::Result;
+
+    fn id_gt_one(name: &str) -> LogicalExpr {
+        LogicalExpr::BinaryExpr(BinaryExpr {
+            left: Box::new(column(name)),
+            op: Operator::Gt,
+            right: Box::new(lit(1_i64)),
+        })
+    }
+
+    #[test]
+    fn filter_on_a_projected_column_moves_below_the_projection() -> Result<()> {
+        let plan = DataFrame::new(scan()?)
+            .project(vec![column("id")])?
+            .filter(id_gt_one("id"))?
+            .logical_plan();
+
+        match FilterPushDown.optimize(&plan) {
+            LogicalPlan::Projection(projection) => match projection.input.as_ref() {
+                LogicalPlan::Filter(filter) => {
+                    assert!(matches!(filter.input.as_ref(), LogicalPlan::TableScan(_)))
+                }
+                other => panic!("expected the filter below the projection, got {:?}", other),
+            },
+            other => panic!("expected a projection on top, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn filter_on_an_alias_stays_above_the_projection() -> Result<()> {
+        // SELECT name AS id FROM t WHERE id > 1：输出的 id 不是输入的 id 列
+        let plan = DataFrame::new(scan()?)
+            .project(vec![LogicalExpr::Alias(Box::new(column("name")), "id".to_string())])?
+            .logical_plan();
+        let plan = LogicalPlan::Filter(Filter {
+            predicate: id_gt_one("id"),
+            input: Arc::new(plan),
+        });
+
+        assert!(matches!(FilterPushDown.optimize(&plan), LogicalPlan::Filter(_)));
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use super::OptimizerRule;
+use crate::logical_plan::plan::{Limit, LogicalPlan, Offset, Projection};
+use std::sync::Arc;
+
+/// 把 Limit 移到 Projection 之下（例如 `SELECT a + 1 FROM t LIMIT 5`），
+/// 投影不会改变行数和行的顺序，先截取再投影可以少计算被丢弃的行
+pub struct LimitPushDown;
+
+impl OptimizerRule for LimitPushDown {
+    fn optimize(&self, plan: &LogicalPlan) -> LogicalPlan {
+        match plan {
+            LogicalPlan::Limit(Limit { n, input }) => match input.as_ref() {
+                LogicalPlan::Projection(projection) => {
+                    let limit = LogicalPlan::Limit(Limit {
+                        n: *n,
+                        input: projection.input.clone(),
+                    });
+                    LogicalPlan::Projection(Projection {
+                        exprs: projection.exprs.clone(),
+                        input: Arc::new(self.optimize(&limit)),
+                        schema: projection.schema.clone(),
+                    })
+                }
+                // LIMIT n OFFSET m 的计划是 Limit(Offset(...))，Offset 同样可以和投影交换
+                LogicalPlan::Offset(Offset { n: offset, input: offset_input }) => {
+                    match offset_input.as_ref() {
+                        LogicalPlan::Projection(projection) => {
+                            let limit = LogicalPlan::Limit(Limit {
+                                n: *n,
+                                input: Arc::new(LogicalPlan::Offset(Offset {
+                                    n: *offset,
+                                    input: projection.input.clone(),
+                                })),
+                            });
+                            LogicalPlan::Projection(Projection {
+                                exprs: projection.exprs.clone(),
+                                input: Arc::new(limit),
+                                schema: projection.schema.clone(),
+                            })
+                        }
+                        _ => plan.clone(),
+                    }
+                }
+                _ => plan.clone(),
+            },
+            _ => plan.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::DataFrame;
+    use crate::optimizer::test_utils::{column, scan};
+    use crate::Result;
+
+    #[test]
+    fn limit_moves_below_projection() -> Result<()> {
+        let plan = DataFrame::new(scan()?)
+            .project(vec![column("name")])?
+            .limit(2)
+            .logical_plan();
+
+        let optimized = LimitPushDown.optimize(&plan);
+        let projection = match &optimized {
+            LogicalPlan::Projection(projection) => projection,
+            other => panic!("expected a projection on top, got {:?}", other),
+        };
+        assert_eq!(projection.schema, *plan.schema());
+        match projection.input.as_ref() {
+            LogicalPlan::Limit(limit) => {
+                assert_eq!(limit.n, 2);
+                assert!(matches!(limit.input.as_ref(), LogicalPlan::TableScan(_)));
+            }
+            other => panic!("expected the limit below the projection, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn limit_with_offset_moves_below_projection() -> Result<()> {
+        let plan = DataFrame::new(scan()?)
+            .project(vec![column("name")])?
+            .offset(1)
+            .limit(1)
+            .logical_plan();
+
+        let optimized = LimitPushDown.optimize(&plan);
+        let input = match &optimized {
+            LogicalPlan::Projection(projection) => projection.input.as_ref(),
+            other => panic!("expected a projection on top, got {:?}", other),
+        };
+        let offset = match input {
+            LogicalPlan::Limit(Limit { n: 1, input }) => input.as_ref(),
+            other => panic!("expected the limit below the projection, got {:?}", other),
+        };
+        assert!(matches!(offset, LogicalPlan::Offset(Offset { n: 1, .. })));
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use super::OptimizerRule;
+use crate::logical_plan::plan::{Filter, LogicalPlan};
+
+/// 把相邻的多个 Filter 合并为一个，谓词用 AND 连接，减少一次遍历数据
+/// 只有两个谓词都为 true 的行才会保留，与依次过滤的结果相同
+pub struct MergeFilter;
+
+impl OptimizerRule for MergeFilter {
+    fn optimize(&self, plan: &LogicalPlan) -> LogicalPlan {
+        match plan {
+            LogicalPlan::Filter(filter) => {
+                let mut predicate = filter.predicate.clone();
+                let mut input = filter.input.clone();
+                // 内层的谓词放在前面
+                while let LogicalPlan::Filter(inner) = input.as_ref() {
+                    predicate = inner.predicate.clone().and(predicate);
+                    input = inner.input.clone();
+                }
+                LogicalPlan::Filter(Filter { predicate, input })
+            }
+            _ => plan.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::expression::{BinaryExpr, LogicalExpr, Operator};
+    use crate::logical_plan::literal::lit;
+    use crate::logical_plan::DataFrame;
+    use crate::optimizer::test_utils::{column, scan};
+    use crate::planner::QueryPlanner;
+    use crate::Result;
+
+    fn compare(name: &str, op: Operator, value: i64) -> LogicalExpr {
+        LogicalExpr::BinaryExpr(BinaryExpr {
+            left: Box::new(column(name)),
+            op,
+            right: Box::new(lit(value)),
+        })
+    }
+
+    #[test]
+    fn stacked_filters_collapse_into_one() -> Result<()> {
+        let plan = DataFrame::new(scan()?)
+            .filter(compare("id", Operator::Gt, 1))?
+            .filter(compare("id", Operator::Lt, 4))?
+            .filter(compare("id", Operator::NotEq, 3))?
+            .logical_plan();
+
+        let optimized = MergeFilter.optimize(&plan);
+        let filter = match &optimized {
+            LogicalPlan::Filter(filter) => filter,
+            other => panic!("expected a filter, got {:?}", other),
+        };
+        assert!(matches!(filter.input.as_ref(), LogicalPlan::TableScan(_)));
+        // 内层的谓词在前
+        let expected = compare("id", Operator::Gt, 1)
+            .and(compare("id", Operator::Lt, 4).and(compare("id", Operator::NotEq, 3)));
+        assert_eq!(format!("{:?}", filter.predicate), format!("{:?}", expected));
+
+        // 合并前后的结果相同：只剩 id = 2
+        for plan in [&plan, &optimized] {
+            let batches = QueryPlanner::default().create_physical_plan(plan)?.execute()?;
+            assert_eq!(batches.iter().map(|batch| batch.num_rows()).sum::<usize>(), 1);
+        }
+        Ok(())
+    }
+}
@@ -1,23 +1,167 @@
+mod eliminate_null_filter;
+mod eliminate_projection;
+mod filter_push_down;
+mod limit_push_down;
+mod merge_filter;
 mod projection_push_down;
 
-use crate::logical_plan::plan::LogicalPlan;
+use crate::logical_plan::plan::{
+    Aggregate, Delete, DistinctOn, Filter, Insert, Join, Limit, LogicalPlan, Offset, Projection,
+    Sort, Update,
+};
+use eliminate_null_filter::EliminateNullFilter;
+use eliminate_projection::EliminateProjection;
+use filter_push_down::FilterPushDown;
+use limit_push_down::LimitPushDown;
+use merge_filter::MergeFilter;
+use projection_push_down::ProjectionPushDown;
 use std::sync::Arc;
 
-#[derive(Default)]
 pub struct Optimizer {
     rules: Vec<Arc<dyn OptimizerRule>>,
 }
 
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self {
+            // LimitPushDown 在前 下推之后位于顶层的多余投影可以被去掉
+            // Filter 移到投影之下后相邻的 Filter 再合并，根据统计信息去掉多余的 IS [NOT] NULL 条件，
+            // 剩下的只选择列的投影合并到表扫描中
+            rules: vec![
+                Arc::new(LimitPushDown),
+                Arc::new(EliminateProjection),
+                Arc::new(FilterPushDown),
+                Arc::new(MergeFilter),
+                Arc::new(EliminateNullFilter),
+                Arc::new(ProjectionPushDown),
+            ],
+        }
+    }
+}
+
 pub trait OptimizerRule {
     fn optimize(&self, plan: &LogicalPlan) -> LogicalPlan;
 }
 
 impl Optimizer {
+    // 每条规则都作用于计划树中的每一个节点
     pub fn optimize(&self, plan: LogicalPlan) -> LogicalPlan {
         let mut plan = plan;
         for rule in &self.rules {
-            plan = rule.optimize(&plan);
+            plan = optimize_recursively(rule.as_ref(), &plan);
         }
         plan
     }
 }
+
+// 先对当前节点应用规则，再对改写后的节点的子计划递归应用
+fn optimize_recursively(rule: &dyn OptimizerRule, plan: &LogicalPlan) -> LogicalPlan {
+    let plan = rule.optimize(plan);
+    let inputs = plan
+        .children()
+        .iter()
+        .map(|child| Arc::new(optimize_recursively(rule, child)))
+        .collect();
+    with_new_inputs(&plan, inputs)
+}
+
+// 用新的子计划替换原来的子计划，其余部分保持不变
+pub(crate) fn with_new_inputs(plan: &LogicalPlan, inputs: Vec<Arc<LogicalPlan>>) -> LogicalPlan {
+    let mut inputs = inputs.into_iter();
+    let mut next = || inputs.next().unwrap();
+    match plan {
+        LogicalPlan::Projection(projection) => LogicalPlan::Projection(Projection {
+            input: next(),
+            ..projection.clone()
+        }),
+        LogicalPlan::Filter(filter) => LogicalPlan::Filter(Filter {
+            input: next(),
+            ..filter.clone()
+        }),
+        LogicalPlan::Aggregate(aggregate) => LogicalPlan::Aggregate(Aggregate {
+            input: next(),
+            ..aggregate.clone()
+        }),
+        LogicalPlan::Join(join) => LogicalPlan::Join(Join {
+            left: next(),
+            right: next(),
+            ..join.clone()
+        }),
+        LogicalPlan::CrossJoin(join) => LogicalPlan::CrossJoin(Join {
+            left: next(),
+            right: next(),
+            ..join.clone()
+        }),
+        LogicalPlan::Limit(limit) => LogicalPlan::Limit(Limit {
+            input: next(),
+            ..limit.clone()
+        }),
+        LogicalPlan::Offset(offset) => LogicalPlan::Offset(Offset {
+            input: next(),
+            ..offset.clone()
+        }),
+        LogicalPlan::Sort(sort) => LogicalPlan::Sort(Sort {
+            input: next(),
+            ..sort.clone()
+        }),
+        LogicalPlan::DistinctOn(distinct_on) => LogicalPlan::DistinctOn(DistinctOn {
+            input: next(),
+            ..distinct_on.clone()
+        }),
+        LogicalPlan::Update(update) => LogicalPlan::Update(Update {
+            input: next(),
+            ..update.clone()
+        }),
+        LogicalPlan::Insert(insert) => LogicalPlan::Insert(Insert {
+            input: next(),
+            ..insert.clone()
+        }),
+        LogicalPlan::Delete(delete) => LogicalPlan::Delete(Delete {
+            input: next(),
+            ..delete.clone()
+        }),
+        LogicalPlan::TableScan(_) | LogicalPlan::CreateTable(_) => plan.clone(),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+
+    use crate::datasource::MemTable;
+    use crate::logical_plan::expression::LogicalExpr;
+    use crate::logical_plan::plan::{LogicalPlan, TableScan};
+    use crate::logical_plan::schema::NaiveSchema;
+    use crate::Result;
+
+    // 优化规则和逻辑计划测试使用的表 t(id, name)
+    pub fn scan() -> Result<LogicalPlan> {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, false),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef,
+                Arc::new(StringArray::from(vec!["a", "b", "c"])) as ArrayRef,
+            ],
+        )?;
+        scan_batches(vec![batch])
+    }
+
+    // 由给定的 batch 组成的表 t，各个 batch 的 schema 相同
+    pub fn scan_batches(batches: Vec<RecordBatch>) -> Result<LogicalPlan> {
+        let schema = batches[0].schema();
+        let source = MemTable::try_create(NaiveSchema::from_qualified("t", &schema), batches)?;
+        Ok(LogicalPlan::TableScan(TableScan::new(source)))
+    }
+
+    pub fn column(name: &str) -> LogicalExpr {
+        LogicalExpr::column(None, name.to_string())
+    }
+}
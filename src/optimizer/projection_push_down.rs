@@ -1,5 +1,6 @@
 use super::OptimizerRule;
-use crate::logical_plan::plan::{LogicalPlan, TableScan, };
+use crate::logical_plan::expression::{Column, LogicalExpr};
+use crate::logical_plan::plan::{LogicalPlan, TableScan};
 
 pub struct ProjectionPushDown;
 
@@ -13,21 +14,40 @@ impl OptimizerRule for ProjectionPushDown {
 
                 // 如果子计划是 TableScan，则可以下推投影
                 if let LogicalPlan::TableScan(scan_plan) = &**input_plan {
-                    // 获取 TableScan 的投影
-                    let existing_projection = scan_plan.projection.clone();
+                    // 只有每个投影表达式都是普通的列时才能下推 否则保持原有的 Projection 计划
+                    // 按列名（以及表名）在扫描输出的 schema 中找到每一列真正的位置
+                    let mut indices = vec![];
+                    for expr in projection_exprs {
+                        match expr {
+                            LogicalExpr::Column(Column { table, name }) => {
+                                match scan_plan.schema.index_of_column(table.as_deref(), name) {
+                                    Ok(index) => indices.push(index),
+                                    Err(_) => return plan.clone(),
+                                }
+                            }
+                            _ => return plan.clone(),
+                        }
+                    }
+                    let schema = match scan_plan.schema.project(&indices) {
+                        Ok(schema) => schema,
+                        Err(_) => return plan.clone(),
+                    };
+                    // 投影改变了列的限定名（例如子查询的别名）时不能去掉投影
+                    if schema != projection_plan.schema {
+                        return plan.clone();
+                    }
 
-                    // 合并投影表达式
-                    let new_projection = Some(projection_exprs.iter().enumerate().map(|(index, _expr)| {
-                        // 假设我们有一个方法可以将 LogicalExpr 转换为列索引
-                        // 这里我们手动根据表达式的顺序来生成索引
-                        // 例如，假设 expr 是直接可以转换为列索引的（只做简单的索引映射）
-                        index
-                    }).collect::<Vec<_>>());
+                    // 扫描已经有投影时 序号是相对于投影之后的列
+                    let projection = match &scan_plan.projection {
+                        Some(existing) => indices.iter().map(|i| existing[*i]).collect(),
+                        None => indices,
+                    };
 
                     // 创建新的 TableScan 计划，设置新的投影
                     let new_scan_plan = TableScan {
                         source: scan_plan.source.clone(),
-                        projection: new_projection.or(existing_projection),
+                        projection: Some(projection),
+                        schema,
                     };
 
                     // 返回新的 TableScan 计划
@@ -42,3 +62,57 @@ impl OptimizerRule for ProjectionPushDown {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int64Array};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+
+    use super::*;
+    use crate::logical_plan::expression::{BinaryExpr, Operator, ScalarValue};
+    use crate::logical_plan::DataFrame;
+    use crate::optimizer::test_utils::{column, scan_batches};
+    use crate::Result;
+
+    // 表 t(a, b, c)
+    fn scan() -> Result<LogicalPlan> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int64, false),
+            Field::new("b", DataType::Int64, false),
+            Field::new("c", DataType::Int64, false),
+        ]);
+        let columns = (1..=3)
+            .map(|i| Arc::new(Int64Array::from(vec![i, i * 10])) as ArrayRef)
+            .collect();
+        scan_batches(vec![RecordBatch::try_new(Arc::new(schema), columns)?])
+    }
+
+    #[test]
+    fn columns_selected_out_of_order_push_down_their_indices() -> Result<()> {
+        // SELECT c, a FROM t
+        let plan = DataFrame::new(scan()?)
+            .project(vec![column("c"), column("a")])?
+            .logical_plan();
+        match ProjectionPushDown.optimize(&plan) {
+            LogicalPlan::TableScan(scan) => {
+                assert_eq!(scan.projection, Some(vec![2, 0]));
+                let names = scan.schema.fields().iter().map(|f| f.name().clone()).collect::<Vec<_>>();
+                assert_eq!(names, ["c", "a"]);
+            }
+            other => panic!("expected a TableScan, got {:?}", other),
+        }
+
+        // 有不是普通列的表达式时保留投影
+        let plus_one = LogicalExpr::BinaryExpr(BinaryExpr {
+            left: Box::new(column("b")),
+            op: Operator::Plus,
+            right: Box::new(LogicalExpr::Literal(ScalarValue::Int64(Some(1)))),
+        });
+        let plan = DataFrame::new(scan()?).project(vec![column("c"), plus_one])?.logical_plan();
+        assert!(matches!(ProjectionPushDown.optimize(&plan), LogicalPlan::Projection(_)));
+        Ok(())
+    }
+}
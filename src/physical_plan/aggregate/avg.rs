@@ -1,4 +1,5 @@
 use arrow::array::Array;
+use arrow::array::ArrayRef;
 use arrow::array::PrimitiveArray;
 use arrow::datatypes::DataType;
 
@@ -99,13 +100,21 @@ impl AggregateOperator for Avg {
         Ok(())
     }
 
-    fn update(&mut self, data: &RecordBatch, idx: usize) -> Result<()> {
-        let col = self.col_expr.evaluate(data)?.into_array();
+    fn argument(&self, data: &RecordBatch) -> Result<ArrayRef> {
+        Ok(self.col_expr.evaluate(data)?.into_array())
+    }
+
+    fn update(&mut self, col: &ArrayRef, idx: usize) -> Result<()> {
         match col.data_type() {
             DataType::Int64 => update_value!(col, Int64Type, idx, self),
             DataType::UInt64 => update_value!(col, UInt64Type, idx, self),
             DataType::Float64 => update_value!(col, Float64Type, idx, self),
-            _ => unimplemented!(),
+            _ => {
+                return Err(ErrorCode::NotSupported(format!(
+                    "Avg func for {:?} is not supported",
+                    col.data_type()
+                )))
+            }
         }
         Ok(())
     }
@@ -118,4 +127,8 @@ impl AggregateOperator for Avg {
         self.sum = 0.0;
         self.cnt = 0;
     }
+
+    fn clone_box(&self) -> Box<dyn AggregateOperator> {
+        Box::new(self.clone())
+    }
 }
@@ -1,8 +1,11 @@
+use std::collections::HashSet;
+
+use arrow::array::ArrayRef;
 use arrow::datatypes::DataType;
 
 use arrow::record_batch::RecordBatch;
 
-use super::AggregateOperator;
+use super::{group_value, AggregateOperator, GroupValue};
 use crate::error::ErrorCode;
 use crate::logical_plan::expression::ScalarValue;
 use crate::logical_plan::schema::NaiveField;
@@ -13,13 +16,45 @@ use crate::Result;
 
 #[derive(Debug, Clone)]
 pub struct Count {
-    cnt: u64,
+    cnt: i64,
     col_expr: ColumnExpr,
+    distinct: bool,
+    seen: HashSet<GroupValue>,  // count(DISTINCT ...) 已经出现过的非空值
 }
 
 impl Count {
-    pub fn create(col_expr: ColumnExpr) -> Box<dyn AggregateOperator> {
-        Box::new(Self { cnt: 0, col_expr })
+    pub fn create(col_expr: ColumnExpr, distinct: bool) -> Box<dyn AggregateOperator> {
+        Box::new(Self {
+            cnt: 0,
+            col_expr,
+            distinct,
+            seen: HashSet::new(),
+        })
+    }
+
+    fn name(&self, col: &str) -> String {
+        if self.distinct {
+            format!("count(distinct {})", col)
+        } else {
+            format!("count({})", col)
+        }
+    }
+
+    // 记录 idx 行的值 null 不计入
+    fn insert_distinct(&mut self, col: &ArrayRef, idx: usize) -> Result<()> {
+        match col.data_type() {
+            DataType::Int64 | DataType::UInt64 | DataType::Utf8 => {}
+            other => {
+                return Err(ErrorCode::NotSupported(format!(
+                    "count(DISTINCT) only support `Int64`, `UInt64`, `String`, found {}",
+                    other
+                )))
+            }
+        }
+        if let Some(val) = group_value(col, idx) {
+            self.seen.insert(val);
+        }
+        Ok(())
     }
 }
 
@@ -30,8 +65,8 @@ impl AggregateOperator for Count {
             let field = schema.field_with_unqualified_name(name)?;
             return Ok(NaiveField::new(
                 None,
-                format!("count({})", field.name()).as_str(),
-                DataType::UInt64,
+                self.name(field.name()).as_str(),
+                DataType::Int64,
                 false,
             ));
         }
@@ -40,8 +75,8 @@ impl AggregateOperator for Count {
             let field = schema.field(*idx);
             return Ok(NaiveField::new(
                 None,
-                format!("count({})", field.name()).as_str(),
-                DataType::UInt64,
+                self.name(field.name()).as_str(),
+                DataType::Int64,
                 false,
             ));
         }
@@ -53,12 +88,24 @@ impl AggregateOperator for Count {
 
     fn update_batch(&mut self, data: &RecordBatch) -> Result<()> {
         let col = self.col_expr.evaluate(data)?.into_array();
-        self.cnt += (col.len() - col.null_count()) as u64;
+        if self.distinct {
+            for idx in 0..col.len() {
+                self.insert_distinct(&col, idx)?;
+            }
+            return Ok(());
+        }
+        self.cnt += (col.len() - col.null_count()) as i64;
         Ok(())
     }
 
-    fn update(&mut self, data: &RecordBatch, idx: usize) -> Result<()> {
-        let col = self.col_expr.evaluate(data)?.into_array();
+    fn argument(&self, data: &RecordBatch) -> Result<ArrayRef> {
+        Ok(self.col_expr.evaluate(data)?.into_array())
+    }
+
+    fn update(&mut self, col: &ArrayRef, idx: usize) -> Result<()> {
+        if self.distinct {
+            return self.insert_distinct(col, idx);
+        }
         if !col.is_null(idx) {
             self.cnt += 1;
         }
@@ -66,10 +113,35 @@ impl AggregateOperator for Count {
     }
 
     fn evaluate(&self) -> Result<ScalarValue> {
-        Ok(ScalarValue::UInt64(Some(self.cnt)))
+        if self.distinct {
+            return Ok(ScalarValue::Int64(Some(self.seen.len() as i64)));
+        }
+        Ok(ScalarValue::Int64(Some(self.cnt)))
     }
 
     fn clear_state(&mut self) {
         self.cnt = 0;
+        self.seen.clear();
+    }
+
+    fn clone_box(&self) -> Box<dyn AggregateOperator> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_of_a_string_column_is_int64() {
+        let schema = NaiveSchema::new(vec![NaiveField::new(None, "name", DataType::Utf8, true)]);
+        for distinct in [false, true] {
+            let count = Count::create(ColumnExpr { name: None, idx: Some(0) }, distinct);
+            let field = count.data_field(&schema).unwrap();
+            assert_eq!(field.data_type(), &DataType::Int64);
+            assert!(!field.is_nullable());
+            assert!(matches!(count.evaluate().unwrap(), ScalarValue::Int64(Some(0))));
+        }
     }
 }
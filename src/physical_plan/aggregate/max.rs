@@ -1,5 +1,7 @@
 use arrow::array::Array;
+use arrow::array::ArrayRef;
 use arrow::array::PrimitiveArray;
+use arrow::array::StringArray;
 use arrow::datatypes::DataType;
 
 use arrow::datatypes::Float64Type;
@@ -21,17 +23,41 @@ use crate::Result;
 pub struct Max {
     // TODO(veeupup): should use generic type for Int64, UInt Float64
     val: OrderedFloat<f64>,
+    // 字符串列按字典序比较 没有非空值时为 None
+    str_val: Option<String>,
+    // 参数的类型 字符串列的结果仍然是字符串
+    data_type: DataType,
     // physical column
     col_expr: ColumnExpr,
 }
 
 impl Max {
-    pub fn create(col_expr: ColumnExpr) -> Box<dyn AggregateOperator> {
+    pub fn create(col_expr: ColumnExpr, data_type: DataType) -> Box<dyn AggregateOperator> {
         Box::new(Self {
             val: OrderedFloat::from(f64::MIN),
+            str_val: None,
+            data_type,
             col_expr,
         })
     }
+
+    fn update_str(&mut self, val: &str) {
+        if self.str_val.as_ref().is_none_or(|cur| val > cur.as_str()) {
+            self.str_val = Some(val.to_string());
+        }
+    }
+
+    // 字符串列没有非空值时结果为 NULL
+    fn nullable(&self) -> bool {
+        self.data_type == DataType::Utf8
+    }
+
+    fn result_type(&self) -> DataType {
+        match self.data_type {
+            DataType::Utf8 => DataType::Utf8,
+            _ => DataType::Float64,
+        }
+    }
 }
 
 macro_rules! update_match {
@@ -66,8 +92,8 @@ impl AggregateOperator for Max {
             return Ok(NaiveField::new(
                 None,
                 format!("max({})", field.name()).as_str(),
-                DataType::Float64,
-                false,
+                self.result_type(),
+                self.nullable(),
             ));
         }
 
@@ -76,8 +102,8 @@ impl AggregateOperator for Max {
             return Ok(NaiveField::new(
                 None,
                 format!("max({})", field.name()).as_str(),
-                DataType::Float64,
-                false,
+                self.result_type(),
+                self.nullable(),
             ));
         }
 
@@ -92,6 +118,12 @@ impl AggregateOperator for Max {
             DataType::Int64 => update_match!(col, Int64Type, self),
             DataType::UInt64 => update_match!(col, UInt64Type, self),
             DataType::Float64 => update_match!(col, Float64Type, self),
+            DataType::Utf8 => {
+                let col = col.as_any().downcast_ref::<StringArray>().unwrap();
+                for val in col.into_iter().flatten() {
+                    self.update_str(val);
+                }
+            }
             _ => {
                 return Err(ErrorCode::NotSupported(format!(
                     "Max func for {:?} is not supported",
@@ -103,22 +135,44 @@ impl AggregateOperator for Max {
         Ok(())
     }
 
-    fn update(&mut self, data: &RecordBatch, idx: usize) -> Result<()> {
-        let col = self.col_expr.evaluate(data)?.into_array();
+    fn argument(&self, data: &RecordBatch) -> Result<ArrayRef> {
+        Ok(self.col_expr.evaluate(data)?.into_array())
+    }
+
+    fn update(&mut self, col: &ArrayRef, idx: usize) -> Result<()> {
         match col.data_type() {
             DataType::Int64 => update_value!(col, Int64Type, idx, self),
             DataType::UInt64 => update_value!(col, UInt64Type, idx, self),
             DataType::Float64 => update_value!(col, Float64Type, idx, self),
-            _ => unimplemented!(),
+            DataType::Utf8 => {
+                let col = col.as_any().downcast_ref::<StringArray>().unwrap();
+                if !col.is_null(idx) {
+                    self.update_str(col.value(idx));
+                }
+            }
+            _ => {
+                return Err(ErrorCode::NotSupported(format!(
+                    "Max func for {:?} is not supported",
+                    col.data_type()
+                )))
+            }
         }
         Ok(())
     }
 
     fn evaluate(&self) -> Result<ScalarValue> {
+        if self.data_type == DataType::Utf8 {
+            return Ok(ScalarValue::Utf8(self.str_val.clone()));
+        }
         Ok(ScalarValue::Float64(Some(self.val.into())))
     }
 
     fn clear_state(&mut self) {
         self.val = OrderedFloat::from(f64::MIN);
+        self.str_val = None;
+    }
+
+    fn clone_box(&self) -> Box<dyn AggregateOperator> {
+        Box::new(self.clone())
     }
 }
@@ -16,9 +16,11 @@ use super::{concat_batches, PhysicalPlan, PhysicalPlanRef};
 
 use crate::physical_plan::PhysicalExprRef;
 use crate::Result;
-use arrow::array::{PrimitiveArray, StringArray};
-use arrow::datatypes::{DataType, Field, Int64Type, Schema, UInt64Type};
+use arrow::array::{Array, ArrayRef, PrimitiveArray, StringArray, UInt32Array};
+use arrow::compute::take;
+use arrow::datatypes::{DataType, Int64Type, SchemaRef, UInt64Type};
 use arrow::record_batch::RecordBatch;
+use rayon::prelude::*;
 
 #[derive(Debug)]
 pub struct PhysicalAggregatePlan {
@@ -26,74 +28,36 @@ pub struct PhysicalAggregatePlan {
     pub aggr_ops: Mutex<Vec<Box<dyn AggregateOperator>>>,  // 聚合操作集合
     pub input: PhysicalPlanRef,
     pub schema: NaiveSchema,
+    pub max_groups: usize,  // 分组数上限 超过时报错而不是耗尽内存
 }
 
 impl PhysicalAggregatePlan {
+    pub const DEFAULT_MAX_GROUPS: usize = 1_000_000;
+
+    /// schema 为聚合的输出：分组列在前，聚合结果在后
     pub fn create(
         group_expr: Vec<PhysicalExprRef>,
         aggr_ops: Vec<Box<dyn AggregateOperator>>,
         input: PhysicalPlanRef,
+        schema: NaiveSchema,
+        max_groups: usize,
     ) -> PhysicalPlanRef {
-        let schema = input.schema().clone();
         Arc::new(Self {
             group_expr,
             aggr_ops: Mutex::new(aggr_ops),
             input,
             schema,
+            max_groups,
         })
     }
 }
 
-// group by 聚合逻辑
-macro_rules! group_by_datatype {
-    ($VAL: expr, $DT: ty, $GROUP_DT: ty, $GROUP_IDXS: expr, $AGGR_OPS: expr, $SINGLE_BATCH: expr, $SCHEMA: expr, $LEN: expr) => {{
-        // 从分组的列中获取的groupby值的计算数据 primitive array是一个表示基本类型的数组
-        let group_val = $VAL.as_any().downcast_ref::<PrimitiveArray<$DT>>().unwrap();
-        
-        // 初始化分组映射 键是分组的值 值是该分组包含的行的索引列表 
-        let mut group_idxs = HashMap::<$GROUP_DT, Vec<usize>>::new();
-
-        //  遍历数据并进行分组 按其值将数据行的索引分类到不同的分组中 存在则添加，不存在则新建
-        for (idx, val) in group_val.iter().enumerate() {
-            if let Some(val) = val {
-                if let Some(idxs) = group_idxs.get_mut(&val) {
-                    idxs.push(idx);
-                } else {
-                    group_idxs.insert(val, vec![idx]);
-                }
-            }
-        }
-
-        // 对于每一个分组，遍历改组内的数据行，更新聚合操作
-        // signle batch包含了所有的数据 idx是当前在同一个组的索引 根据索引 计算这个组中的全部的数据
-        let mut batches = vec![];
-
-        for group_idx in group_idxs.values() {
-            for idx in group_idx {
-                for i in 0..$LEN {
-                    $AGGR_OPS.get_mut(i).unwrap().update(&$SINGLE_BATCH, *idx)?;
-                }
-            }
-
-            let mut arrays = vec![];
-            // let aggr_ops = self.aggr_ops.lock().unwrap();
-            for aggr_op in $AGGR_OPS.iter() {
-                let x = aggr_op.evaluate()?;
-                arrays.push(x.into_array(1));
-            }
-
-            let record_batch = RecordBatch::try_new($SCHEMA.clone(), arrays)?;
-            batches.push(record_batch);
-
-            // for next group aggregate usage
-            for i in 0..$LEN {
-                $AGGR_OPS.get_mut(i).unwrap().clear_state();
-            }
-        }
-
-        let single_batch = concat_batches(&$SCHEMA, &batches)?;
-        Ok(vec![single_batch])
-    }};
+// 分组键中单列的取值，多个分组列的取值组成一个复合分组键
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum GroupValue {
+    Int64(i64),
+    UInt64(u64),
+    Utf8(String),
 }
 
 impl PhysicalPlan for PhysicalAggregatePlan {
@@ -106,25 +70,20 @@ impl PhysicalPlan for PhysicalAggregatePlan {
     }
 
     fn execute(&self) -> Result<Vec<RecordBatch>> {
-        // output schema
+        // output schema 分组列加上聚合结果列
         let mut aggr_ops = self.aggr_ops.lock().unwrap();
         let len = aggr_ops.len();
-        let mut fields: Vec<Field> = vec![];   // fields 用来存储输出字段的集合，字段的数量由 aggr_ops 的长度决定。
-        for aggr_op in aggr_ops.iter() {
-            fields.push(aggr_op.data_field(self.schema())?.into());
-        }
-        let schema = Arc::new(Schema::new(fields));   // 根据输出字段的确定构建输出的 schema
+        let schema = SchemaRef::from(self.schema.clone());
 
         // 没有Group by的聚合查询 直接计算
         if self.group_expr.is_empty() {
             let batches = self.input.execute()?;
 
             // 对于每个batch的数据，调用每个聚合函数的update_batch方法，更新聚合状态
-            for batch in &batches {
-                for i in 0..len {
-                    aggr_ops.get_mut(i).unwrap().update_batch(batch)?;
-                }
-            }
+            // 各个聚合函数的状态相互独立 在当前线程池中并行计算
+            aggr_ops
+                .par_iter_mut()
+                .try_for_each(|aggr_op| batches.iter().try_for_each(|batch| aggr_op.update_batch(batch)))?;
 
             let mut arrays = vec![];
             for aggr_op in aggr_ops.iter() {
@@ -132,6 +91,11 @@ impl PhysicalPlan for PhysicalAggregatePlan {
                 arrays.push(x.into_array(1));     // 实际上就是一个元组 多个列 列就是fields刚才构建的属性
             }
 
+            // 清空状态 计划可能会被再次执行
+            for i in 0..len {
+                aggr_ops.get_mut(i).unwrap().clear_state();
+            }
+
             // 使用计算得到的 arrays 和生成的 schema 创建一个新的 RecordBatch
             let record_batch = RecordBatch::try_new(schema, arrays)?;
             Ok(vec![record_batch])    
@@ -141,95 +105,162 @@ impl PhysicalPlan for PhysicalAggregatePlan {
             // 将多个batch合并在一起 因为groupby需要遍历整个数据集
             let single_batch = concat_batches(&self.input.schema().clone().into(), &batches)?;
 
-            // 提取groupby的第一个表达式
-            let group_by_expr = &self.group_expr[0];
-
-            let val = group_by_expr.evaluate(&single_batch)?.into_array();
-            // 根据分组值 调用 group_by_datatype! 宏处理
-            // 64位有符号整数 64位无符号整数 可变长度字符串
-            match val.data_type() {
-                DataType::Int64 => group_by_datatype!(
-                    val,
-                    Int64Type,
-                    i64,
-                    group_idxs,
-                    aggr_ops,
-                    single_batch,
-                    schema,
-                    len
-                ),
-                DataType::UInt64 => group_by_datatype!(
-                    val,
-                    UInt64Type,
-                    u64,
-                    group_idxs,
-                    aggr_ops,
-                    single_batch,
-                    schema,
-                    len
-                ),
-                DataType::Utf8 => {
-                    let group_val = val.as_any().downcast_ref::<StringArray>().unwrap();
-                
-                    let mut group_idxs = HashMap::<String, Vec<usize>>::new();
-
-                    // split into different groups
-                    for (idx, val) in group_val.iter().enumerate() {
-                        if let Some(val) = val {
-                            if let Some(idxs) = group_idxs.get_mut(val) {
-                                idxs.push(idx);
-                            } else {
-                                group_idxs.insert(val.to_string(), vec![idx]);
-                            }
-                        }
+            // 计算每个分组表达式 such as `group by department_id, rank`
+            let mut vals = vec![];
+            for group_expr in &self.group_expr {
+                let val = group_expr.evaluate(&single_batch)?.into_array();
+                match val.data_type() {
+                    DataType::Int64 | DataType::UInt64 | DataType::Utf8 => vals.push(val),
+                    _ => {
+                        return Err(ErrorCode::NotSupported(
+                            "group by only support by `Int64`, `UInt64`, `String`".to_string(),
+                        ))
                     }
+                }
+            }
 
-                    // for each group, calculate aggregating value  
-                    // 对于每一个分组，遍历改组内的数据行，更新聚合操作
-                    let mut batches = vec![];
-
-                    for group_idx in group_idxs.values() {
-                        for idx in group_idx {
-                            for i in 0..len {
-                                aggr_ops.get_mut(i).unwrap().update(&single_batch, *idx)?;
-                            }
+            // 按复合分组键把行划分到不同的分组 分组按第一次出现的顺序输出
+            // null 也是一个分组键的取值 分组列为 null 的行归入同一个分组
+            let mut group_ids = HashMap::<Vec<Option<GroupValue>>, usize>::new();
+            let mut group_idxs: Vec<Vec<usize>> = vec![];
+            for idx in 0..single_batch.num_rows() {
+                let key = vals.iter().map(|val| group_value(val, idx)).collect::<Vec<_>>();
+                match group_ids.get(&key) {
+                    Some(id) => group_idxs[*id].push(idx),
+                    None => {
+                        if group_idxs.len() >= self.max_groups {
+                            return Err(ErrorCode::NotSupported(format!(
+                                "too many groups: GROUP BY produces more than {} groups",
+                                group_idxs.len()
+                            )));
                         }
+                        group_ids.insert(key, group_idxs.len());
+                        group_idxs.push(vec![idx]);
+                    }
+                }
+            }
 
-                        let mut arrays = vec![];
-                        // let aggr_ops = self.aggr_ops.lock().unwrap();
-                        for aggr_op in aggr_ops.iter() {
-                            let x = aggr_op.evaluate()?;
-                            arrays.push(x.into_array(1));
+            // 每个聚合函数的参数列只计算一次 各个分组按行号从中取值
+            let args = aggr_ops
+                .iter()
+                .map(|aggr_op| aggr_op.argument(&single_batch))
+                .collect::<Result<Vec<_>>>()?;
+
+            // 对于每一个分组，遍历该组内的数据行，更新聚合操作
+            // 各个分组相互独立 在当前线程池中并行计算 每个分组使用一份新的聚合操作
+            let batches = group_idxs
+                .par_iter()
+                .map(|group_idx| {
+                    let mut group_ops = aggr_ops.iter().map(|aggr_op| aggr_op.clone_box()).collect::<Vec<_>>();
+                    for idx in group_idx {
+                        for (aggr_op, arg) in group_ops.iter_mut().zip(&args) {
+                            aggr_op.update(arg, *idx)?;
                         }
+                    }
 
-                        let record_batch = RecordBatch::try_new(schema.clone(), arrays)?;
-                        batches.push(record_batch);
-
-                        // for next group aggregate usage
-                        for i in 0..len {
-                            aggr_ops.get_mut(i).unwrap().clear_state();
-                        }
+                    // 输出的前几列是分组列的值
+                    let mut arrays = vec![];
+                    for val in &vals {
+                        arrays.push(group_key(val, group_idx[0])?);
+                    }
+                    for aggr_op in &group_ops {
+                        let x = aggr_op.evaluate()?;
+                        arrays.push(x.into_array(1));
                     }
+                    Ok(RecordBatch::try_new(schema.clone(), arrays)?)
+                })
+                .collect::<Result<Vec<_>>>()?;
 
-                    let single_batch = concat_batches(&schema, &batches)?;
-                    Ok(vec![single_batch])
-                }
-                _ => Err(ErrorCode::NotSupported(
-                    "group by only support by `Int64`, `UInt64`, `String`".to_string(),
-                )),
-            }
+            let single_batch = concat_batches(&schema, &batches)?;
+            Ok(vec![single_batch])
         }
     }
 }
 
-pub trait AggregateOperator: Debug {
+// 取出分组列在 idx 行的值 为 null 时返回 None
+// 其他类型在计算分组列时已经报错
+fn group_value(val: &ArrayRef, idx: usize) -> Option<GroupValue> {
+    if val.is_null(idx) {
+        return None;
+    }
+    match val.data_type() {
+        DataType::Int64 => {
+            let arr = val.as_any().downcast_ref::<PrimitiveArray<Int64Type>>().unwrap();
+            Some(GroupValue::Int64(arr.value(idx)))
+        }
+        DataType::UInt64 => {
+            let arr = val.as_any().downcast_ref::<PrimitiveArray<UInt64Type>>().unwrap();
+            Some(GroupValue::UInt64(arr.value(idx)))
+        }
+        DataType::Utf8 => {
+            let arr = val.as_any().downcast_ref::<StringArray>().unwrap();
+            Some(GroupValue::Utf8(arr.value(idx).to_string()))
+        }
+        _ => None,
+    }
+}
+
+// 取出分组列在 idx 行的值 作为该分组输出行的分组列
+fn group_key(val: &ArrayRef, idx: usize) -> Result<ArrayRef> {
+    Ok(take(val.as_ref(), &UInt32Array::from(vec![idx as u32]), None)?)
+}
+
+pub trait AggregateOperator: Debug + Send + Sync {
+    #[allow(unused)]
     fn data_field(&self, schema: &NaiveSchema) -> Result<NaiveField>;
 
     fn update_batch(&mut self, data: &RecordBatch) -> Result<()>;
 
-    fn update(&mut self, data: &RecordBatch, idx: usize) -> Result<()>;
+    /// 计算聚合函数的参数列 分组聚合时对整个输入只计算一次
+    fn argument(&self, data: &RecordBatch) -> Result<ArrayRef>;
+
+    /// 用参数列中 idx 行的值更新聚合状态
+    fn update(&mut self, col: &ArrayRef, idx: usize) -> Result<()>;
 
     fn evaluate(&self) -> Result<ScalarValue>;
 
     fn clear_state(&mut self);
+
+    /// 复制一个聚合操作 并行计算各个分组时每个分组使用自己的一份
+    fn clone_box(&self) -> Box<dyn AggregateOperator>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::ColumnExpr;
+    use arrow::array::UInt64Array;
+    use arrow::datatypes::{Field, Schema};
+
+    #[test]
+    fn min_and_max_of_a_string_column_are_nullable() {
+        let schema = NaiveSchema::new(vec![
+            NaiveField::new(None, "name", DataType::Utf8, false),
+            NaiveField::new(None, "id", DataType::Int64, false),
+        ]);
+        let column = |idx| ColumnExpr { name: None, idx: Some(idx) };
+        let operators = [
+            min::Min::create(column(0), DataType::Utf8),
+            max::Max::create(column(0), DataType::Utf8),
+        ];
+        for op in &operators {
+            let field = op.data_field(&schema).unwrap();
+            assert_eq!(field.data_type(), &DataType::Utf8, "{}", field.name());
+            // 没有非空值时结果为 NULL
+            assert!(field.is_nullable(), "{}", field.name());
+            assert!(matches!(op.evaluate().unwrap(), ScalarValue::Utf8(None)));
+        }
+        let field = min::Min::create(column(1), DataType::Int64).data_field(&schema).unwrap();
+        assert_eq!(field.data_type(), &DataType::Float64);
+    }
+
+    #[test]
+    fn sum_of_an_unsigned_column_stays_unsigned() {
+        let schema = Arc::new(Schema::new(vec![Field::new("n", DataType::UInt64, true)]));
+        let column: ArrayRef = Arc::new(UInt64Array::from(vec![Some(2), None, Some(3)]));
+        let batch = RecordBatch::try_new(schema, vec![column]).unwrap();
+        let mut sum = sum::Sum::create(ColumnExpr { name: None, idx: Some(0) }, DataType::UInt64);
+        sum.update_batch(&batch).unwrap();
+        assert!(matches!(sum.evaluate().unwrap(), ScalarValue::UInt64(Some(5))));
+    }
 }
@@ -1,4 +1,5 @@
 use arrow::array::Array;
+use arrow::array::ArrayRef;
 use arrow::array::PrimitiveArray;
 use arrow::datatypes::DataType;
 
@@ -19,38 +20,54 @@ use crate::Result;
 #[derive(Debug, Clone)]
 pub struct Sum {
     sum: f64,      // 初始值为0
+    // 整数列的和 使用 i128 累加 计算结果时再检查是否溢出
+    int_sum: i128,
+    // 参数的类型 整数列的结果仍然是整数
+    data_type: DataType,
     // physical column 
     col_expr: ColumnExpr,
 }
 
 impl Sum {
-    pub fn create(col_expr: ColumnExpr) -> Box<dyn AggregateOperator> {
-        Box::new(Self { sum: 0.0, col_expr })
+    pub fn create(col_expr: ColumnExpr, data_type: DataType) -> Box<dyn AggregateOperator> {
+        Box::new(Self {
+            sum: 0.0,
+            int_sum: 0,
+            data_type,
+            col_expr,
+        })
+    }
+
+    fn result_type(&self) -> DataType {
+        match self.data_type {
+            DataType::Int64 | DataType::UInt64 => self.data_type.clone(),
+            _ => DataType::Float64,
+        }
     }
 }
 
 macro_rules! update_match {
-    ($COL: expr, $DT: ty, $SELF: expr) => {{
+    ($COL: expr, $DT: ty, $SELF: expr, $SUM: ident, $AS: ty) => {{
         // 将 col（列）转换为 PrimitiveArray 类型，然后遍历列中的值并累加到 self.sum。
         let col = $COL.as_any().downcast_ref::<PrimitiveArray<$DT>>().unwrap(); 
         for val in col.into_iter().flatten() {   // flatten() 是用来过滤掉 null 值，仅对非空数据进行累加
-            $SELF.sum += val as f64;
+            $SELF.$SUM += val as $AS;
         }
     }};
 }
 
 // 针对逐行更新操作，给定索引 idx，更新 self.sum 值
 macro_rules! update_value {
-    ($COL: expr, $DT: ty, $IDX: expr, $SELF: expr) => {{
+    ($COL: expr, $DT: ty, $IDX: expr, $SELF: expr, $SUM: ident, $AS: ty) => {{
         let col = $COL.as_any().downcast_ref::<PrimitiveArray<$DT>>().unwrap();
         if !col.is_null($IDX) {
-            $SELF.sum += col.value($IDX) as f64;
+            $SELF.$SUM += col.value($IDX) as $AS;
         }
     }};
 }
 
 impl AggregateOperator for Sum {
-    // 根据列名或索引从模式（NaiveSchema）中查找对应的字段，并生成一个新字段，类型为 Float64，表示求和结果。
+    // 根据列名或索引从模式（NaiveSchema）中查找对应的字段，并生成一个新字段，整数列的类型不变，其余为 Float64，表示求和结果。
     fn data_field(&self, schema: &NaiveSchema) -> Result<NaiveField> {
         // find by name
         if let Some(name) = &self.col_expr.name {
@@ -58,7 +75,7 @@ impl AggregateOperator for Sum {
             return Ok(NaiveField::new(
                 None,
                 format!("sum({})", field.name()).as_str(),
-                DataType::Float64,
+                self.result_type(),
                 false,
             ));
         }
@@ -68,7 +85,7 @@ impl AggregateOperator for Sum {
             return Ok(NaiveField::new(
                 None,
                 format!("sum({})", field.name()).as_str(),
-                DataType::Float64,
+                self.result_type(),
                 false,
             ));
         }
@@ -83,9 +100,9 @@ impl AggregateOperator for Sum {
     fn update_batch(&mut self, data: &RecordBatch) -> Result<()> {
         let col = self.col_expr.evaluate(data)?.into_array();
         match col.data_type() {
-            DataType::Int64 => update_match!(col, Int64Type, self),
-            DataType::UInt64 => update_match!(col, UInt64Type, self),
-            DataType::Float64 => update_match!(col, Float64Type, self),
+            DataType::Int64 => update_match!(col, Int64Type, self, int_sum, i128),
+            DataType::UInt64 => update_match!(col, UInt64Type, self, int_sum, i128),
+            DataType::Float64 => update_match!(col, Float64Type, self, sum, f64),
             _ => {
                 return Err(ErrorCode::NotSupported(format!(
                     "Sum func for {:?} is not supported",
@@ -97,28 +114,50 @@ impl AggregateOperator for Sum {
         Ok(())
     }
 
+    fn argument(&self, data: &RecordBatch) -> Result<ArrayRef> {
+        Ok(self.col_expr.evaluate(data)?.into_array())
+    }
+
     // update 方法是针对逐行数据更新的，它处理单个数据行的更新。
     // 根据数据类型，调用相应的 update_value 宏，通过索引 idx 获取该行的列值并更新总和。
-    fn update(&mut self, data: &RecordBatch, idx: usize) -> Result<()> {
-        let col = self.col_expr.evaluate(data)?.into_array();
+    fn update(&mut self, col: &ArrayRef, idx: usize) -> Result<()> {
         match col.data_type() {
-            DataType::Int64 => update_value!(col, Int64Type, idx, self),
-            DataType::UInt64 => update_value!(col, UInt64Type, idx, self),
-            DataType::Float64 => update_value!(col, Float64Type, idx, self),
-            _ => unimplemented!(),
+            DataType::Int64 => update_value!(col, Int64Type, idx, self, int_sum, i128),
+            DataType::UInt64 => update_value!(col, UInt64Type, idx, self, int_sum, i128),
+            DataType::Float64 => update_value!(col, Float64Type, idx, self, sum, f64),
+            _ => {
+                return Err(ErrorCode::NotSupported(format!(
+                    "Sum func for {:?} is not supported",
+                    col.data_type()
+                )))
+            }
         }
         Ok(())
     }
 
     // evaluate 方法返回当前聚合操作的结果（即 sum 字段的值）。
-    // 它将 sum 转换为 ScalarValue::Float64 类型返回，表示聚合结果。
+    // Int64、UInt64 列的结果保持原来的类型，其余为 ScalarValue::Float64，结果超出类型的范围时报错。
     fn evaluate(&self) -> Result<ScalarValue> {
-        Ok(ScalarValue::Float64(Some(self.sum)))
+        let overflow = || ErrorCode::LogicalError(format!("sum overflows {}", self.data_type));
+        match self.data_type {
+            DataType::Int64 => Ok(ScalarValue::Int64(Some(
+                i64::try_from(self.int_sum).map_err(|_| overflow())?,
+            ))),
+            DataType::UInt64 => Ok(ScalarValue::UInt64(Some(
+                u64::try_from(self.int_sum).map_err(|_| overflow())?,
+            ))),
+            _ => Ok(ScalarValue::Float64(Some(self.sum))),
+        }
     }
 
     // clear_state 方法将 sum 重置为 0.0，清除当前的聚合状态，
     // 通常在处理下一批数据时会调用该方法。
     fn clear_state(&mut self) {
         self.sum = 0.0;
+        self.int_sum = 0;
+    }
+
+    fn clone_box(&self) -> Box<dyn AggregateOperator> {
+        Box::new(self.clone())
     }
 }
@@ -4,17 +4,10 @@ use crate::logical_plan::plan::JoinType;
 use crate::logical_plan::schema::NaiveSchema;
 
 use crate::Result;
-use arrow::array::Array;
-use arrow::array::Float64Array;
-use arrow::array::Int64Array;
-use arrow::array::PrimitiveArray;
-use arrow::array::StringArray;
+use arrow::array::ArrayRef;
 use arrow::array::UInt64Array;
-use arrow::datatypes::DataType;
-use arrow::datatypes::Float64Type;
-use arrow::datatypes::Int64Type;
+use arrow::compute;
 use arrow::datatypes::SchemaRef;
-use arrow::datatypes::UInt64Type;
 use arrow::record_batch::RecordBatch;
 use std::sync::Arc;
 
@@ -49,6 +42,7 @@ impl PhysicalPlan for CrossJoin {
         &self.schema
     }
 
+    // 左侧的每一行依次与右侧的每一行组合，按行号从两侧取值 空值和所有类型的列都保持不变
     fn execute(&self) -> Result<Vec<RecordBatch>> {
         let outer_table = self.left.execute()?;
         let inner_table = self.right.execute()?;
@@ -57,121 +51,25 @@ impl PhysicalPlan for CrossJoin {
 
         for outer in &outer_table {
             for inner in &inner_table {
-                let mut columns: Vec<Arc<dyn Array>> = vec![];
                 let left_rows = outer.num_rows();
                 let right_rows = inner.num_rows();
-                for i in 0..self.left.schema().fields().len() {
-                    let array = outer.column(i);
-                    let dt = self.left.schema().field(i).data_type();
-                    match dt {
-                        // TODO(ywq reafctor with macro)
-                        DataType::Int64 => {
-                            let mut t_vec = vec![];
-                            let left_col = array
-                                .as_any()
-                                .downcast_ref::<PrimitiveArray<Int64Type>>()
-                                .unwrap();
-                            for _ in 0..right_rows {
-                                for k in 0..left_col.len() {
-                                    t_vec.push(left_col.value(k))
-                                }
-                            }
-                            columns.push(Arc::new(Int64Array::from(t_vec)));
-                        }
-                        DataType::UInt64 => {
-                            let mut t_vec = vec![];
-                            let left_col = array
-                                .as_any()
-                                .downcast_ref::<PrimitiveArray<UInt64Type>>()
-                                .unwrap();
-                            for _ in 0..right_rows {
-                                for k in 0..left_col.len() {
-                                    t_vec.push(left_col.value(k))
-                                }
-                            }
-                            columns.push(Arc::new(UInt64Array::from(t_vec)));
-                        }
-                        DataType::Float64 => {
-                            let mut t_vec = vec![];
-                            let left_col = array
-                                .as_any()
-                                .downcast_ref::<PrimitiveArray<Float64Type>>()
-                                .unwrap();
-                            for _ in 0..right_rows {
-                                for k in 0..left_col.len() {
-                                    t_vec.push(left_col.value(k))
-                                }
-                            }
-                            columns.push(Arc::new(Float64Array::from(t_vec)));
-                        }
-                        DataType::Utf8 => {
-                            let mut t_vec = vec![];
-                            let left_col = array.as_any().downcast_ref::<StringArray>().unwrap();
-                            for _ in 0..right_rows {
-                                for k in 0..left_col.len() {
-                                    t_vec.push(left_col.value(k))
-                                }
-                            }
-                            columns.push(Arc::new(StringArray::from(t_vec)));
-                        }
-                        _ => unimplemented!(),
+                let mut left_pos = Vec::with_capacity(left_rows * right_rows);
+                let mut right_pos = Vec::with_capacity(left_rows * right_rows);
+                for i in 0..left_rows {
+                    for j in 0..right_rows {
+                        left_pos.push(i as u64);
+                        right_pos.push(j as u64);
                     }
                 }
-                for i in 0..self.right.schema().fields().len() {
-                    let array = inner.column(i);
-                    let dt = self.right.schema().field(i).data_type();
-                    match dt {
-                        DataType::Int64 => {
-                            let mut t_vec = vec![];
-                            let left_col = array
-                                .as_any()
-                                .downcast_ref::<PrimitiveArray<Int64Type>>()
-                                .unwrap();
-                            for _ in 0..left_rows {
-                                for k in 0..left_col.len() {
-                                    t_vec.push(left_col.value(k))
-                                }
-                            }
-                            columns.push(Arc::new(Int64Array::from(t_vec)));
-                        }
-                        DataType::UInt64 => {
-                            let mut t_vec = vec![];
-                            let left_col = array
-                                .as_any()
-                                .downcast_ref::<PrimitiveArray<UInt64Type>>()
-                                .unwrap();
-                            for _ in 0..left_rows {
-                                for k in 0..left_col.len() {
-                                    t_vec.push(left_col.value(k))
-                                }
-                            }
-                            columns.push(Arc::new(UInt64Array::from(t_vec)));
-                        }
-                        DataType::Float64 => {
-                            let mut t_vec = vec![];
-                            let left_col = array
-                                .as_any()
-                                .downcast_ref::<PrimitiveArray<Float64Type>>()
-                                .unwrap();
-                            for _ in 0..left_rows {
-                                for k in 0..left_col.len() {
-                                    t_vec.push(left_col.value(k))
-                                }
-                            }
-                            columns.push(Arc::new(Float64Array::from(t_vec)));
-                        }
-                        DataType::Utf8 => {
-                            let mut t_vec = vec![];
-                            let left_col = array.as_any().downcast_ref::<StringArray>().unwrap();
-                            for _ in 0..left_rows {
-                                for k in 0..left_col.len() {
-                                    t_vec.push(left_col.value(k))
-                                }
-                            }
-                            columns.push(Arc::new(StringArray::from(t_vec)));
-                        }
-                        _ => unimplemented!(),
-                    }
+                let left_pos = UInt64Array::from(left_pos);
+                let right_pos = UInt64Array::from(right_pos);
+
+                let mut columns: Vec<ArrayRef> = vec![];
+                for array in outer.columns() {
+                    columns.push(compute::take(array.as_ref(), &left_pos, None)?);
+                }
+                for array in inner.columns() {
+                    columns.push(compute::take(array.as_ref(), &right_pos, None)?);
                 }
                 // new batch
                 let batch = RecordBatch::try_new(SchemaRef::from(self.schema.clone()), columns)?;
@@ -0,0 +1,62 @@
+use super::{PhysicalExprRef, PhysicalPlan, PhysicalPlanRef};
+use crate::error::Result;
+use crate::logical_plan::schema::NaiveSchema;
+
+use arrow::array::{Array, BooleanArray};
+use arrow::compute::filter_record_batch;
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::array_value_to_string;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// 保留每个去重键第一次出现的行 输入的顺序决定了保留哪一行
+#[derive(Debug, Clone)]
+pub struct PhysicalDistinctOnPlan {
+    input: PhysicalPlanRef,
+    on_expr: Vec<PhysicalExprRef>,
+}
+
+impl PhysicalDistinctOnPlan {
+    pub fn create(input: PhysicalPlanRef, on_expr: Vec<PhysicalExprRef>) -> PhysicalPlanRef {
+        Arc::new(Self { input, on_expr })
+    }
+}
+
+impl PhysicalPlan for PhysicalDistinctOnPlan {
+    fn schema(&self) -> &NaiveSchema {
+        self.input.schema()
+    }
+
+    fn execute(&self) -> Result<Vec<RecordBatch>> {
+        let batches = self.input.execute()?;
+        // 已经出现过的键 跨batch共享 空值作为一个单独的键
+        let mut seen = HashSet::<Vec<Option<String>>>::new();
+
+        let mut ret = vec![];
+        for batch in &batches {
+            let mut keys = vec![];
+            for expr in &self.on_expr {
+                keys.push(expr.evaluate(batch)?.into_array());
+            }
+
+            let mut mask = Vec::with_capacity(batch.num_rows());
+            for row in 0..batch.num_rows() {
+                let mut key = Vec::with_capacity(keys.len());
+                for array in &keys {
+                    if array.is_null(row) {
+                        key.push(None);
+                    } else {
+                        key.push(Some(array_value_to_string(array, row)?));
+                    }
+                }
+                mask.push(seen.insert(key));
+            }
+            ret.push(filter_record_batch(batch, &BooleanArray::from(mask))?);
+        }
+        Ok(ret)
+    }
+
+    fn children(&self) -> Result<Vec<PhysicalPlanRef>> {
+        Ok(vec![self.input.clone()])
+    }
+}
@@ -1,18 +1,27 @@
 use arrow::{
-    array::{BooleanArray, PrimitiveArray},
+    array::{ArrayRef, BooleanArray, PrimitiveArray, StringArray},
     compute::{
-        and_kleene, eq_dyn, gt_dyn, gt_eq_dyn,
+        and_kleene, cast, eq_dyn, eq_scalar, eq_dyn_bool_scalar, eq_dyn_scalar, eq_dyn_utf8_scalar, gt_dyn,
+        gt_dyn_bool_scalar, gt_dyn_scalar, gt_dyn_utf8_scalar, gt_eq_dyn, gt_eq_dyn_bool_scalar,
+        gt_eq_dyn_scalar, gt_eq_dyn_utf8_scalar,
         kernels::arithmetic::{add, divide, modulus, multiply, subtract},
-        lt_dyn, lt_eq_dyn, neq_dyn, or_kleene,
+        lt_dyn, lt_dyn_bool_scalar, lt_dyn_scalar, lt_dyn_utf8_scalar, lt_eq_dyn,
+        lt_eq_dyn_bool_scalar, lt_eq_dyn_scalar, lt_eq_dyn_utf8_scalar, neq_dyn,
+        like_utf8, like_utf8_scalar, neq_dyn_bool_scalar, neq_dyn_scalar, neq_dyn_utf8_scalar,
+        nlike_utf8, nlike_utf8_scalar, nullif, or_kleene,
     },
-    datatypes::{DataType, Float64Type, Int64Type, UInt64Type},
+    datatypes::{ArrowPrimitiveType, DataType, Float64Type, Int64Type, UInt64Type},
     record_batch::RecordBatch,
 };
 use std::any::Any;
 use std::sync::Arc;
 
 use super::{PhysicalExpr, PhysicalExprRef};
-use crate::{datatype::ColumnValue, error::ErrorCode, logical_plan::expression::Operator};
+use crate::{
+    datatype::ColumnValue,
+    error::ErrorCode,
+    logical_plan::expression::{Operator, ScalarValue},
+};
 
 macro_rules! compare_bin {
     ($OP:expr, $LEFT: expr, $RIGHT: expr) => {
@@ -22,6 +31,24 @@ macro_rules! compare_bin {
     };
 }
 
+// 数组与常量比较，按常量类型选择 arrow 的 scalar kernel，常量为 NULL 时返回 None 走数组路径
+macro_rules! compare_scalar {
+    ($ARRAY: expr, $SCALAR: expr, $NUM_OP: ident, $UTF8_OP: ident, $BOOL_OP: ident) => {{
+        let ret = match $SCALAR {
+            ScalarValue::Int64(Some(v)) => $NUM_OP($ARRAY, *v),
+            ScalarValue::UInt64(Some(v)) => $NUM_OP($ARRAY, *v),
+            ScalarValue::Float64(Some(v)) => $NUM_OP($ARRAY, *v),
+            ScalarValue::Utf8(Some(v)) => $UTF8_OP($ARRAY, v.as_str()),
+            ScalarValue::Boolean(Some(v)) => $BOOL_OP($ARRAY, *v),
+            _ => return None,
+        };
+        Some(
+            ret.map_err(|e| e.into())
+                .map(|a| ColumnValue::Array(Arc::new(a))),
+        )
+    }};
+}
+
 macro_rules! binary_op {
     ($OP:expr, $LEFT_DT: expr, $RIGHT_DT: expr, $LEFT: expr, $RIGHT: expr, $SELF_OP: expr) => {{
         if $LEFT_DT == DataType::Boolean && $RIGHT_DT == DataType::Boolean {
@@ -38,8 +65,35 @@ macro_rules! binary_op {
     }};
 }
 
+// LIKE 的两侧都必须是字符串，模式中 `%` 匹配任意多个字符，`_` 匹配单个字符
+// 模式是常量时使用 scalar kernel，任意一侧为 NULL 的行结果为 NULL
+macro_rules! like_op {
+    ($OP: ident, $SCALAR_OP: ident, $LEFT: expr, $RIGHT: expr, $SELF_OP: expr) => {{
+        let left_dt = $LEFT.data_type();
+        let right_dt = $RIGHT.data_type();
+        if left_dt != DataType::Utf8 || right_dt != DataType::Utf8 {
+            return Err(ErrorCode::IntervalError(format!(
+                "Cannot evaluate binary expression {:?} with types {:?} and {:?}",
+                $SELF_OP, left_dt, right_dt
+            )));
+        }
+        let left = $LEFT.into_array();
+        let left = left.as_any().downcast_ref::<StringArray>().unwrap();
+        let ret = match $RIGHT {
+            ColumnValue::Const(ScalarValue::Utf8(Some(pattern)), _) => $SCALAR_OP(left, &pattern)?,
+            right => {
+                let right = right.into_array();
+                let right = right.as_any().downcast_ref::<StringArray>().unwrap();
+                $OP(left, right)?
+            }
+        };
+        Ok(ColumnValue::Array(Arc::new(ret)))
+    }};
+}
+
+// 整数使用 checked 运算 溢出时返回错误，而不是 panic（debug）或者结果回绕（release）
 macro_rules! arithemic_op {
-    ($OP:expr, $LEFT_DT: expr, $LEFT: expr, $RIGHT: expr) => {{
+    ($OP:expr, $CHECKED_OP: ident, $LEFT_DT: expr, $LEFT: expr, $RIGHT: expr) => {{
         match $LEFT_DT {
             DataType::Int64 => {
                 let left = $LEFT
@@ -50,7 +104,7 @@ macro_rules! arithemic_op {
                     .as_any()
                     .downcast_ref::<PrimitiveArray<Int64Type>>()
                     .unwrap();
-                let x = $OP(left, right)?;
+                let x = checked_arithmetic(left, right, |l: i64, r| l.$CHECKED_OP(r))?;
                 Ok(ColumnValue::Array(Arc::new(x)))
             }
             DataType::UInt64 => {
@@ -62,7 +116,7 @@ macro_rules! arithemic_op {
                     .as_any()
                     .downcast_ref::<PrimitiveArray<UInt64Type>>()
                     .unwrap();
-                let x = $OP(left, right)?;
+                let x = checked_arithmetic(left, right, |l: u64, r| l.$CHECKED_OP(r))?;
                 Ok(ColumnValue::Array(Arc::new(x)))
             }
             DataType::Float64 => {
@@ -77,11 +131,33 @@ macro_rules! arithemic_op {
                 let x = $OP(left, right)?;
                 Ok(ColumnValue::Array(Arc::new(x)))
             }
-            _ => unimplemented!(),
+            other => Err(ErrorCode::IntervalError(format!(
+                "Cannot evaluate arithmetic expression with type {:?}",
+                other
+            ))),
         }
     }};
 }
 
+// 逐行计算 任意一侧为 NULL 的行结果为 NULL
+fn checked_arithmetic<T: ArrowPrimitiveType>(
+    left: &PrimitiveArray<T>,
+    right: &PrimitiveArray<T>,
+    op: impl Fn(T::Native, T::Native) -> Option<T::Native>,
+) -> crate::Result<PrimitiveArray<T>> {
+    let values = left
+        .iter()
+        .zip(right.iter())
+        .map(|(l, r)| match (l, r) {
+            (Some(l), Some(r)) => op(l, r).map(Some).ok_or_else(|| {
+                ErrorCode::LogicalError(format!("integer overflow in {} arithmetic", T::DATA_TYPE))
+            }),
+            _ => Ok(None),
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+    Ok(values.into_iter().collect())
+}
+
 #[derive(Debug)]
 pub struct PhysicalBinaryExpr {
     left: PhysicalExprRef,
@@ -93,6 +169,191 @@ impl PhysicalBinaryExpr {
     pub fn create(left: PhysicalExprRef, op: Operator, right: PhysicalExprRef) -> PhysicalExprRef {
         Arc::new(Self { left, op, right })
     }
+
+    /// Fast path for comparisons where one side is a constant: use the scalar
+    /// comparison kernels instead of materializing the constant as an array.
+    /// Returns `None` when the fast path doesn't apply.
+    fn evaluate_with_scalar(
+        &self,
+        left: &ColumnValue,
+        right: &ColumnValue,
+    ) -> Option<crate::Result<ColumnValue>> {
+        let (array, scalar, op) = match (left, right) {
+            (ColumnValue::Array(array), ColumnValue::Const(scalar, _)) => {
+                (array, scalar, self.op.clone())
+            }
+            // 常量在左侧时交换比较方向，例如 `1 < a` 等价于 `a > 1`
+            (ColumnValue::Const(scalar, _), ColumnValue::Array(array)) => {
+                (array, scalar, swap_comparison(&self.op)?)
+            }
+            _ => return None,
+        };
+        let array = array.as_ref();
+
+        match op {
+            Operator::Eq => compare_scalar!(
+                array,
+                scalar,
+                eq_dyn_scalar,
+                eq_dyn_utf8_scalar,
+                eq_dyn_bool_scalar
+            ),
+            Operator::NotEq => compare_scalar!(
+                array,
+                scalar,
+                neq_dyn_scalar,
+                neq_dyn_utf8_scalar,
+                neq_dyn_bool_scalar
+            ),
+            Operator::Lt => compare_scalar!(
+                array,
+                scalar,
+                lt_dyn_scalar,
+                lt_dyn_utf8_scalar,
+                lt_dyn_bool_scalar
+            ),
+            Operator::LtEq => compare_scalar!(
+                array,
+                scalar,
+                lt_eq_dyn_scalar,
+                lt_eq_dyn_utf8_scalar,
+                lt_eq_dyn_bool_scalar
+            ),
+            Operator::Gt => compare_scalar!(
+                array,
+                scalar,
+                gt_dyn_scalar,
+                gt_dyn_utf8_scalar,
+                gt_dyn_bool_scalar
+            ),
+            Operator::GtEq => compare_scalar!(
+                array,
+                scalar,
+                gt_eq_dyn_scalar,
+                gt_eq_dyn_utf8_scalar,
+                gt_eq_dyn_bool_scalar
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// 日期列与字符串常量比较时（例如 `hire_date >= '2020-01-01'`），先把字符串转换成日期，
+/// 无法解析的字符串会变成 NULL
+fn coerce_date_literal(value: ColumnValue, target: &DataType) -> crate::Result<ColumnValue> {
+    match (&value, target) {
+        (ColumnValue::Const(ScalarValue::Utf8(_), _), DataType::Date32 | DataType::Date64) => {
+            Ok(ColumnValue::Array(cast(&value.into_array(), target)?))
+        }
+        _ => Ok(value),
+    }
+}
+
+/// 整数常量与 UInt64/Float64 的值比较或计算时（例如 `n BETWEEN 1 AND 3`），把常量转换为对方的类型
+fn coerce_numeric_literal(value: ColumnValue, target: &DataType) -> ColumnValue {
+    match (&value, target) {
+        (ColumnValue::Const(ScalarValue::Int64(Some(v)), n), DataType::UInt64) if *v >= 0 => {
+            ColumnValue::Const(ScalarValue::UInt64(Some(*v as u64)), *n)
+        }
+        (ColumnValue::Const(ScalarValue::Int64(Some(v)), n), DataType::Float64) => {
+            ColumnValue::Const(ScalarValue::Float64(Some(*v as f64)), *n)
+        }
+        (ColumnValue::Const(ScalarValue::UInt64(Some(v)), n), DataType::Float64) => {
+            ColumnValue::Const(ScalarValue::Float64(Some(*v as f64)), *n)
+        }
+        _ => value,
+    }
+}
+
+/// 两个不同的数值类型的共同类型：有一侧是 Float64 时为 Float64，Int64 与 UInt64 为 Int64，
+/// 有一侧不是数值时返回 None
+pub(crate) fn common_numeric_type(left: &DataType, right: &DataType) -> Option<DataType> {
+    let is_numeric = |dt: &DataType| matches!(dt, DataType::Int64 | DataType::UInt64 | DataType::Float64);
+    if !is_numeric(left) || !is_numeric(right) {
+        None
+    } else if left == &DataType::Float64 || right == &DataType::Float64 {
+        Some(DataType::Float64)
+    } else {
+        Some(DataType::Int64)
+    }
+}
+
+/// 不同的数值类型之间计算或比较时，先转换为 common_numeric_type 给出的共同类型
+fn coerce_numeric_types(
+    left: ColumnValue,
+    right: ColumnValue,
+) -> crate::Result<(ColumnValue, ColumnValue)> {
+    let left_dt = left.data_type();
+    let right_dt = right.data_type();
+    let target = match common_numeric_type(&left_dt, &right_dt) {
+        Some(target) if left_dt != right_dt => target,
+        _ => return Ok((left, right)),
+    };
+    Ok((cast_numeric(left, &target)?, cast_numeric(right, &target)?))
+}
+
+fn cast_numeric(value: ColumnValue, target: &DataType) -> crate::Result<ColumnValue> {
+    if value.data_type() == *target {
+        return Ok(value);
+    }
+    // 常量仍然保持为常量 比较时可以使用 scalar kernel
+    let (scalar, num_rows) = match (&value, target) {
+        (ColumnValue::Const(ScalarValue::Int64(v), num_rows), DataType::Float64) => {
+            (ScalarValue::Float64(v.map(|v| v as f64)), *num_rows)
+        }
+        (ColumnValue::Const(ScalarValue::UInt64(v), num_rows), DataType::Float64) => {
+            (ScalarValue::Float64(v.map(|v| v as f64)), *num_rows)
+        }
+        (ColumnValue::Const(ScalarValue::UInt64(v), num_rows), DataType::Int64) => {
+            (ScalarValue::Int64(v.and_then(|v| i64::try_from(v).ok())), *num_rows)
+        }
+        _ => return Ok(ColumnValue::Array(cast(&value.into_array(), target)?)),
+    };
+    Ok(ColumnValue::Const(scalar, num_rows))
+}
+
+/// 除法和取模中除数为 0 的行结果为 NULL，而不是报错
+fn null_if_zero(array: &ArrayRef) -> crate::Result<ArrayRef> {
+    let ret: ArrayRef = match array.data_type() {
+        DataType::Int64 => {
+            let array = array.as_any().downcast_ref::<PrimitiveArray<Int64Type>>().unwrap();
+            Arc::new(nullif(array, &eq_scalar(array, 0)?)?)
+        }
+        DataType::UInt64 => {
+            let array = array.as_any().downcast_ref::<PrimitiveArray<UInt64Type>>().unwrap();
+            Arc::new(nullif(array, &eq_scalar(array, 0)?)?)
+        }
+        DataType::Float64 => {
+            let array = array.as_any().downcast_ref::<PrimitiveArray<Float64Type>>().unwrap();
+            Arc::new(nullif(array, &eq_scalar(array, 0.0)?)?)
+        }
+        _ => array.clone(),
+    };
+    Ok(ret)
+}
+
+/// NULL 常量没有具体的类型，转换为另一侧类型的全空数组，
+/// 例如 `a AND NULL` 按照三值逻辑计算，`a = NULL` 的结果全部为 NULL
+fn coerce_null_literal(value: ColumnValue, target: &DataType) -> crate::Result<ColumnValue> {
+    match &value {
+        ColumnValue::Const(ScalarValue::Null, _) => {
+            Ok(ColumnValue::Array(value.into_array_of_type(target)?))
+        }
+        _ => Ok(value),
+    }
+}
+
+/// The comparison that gives the same result once both operands are swapped
+fn swap_comparison(op: &Operator) -> Option<Operator> {
+    match op {
+        Operator::Eq => Some(Operator::Eq),
+        Operator::NotEq => Some(Operator::NotEq),
+        Operator::Lt => Some(Operator::Gt),
+        Operator::LtEq => Some(Operator::GtEq),
+        Operator::Gt => Some(Operator::Lt),
+        Operator::GtEq => Some(Operator::LtEq),
+        _ => None,
+    }
 }
 
 impl PhysicalExpr for PhysicalBinaryExpr {
@@ -103,6 +364,22 @@ impl PhysicalExpr for PhysicalBinaryExpr {
     fn evaluate(&self, input: &RecordBatch) -> crate::Result<ColumnValue> {
         let left_value = self.left.evaluate(input)?;
         let right_value = self.right.evaluate(input)?;
+        let left_value = coerce_date_literal(left_value, &right_value.data_type())?;
+        let right_value = coerce_date_literal(right_value, &left_value.data_type())?;
+        let left_value = coerce_numeric_literal(left_value, &right_value.data_type());
+        let right_value = coerce_numeric_literal(right_value, &left_value.data_type());
+        let (left_value, right_value) = coerce_numeric_types(left_value, right_value)?;
+        let left_value = match &right_value {
+            ColumnValue::Const(ScalarValue::Null, _) => left_value,
+            _ => coerce_null_literal(left_value, &right_value.data_type())?,
+        };
+        let right_value = coerce_null_literal(right_value, &left_value.data_type())?;
+
+        match self.op {
+            Operator::Like => return like_op!(like_utf8, like_utf8_scalar, left_value, right_value, self.op),
+            Operator::NotLike => return like_op!(nlike_utf8, nlike_utf8_scalar, left_value, right_value, self.op),
+            _ => {}
+        }
 
         let left_data_type = left_value.data_type();
         let right_data_type = right_value.data_type();
@@ -113,7 +390,9 @@ impl PhysicalExpr for PhysicalBinaryExpr {
             )));
         }
 
-        // TODO(veeupup): speed up if left_value or right_value is scalar
+        if let Some(ret) = self.evaluate_with_scalar(&left_value, &right_value) {
+            return ret;
+        }
 
         let left_array = left_value.into_array();
         let right_array = right_value.into_array();
@@ -141,11 +420,95 @@ impl PhysicalExpr for PhysicalBinaryExpr {
                 right_array,
                 Operator::Or
             ),
-            Operator::Plus => arithemic_op!(add, left_data_type, left_array, right_array),
-            Operator::Minus => arithemic_op!(subtract, left_data_type, left_array, right_array),
-            Operator::Multiply => arithemic_op!(multiply, left_data_type, left_array, right_array),
-            Operator::Divide => arithemic_op!(divide, left_data_type, left_array, right_array),
-            Operator::Modulos => arithemic_op!(modulus, left_data_type, left_array, right_array),
+            Operator::Plus => arithemic_op!(add, checked_add, left_data_type, left_array, right_array),
+            Operator::Minus => arithemic_op!(subtract, checked_sub, left_data_type, left_array, right_array),
+            Operator::Multiply => arithemic_op!(multiply, checked_mul, left_data_type, left_array, right_array),
+            // 整数先转换为 Float64 再相除
+            Operator::Divide => {
+                let (left_data_type, left_array, right_array) = match left_data_type {
+                    DataType::Int64 | DataType::UInt64 => (
+                        DataType::Float64,
+                        cast(&left_array, &DataType::Float64)?,
+                        cast(&right_array, &DataType::Float64)?,
+                    ),
+                    _ => (left_data_type, left_array, right_array),
+                };
+                let right_array = null_if_zero(&right_array)?;
+                arithemic_op!(divide, checked_div, left_data_type, left_array, right_array)
+            }
+            Operator::IntegerDivide => {
+                let right_array = null_if_zero(&right_array)?;
+                arithemic_op!(divide, checked_div, left_data_type, left_array, right_array)
+            }
+            Operator::Modulos => {
+                let right_array = null_if_zero(&right_array)?;
+                arithemic_op!(modulus, checked_rem, left_data_type, left_array, right_array)
+            }
+            Operator::Like | Operator::NotLike => Err(ErrorCode::IntervalError(format!(
+                "Cannot evaluate binary expression {:?} with types {:?} and {:?}",
+                self.op, left_data_type, right_data_type
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::{ColumnExpr, PhysicalLiteralExpr};
+    use arrow::array::Int64Array;
+    use arrow::datatypes::{Field, Schema};
+
+    const COMPARISONS: [Operator; 6] = [
+        Operator::Eq,
+        Operator::NotEq,
+        Operator::Lt,
+        Operator::LtEq,
+        Operator::Gt,
+        Operator::GtEq,
+    ];
+
+    fn evaluate(left: PhysicalExprRef, op: Operator, right: PhysicalExprRef, batch: &RecordBatch) -> Vec<Option<bool>> {
+        let result = PhysicalBinaryExpr::create(left, op, right).evaluate(batch).unwrap().into_array();
+        result.as_any().downcast_ref::<BooleanArray>().unwrap().iter().collect()
+    }
+
+    #[test]
+    fn scalar_fast_path_matches_array_path() {
+        // 每种类型一列数据 加上一列把常量展开后的数组
+        let columns: Vec<(ArrayRef, ScalarValue, ArrayRef)> = vec![
+            (
+                Arc::new(Int64Array::from(vec![Some(1), None, Some(3), Some(5)])),
+                ScalarValue::Int64(Some(3)),
+                Arc::new(Int64Array::from(vec![3; 4])),
+            ),
+            (
+                Arc::new(StringArray::from(vec![Some("a"), None, Some("c"), Some("b")])),
+                ScalarValue::Utf8(Some("b".to_string())),
+                Arc::new(StringArray::from(vec!["b"; 4])),
+            ),
+            (
+                Arc::new(BooleanArray::from(vec![Some(true), None, Some(false), Some(true)])),
+                ScalarValue::Boolean(Some(true)),
+                Arc::new(BooleanArray::from(vec![true; 4])),
+            ),
+        ];
+
+        for (data, scalar, expanded) in columns {
+            let schema = Schema::new(vec![
+                Field::new("data", data.data_type().clone(), true),
+                Field::new("expanded", expanded.data_type().clone(), false),
+            ]);
+            let batch = RecordBatch::try_new(Arc::new(schema), vec![data, expanded]).unwrap();
+            let column = |idx| ColumnExpr::try_create(None, Some(idx)).unwrap();
+            let literal = || PhysicalLiteralExpr::create(scalar.clone());
+            for op in COMPARISONS {
+                let array_path = evaluate(column(0), op.clone(), column(1), &batch);
+                assert_eq!(evaluate(column(0), op.clone(), literal(), &batch), array_path, "{:?}", op);
+                // 常量在左侧时交换比较方向
+                let swapped = evaluate(column(1), op.clone(), column(0), &batch);
+                assert_eq!(evaluate(literal(), op.clone(), column(0), &batch), swapped, "{:?}", op);
+            }
         }
     }
 }
@@ -0,0 +1,68 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use super::{PhysicalExpr, PhysicalExprRef};
+use crate::datatype::{new_null_array, ColumnValue};
+use crate::error::ErrorCode;
+use crate::Result;
+use arrow::array::{Array, BooleanArray};
+use arrow::compute::cast;
+use arrow::compute::kernels::zip::zip;
+use arrow::datatypes::DataType;
+use arrow::record_batch::RecordBatch;
+
+/// `CASE WHEN c1 THEN r1 ... [ELSE r] END`，每一行取第一个成立的条件对应的结果
+/// 条件为 NULL 时视为不成立，没有成立的条件且没有 ELSE 时结果为 NULL
+#[derive(Debug)]
+pub struct PhysicalCaseExpr {
+    when_then: Vec<(PhysicalExprRef, PhysicalExprRef)>,
+    else_expr: Option<PhysicalExprRef>,
+    data_type: DataType,
+}
+
+impl PhysicalCaseExpr {
+    pub fn create(
+        when_then: Vec<(PhysicalExprRef, PhysicalExprRef)>,
+        else_expr: Option<PhysicalExprRef>,
+        data_type: DataType,
+    ) -> PhysicalExprRef {
+        Arc::new(Self {
+            when_then,
+            else_expr,
+            data_type,
+        })
+    }
+}
+
+impl PhysicalExpr for PhysicalCaseExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn evaluate(&self, input: &RecordBatch) -> Result<ColumnValue> {
+        let mut result = match &self.else_expr {
+            Some(expr) => cast(
+                &expr.evaluate(input)?.into_array_of_type(&self.data_type)?,
+                &self.data_type,
+            )?,
+            None => new_null_array(&self.data_type, input.num_rows())?,
+        };
+        // 从最后一个分支开始 前面成立的分支覆盖后面的结果
+        for (when, then) in self.when_then.iter().rev() {
+            let mask = when.evaluate(input)?.into_array_of_type(&DataType::Boolean)?;
+            let mask = mask.as_any().downcast_ref::<BooleanArray>().ok_or_else(|| {
+                ErrorCode::LogicalError(format!(
+                    "CASE WHEN expects a boolean condition, found {}",
+                    mask.data_type()
+                ))
+            })?;
+            let mask = mask.iter().map(|v| Some(v.unwrap_or(false))).collect::<BooleanArray>();
+            let then = cast(
+                &then.evaluate(input)?.into_array_of_type(&self.data_type)?,
+                &self.data_type,
+            )?;
+            result = zip(&mask, then.as_ref(), result.as_ref())?;
+        }
+        Ok(ColumnValue::Array(result))
+    }
+}
@@ -0,0 +1,187 @@
+use std::any::Any;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use super::binary::common_numeric_type;
+use super::{PhysicalExpr, PhysicalExprRef};
+use crate::datatype::ColumnValue;
+use crate::error::ErrorCode;
+use crate::physical_plan::PhysicalPlanRef;
+use crate::Result;
+use arrow::array::{
+    Array, ArrayRef, BooleanArray, Date32Array, Date64Array, Float64Array, Int64Array, StringArray,
+    UInt64Array,
+};
+use arrow::compute::cast;
+use arrow::datatypes::DataType;
+use arrow::record_batch::RecordBatch;
+use ordered_float::OrderedFloat;
+
+/// `expr IN (subquery)`，negated 为 true 时表示 `expr NOT IN (subquery)`
+/// 子查询与外层的行无关，第一次计算时执行一次，结果集合在之后的 batch 中复用；按 SQL 的三值逻辑处理 NULL
+#[derive(Debug)]
+pub struct PhysicalInSubqueryExpr {
+    expr: PhysicalExprRef,
+    subquery: PhysicalPlanRef,
+    negated: bool,
+    // 各个 batch 可能并行计算 加锁保证子查询只执行一次
+    values: Mutex<Option<Arc<SubqueryValues>>>,
+}
+
+impl PhysicalInSubqueryExpr {
+    pub fn create(expr: PhysicalExprRef, subquery: PhysicalPlanRef, negated: bool) -> PhysicalExprRef {
+        Arc::new(Self {
+            expr,
+            subquery,
+            negated,
+            values: Mutex::new(None),
+        })
+    }
+
+    // 执行子查询并按比较的类型建立集合 只在第一次计算时执行
+    fn values(&self, left: &DataType) -> Result<Arc<SubqueryValues>> {
+        let mut cached = self.values.lock().unwrap();
+        if let Some(values) = cached.as_ref() {
+            return Ok(values.clone());
+        }
+        let batches = self.subquery.execute()?;
+        let right = match batches.first() {
+            Some(batch) => batch.column(0).data_type().clone(),
+            None => self.subquery.schema().field(0).data_type().clone(),
+        };
+        let data_type = compare_type(left, &right)?;
+        // 子查询的结果 NULL 不放入集合 只记录是否出现过
+        let mut set = HashSet::new();
+        let mut has_null = false;
+        for batch in &batches {
+            let value = cast(batch.column(0), &data_type)?;
+            for row in 0..value.len() {
+                match in_value(&value, row)? {
+                    Some(value) => {
+                        set.insert(value);
+                    }
+                    None => has_null = true,
+                }
+            }
+        }
+        let values = Arc::new(SubqueryValues {
+            data_type,
+            set,
+            has_null,
+        });
+        *cached = Some(values.clone());
+        Ok(values)
+    }
+}
+
+#[derive(Debug)]
+struct SubqueryValues {
+    data_type: DataType,
+    set: HashSet<InValue>,
+    has_null: bool,
+}
+
+// 转换为比较的类型之后的一个非空值
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum InValue {
+    Boolean(bool),
+    Int64(i64),
+    UInt64(u64),
+    Float64(OrderedFloat<f64>),
+    Utf8(String),
+}
+
+// 两边比较时使用的类型，与二元比较的规则相同：类型相同时不变，不同的数值类型转换为共同的类型，
+// NULL 常量使用子查询的类型，其他类型之间不能比较
+fn compare_type(left: &DataType, right: &DataType) -> Result<DataType> {
+    if left == right || left == &DataType::Null {
+        return Ok(right.clone());
+    }
+    common_numeric_type(left, right).ok_or_else(|| {
+        ErrorCode::IntervalError(format!(
+            "Cannot evaluate IN subquery with types {:?} and {:?}",
+            left, right
+        ))
+    })
+}
+
+// 取出 row 行的值 为 null 时返回 None
+fn in_value(array: &ArrayRef, row: usize) -> Result<Option<InValue>> {
+    // arrow 的 NullArray 没有空值位图 is_null 不能判断出空值
+    if array.is_null(row) || array.data_type() == &DataType::Null {
+        return Ok(None);
+    }
+    let any = array.as_any();
+    let value = match array.data_type() {
+        DataType::Boolean => InValue::Boolean(any.downcast_ref::<BooleanArray>().unwrap().value(row)),
+        DataType::Int64 => InValue::Int64(any.downcast_ref::<Int64Array>().unwrap().value(row)),
+        DataType::UInt64 => InValue::UInt64(any.downcast_ref::<UInt64Array>().unwrap().value(row)),
+        DataType::Float64 => InValue::Float64(OrderedFloat::from(
+            any.downcast_ref::<Float64Array>().unwrap().value(row),
+        )),
+        DataType::Utf8 => InValue::Utf8(any.downcast_ref::<StringArray>().unwrap().value(row).to_string()),
+        DataType::Date32 => InValue::Int64(any.downcast_ref::<Date32Array>().unwrap().value(row) as i64),
+        DataType::Date64 => InValue::Int64(any.downcast_ref::<Date64Array>().unwrap().value(row)),
+        other => {
+            return Err(ErrorCode::NotSupported(format!(
+                "IN subquery does not support values of type {}",
+                other
+            )))
+        }
+    };
+    Ok(Some(value))
+}
+
+impl PhysicalExpr for PhysicalInSubqueryExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn evaluate(&self, input: &RecordBatch) -> Result<ColumnValue> {
+        let array = self.expr.evaluate(input)?.into_array();
+        let values = self.values(array.data_type())?;
+        // NULL 常量的每一行结果都是 NULL 不需要转换类型
+        let array = match array.data_type() {
+            DataType::Null => array,
+            _ => cast(&array, &values.data_type)?,
+        };
+
+        let mut result = Vec::with_capacity(array.len());
+        for row in 0..array.len() {
+            result.push(match in_value(&array, row)? {
+                None => None,
+                Some(value) if values.set.contains(&value) => Some(!self.negated),
+                Some(_) if values.has_null => None,
+                Some(_) => Some(self.negated),
+            });
+        }
+        Ok(ColumnValue::Array(Arc::new(BooleanArray::from(result))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::Catalog;
+    use crate::datasource::test_utils::CountingTable;
+    use crate::optimizer::Optimizer;
+    use crate::planner::QueryPlanner;
+    use crate::sql::parser::SQLParser;
+    use crate::sql::planner::SQLPlanner;
+
+    #[test]
+    fn subquery_is_executed_once_for_all_batches() -> Result<()> {
+        // 3 个 batch 每个 batch 2 行
+        let source = CountingTable::create(3, 2)?;
+        let mut catalog = Catalog::default();
+        catalog.add_new_table("t".to_string(), source.clone())?;
+
+        let (statement, _) = SQLParser::parse("SELECT id FROM t WHERE id IN (SELECT id FROM t WHERE id > 4)")?;
+        let plan = Optimizer::default().optimize(SQLPlanner::new(&catalog).statement_to_plan(statement)?);
+        let batches = QueryPlanner::default().create_physical_plan(&plan)?.execute()?;
+        assert_eq!(batches.iter().map(|batch| batch.num_rows()).sum::<usize>(), 2);
+        // 外层查询和子查询各扫描一次表
+        assert_eq!(source.scans(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use super::{PhysicalExpr, PhysicalExprRef};
+use crate::datatype::ColumnValue;
+use crate::logical_plan::expression::ScalarValue;
+use crate::Result;
+use arrow::array::Array;
+use arrow::compute::{is_not_null, is_null};
+use arrow::record_batch::RecordBatch;
+
+/// `expr IS NULL`，negated 为 true 时表示 `expr IS NOT NULL`
+#[derive(Debug)]
+pub struct PhysicalIsNullExpr {
+    expr: PhysicalExprRef,
+    negated: bool,
+}
+
+impl PhysicalIsNullExpr {
+    pub fn create(expr: PhysicalExprRef, negated: bool) -> PhysicalExprRef {
+        Arc::new(Self { expr, negated })
+    }
+}
+
+impl PhysicalExpr for PhysicalIsNullExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn evaluate(&self, input: &RecordBatch) -> Result<ColumnValue> {
+        let array = match self.expr.evaluate(input)? {
+            ColumnValue::Const(scalar, num_rows) => {
+                let is_null = matches!(
+                    scalar,
+                    ScalarValue::Null
+                        | ScalarValue::Boolean(None)
+                        | ScalarValue::Int64(None)
+                        | ScalarValue::UInt64(None)
+                        | ScalarValue::Float64(None)
+                        | ScalarValue::Utf8(None)
+                );
+                return Ok(ColumnValue::Const(
+                    ScalarValue::Boolean(Some(is_null != self.negated)),
+                    num_rows,
+                ));
+            }
+            ColumnValue::Array(array) => array,
+        };
+        // arrow 数组记录了每个 batch 中空值的个数：没有空值或者全是空值的 batch 直接得到常量结果，
+        // 不必逐行检查空值位图，SelectionPlan 遇到常量谓词时整个 batch 保留或者丢弃
+        if array.null_count() == 0 || array.null_count() == array.len() {
+            let all_null = array.null_count() > 0;
+            return Ok(ColumnValue::Const(
+                ScalarValue::Boolean(Some(all_null != self.negated)),
+                array.len(),
+            ));
+        }
+        let ret = if self.negated {
+            is_not_null(array.as_ref())?
+        } else {
+            is_null(array.as_ref())?
+        };
+        Ok(ColumnValue::Array(Arc::new(ret)))
+    }
+}
@@ -1,10 +1,20 @@
 mod binary;
+mod case;
 mod column;
+mod in_subquery;
+mod is_null;
 mod literal;
+mod not;
+mod scalar_function;
 
 pub use binary::PhysicalBinaryExpr;
+pub use case::PhysicalCaseExpr;
 pub use column::ColumnExpr;
+pub use in_subquery::PhysicalInSubqueryExpr;
+pub use is_null::PhysicalIsNullExpr;
 pub use literal::PhysicalLiteralExpr;
+pub use not::PhysicalNotExpr;
+pub use scalar_function::PhysicalScalarFunctionExpr;
 
 use crate::{datatype::ColumnValue, error::Result};
 use arrow::record_batch::RecordBatch;
@@ -12,7 +22,7 @@ use std::any::Any;
 use std::fmt::Debug;
 use std::sync::Arc;
 
-pub trait PhysicalExpr: Debug {
+pub trait PhysicalExpr: Debug + Send + Sync {
     fn as_any(&self) -> &dyn Any;
 
     fn evaluate(&self, input: &RecordBatch) -> Result<ColumnValue>;
@@ -0,0 +1,54 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use super::{PhysicalExpr, PhysicalExprRef};
+use crate::datatype::ColumnValue;
+use crate::error::ErrorCode;
+use crate::logical_plan::expression::ScalarValue;
+use crate::Result;
+use arrow::array::BooleanArray;
+use arrow::compute::not;
+use arrow::record_batch::RecordBatch;
+
+/// `NOT expr`，对非空值取反 空值保持为空
+#[derive(Debug)]
+pub struct PhysicalNotExpr {
+    expr: PhysicalExprRef,
+}
+
+impl PhysicalNotExpr {
+    pub fn create(expr: PhysicalExprRef) -> PhysicalExprRef {
+        Arc::new(Self { expr })
+    }
+}
+
+impl PhysicalExpr for PhysicalNotExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn evaluate(&self, input: &RecordBatch) -> Result<ColumnValue> {
+        match self.expr.evaluate(input)? {
+            ColumnValue::Const(ScalarValue::Boolean(val), num_rows) => Ok(ColumnValue::Const(
+                ScalarValue::Boolean(val.map(|v| !v)),
+                num_rows,
+            )),
+            ColumnValue::Const(ScalarValue::Null, num_rows) => {
+                Ok(ColumnValue::Const(ScalarValue::Boolean(None), num_rows))
+            }
+            ColumnValue::Array(array) => {
+                let array = array.as_any().downcast_ref::<BooleanArray>().ok_or_else(|| {
+                    ErrorCode::LogicalError(format!(
+                        "NOT expects a boolean operand, found {}",
+                        array.data_type()
+                    ))
+                })?;
+                Ok(ColumnValue::Array(Arc::new(not(array)?)))
+            }
+            ColumnValue::Const(scalar, _) => Err(ErrorCode::LogicalError(format!(
+                "NOT expects a boolean operand, found {:?}",
+                scalar
+            ))),
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use super::{PhysicalExpr, PhysicalExprRef};
+use crate::datatype::ColumnValue;
+use crate::error::ErrorCode;
+use crate::logical_plan::expression::ScalarFunc;
+use crate::Result;
+use arrow::array::{Array, ArrayRef, Float64Array, Int64Array, StringArray};
+use arrow::compute::cast;
+use arrow::datatypes::DataType;
+use arrow::record_batch::RecordBatch;
+
+/// 标量函数 对每一行分别计算 参数为空值时结果为空值
+#[derive(Debug)]
+pub struct PhysicalScalarFunctionExpr {
+    fun: ScalarFunc,
+    args: Vec<PhysicalExprRef>,
+}
+
+impl PhysicalScalarFunctionExpr {
+    pub fn create(fun: ScalarFunc, args: Vec<PhysicalExprRef>) -> PhysicalExprRef {
+        Arc::new(Self { fun, args })
+    }
+}
+
+// 参数转换为字符串数组 NULL 常量转换为全空的字符串数组
+fn string_arg(fun: ScalarFunc, value: ColumnValue) -> Result<StringArray> {
+    let array = value.into_array_of_type(&DataType::Utf8)?;
+    match array.as_any().downcast_ref::<StringArray>() {
+        Some(array) => Ok(StringArray::from(array.data().clone())),
+        None => Err(ErrorCode::LogicalError(format!(
+            "{} expects a string argument, found {}",
+            fun,
+            array.data_type()
+        ))),
+    }
+}
+
+// 整数参数统一转换为 Int64 数组
+fn int_arg(fun: ScalarFunc, value: ColumnValue) -> Result<Int64Array> {
+    let array = cast(&value.into_array_of_type(&DataType::Int64)?, &DataType::Int64)?;
+    match array.as_any().downcast_ref::<Int64Array>() {
+        Some(array) => Ok(Int64Array::from(array.data().clone())),
+        None => Err(ErrorCode::LogicalError(format!(
+            "{} expects an integer argument, found {}",
+            fun,
+            array.data_type()
+        ))),
+    }
+}
+
+// 数值参数转换为 Float64 数组
+fn float_arg(fun: ScalarFunc, value: ColumnValue) -> Result<Float64Array> {
+    let array = cast(&value.into_array_of_type(&DataType::Float64)?, &DataType::Float64)?;
+    match array.as_any().downcast_ref::<Float64Array>() {
+        Some(array) => Ok(Float64Array::from(array.data().clone())),
+        None => Err(ErrorCode::LogicalError(format!(
+            "{} expects a numeric argument, found {}",
+            fun,
+            array.data_type()
+        ))),
+    }
+}
+
+// abs、ceil、floor：整数保持原来的类型（ceil、floor 不变），浮点数按 f 计算
+fn integer_preserving(fun: ScalarFunc, value: ColumnValue, f: fn(f64) -> f64) -> Result<ArrayRef> {
+    let array = value.into_array_of_type(&DataType::Float64)?;
+    match array.data_type() {
+        DataType::Int64 if fun == ScalarFunc::Abs => {
+            let array = array.as_any().downcast_ref::<Int64Array>().unwrap();
+            let mut result = Vec::with_capacity(array.len());
+            for v in array.iter() {
+                result.push(match v {
+                    Some(v) => Some(v.checked_abs().ok_or_else(|| {
+                        ErrorCode::LogicalError(format!("abs({}) overflows Int64", v))
+                    })?),
+                    None => None,
+                });
+            }
+            Ok(Arc::new(Int64Array::from(result)))
+        }
+        DataType::Int64 | DataType::UInt64 => Ok(array),
+        _ => {
+            let array = float_arg(fun, ColumnValue::Array(array))?;
+            Ok(Arc::new(array.iter().map(|v| v.map(f)).collect::<Float64Array>()))
+        }
+    }
+}
+
+// 四舍五入到 precision 位小数 precision 可以为负数
+fn round(value: f64, precision: i64) -> f64 {
+    let factor = 10f64.powi(precision.clamp(i32::MIN as i64, i32::MAX as i64) as i32);
+    (value * factor).round() / factor
+}
+
+// 取 [start, start + len) 范围内的字符 start 从 1 开始，超出字符串的部分被截掉
+// len 为 None 时取到末尾，len 为负数时结果为空字符串
+fn substring(value: &str, start: i64, len: Option<i64>) -> String {
+    let begin = start.max(1);
+    let end = match len {
+        Some(len) => start.saturating_add(len.max(0)),
+        None => i64::MAX,
+    };
+    if end <= begin {
+        return String::new();
+    }
+    value
+        .chars()
+        .skip((begin - 1) as usize)
+        .take((end - begin) as usize)
+        .collect()
+}
+
+impl PhysicalExpr for PhysicalScalarFunctionExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn evaluate(&self, input: &RecordBatch) -> Result<ColumnValue> {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| arg.evaluate(input))
+            .collect::<Result<Vec<_>>>()?;
+        let array: ArrayRef = match self.fun {
+            ScalarFunc::Upper => {
+                let arg = string_arg(self.fun, args[0].clone())?;
+                Arc::new(arg.iter().map(|v| v.map(str::to_uppercase)).collect::<StringArray>())
+            }
+            ScalarFunc::Lower => {
+                let arg = string_arg(self.fun, args[0].clone())?;
+                Arc::new(arg.iter().map(|v| v.map(str::to_lowercase)).collect::<StringArray>())
+            }
+            // 按字符计数 而不是字节数
+            ScalarFunc::Length => {
+                let arg = string_arg(self.fun, args[0].clone())?;
+                Arc::new(
+                    arg.iter()
+                        .map(|v| v.map(|v| v.chars().count() as i64))
+                        .collect::<Int64Array>(),
+                )
+            }
+            ScalarFunc::Substring => {
+                let arg = string_arg(self.fun, args[0].clone())?;
+                let start = int_arg(self.fun, args[1].clone())?;
+                let len = match args.get(2) {
+                    Some(len) => Some(int_arg(self.fun, len.clone())?),
+                    None => None,
+                };
+                let mut result = Vec::with_capacity(arg.len());
+                for row in 0..arg.len() {
+                    let len_is_null = matches!(&len, Some(len) if len.is_null(row));
+                    result.push(if arg.is_null(row) || start.is_null(row) || len_is_null {
+                        None
+                    } else {
+                        let len = len.as_ref().map(|len| len.value(row));
+                        Some(substring(arg.value(row), start.value(row), len))
+                    });
+                }
+                Arc::new(result.into_iter().collect::<StringArray>())
+            }
+            ScalarFunc::Trim => {
+                let arg = string_arg(self.fun, args[0].clone())?;
+                Arc::new(arg.iter().map(|v| v.map(str::trim)).collect::<StringArray>())
+            }
+            ScalarFunc::LTrim => {
+                let arg = string_arg(self.fun, args[0].clone())?;
+                Arc::new(arg.iter().map(|v| v.map(str::trim_start)).collect::<StringArray>())
+            }
+            ScalarFunc::RTrim => {
+                let arg = string_arg(self.fun, args[0].clone())?;
+                Arc::new(arg.iter().map(|v| v.map(str::trim_end)).collect::<StringArray>())
+            }
+            ScalarFunc::Abs => integer_preserving(self.fun, args[0].clone(), f64::abs)?,
+            ScalarFunc::Ceil => integer_preserving(self.fun, args[0].clone(), f64::ceil)?,
+            ScalarFunc::Floor => integer_preserving(self.fun, args[0].clone(), f64::floor)?,
+            ScalarFunc::Round => {
+                let arg = float_arg(self.fun, args[0].clone())?;
+                let precision = match args.get(1) {
+                    Some(precision) => Some(int_arg(self.fun, precision.clone())?),
+                    None => None,
+                };
+                let mut result = Vec::with_capacity(arg.len());
+                for row in 0..arg.len() {
+                    result.push(match &precision {
+                        _ if arg.is_null(row) => None,
+                        Some(precision) if precision.is_null(row) => None,
+                        Some(precision) => Some(round(arg.value(row), precision.value(row))),
+                        None => Some(arg.value(row).round()),
+                    });
+                }
+                Arc::new(Float64Array::from(result))
+            }
+            ScalarFunc::Sqrt => {
+                let arg = float_arg(self.fun, args[0].clone())?;
+                Arc::new(
+                    arg.iter()
+                        .map(|v| v.filter(|v| *v >= 0.0).map(f64::sqrt))
+                        .collect::<Float64Array>(),
+                )
+            }
+        };
+        Ok(ColumnValue::Array(array))
+    }
+}
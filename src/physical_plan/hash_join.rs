@@ -1,95 +1,75 @@
+use arrow::array::build_compare;
+use arrow::array::Array;
 use arrow::array::ArrayRef;
+use arrow::array::BooleanArray;
+use arrow::array::Float64Array;
+use arrow::array::Int64Array;
 use arrow::array::Int64Builder;
-use arrow::array::PrimitiveArray;
 use arrow::array::StringArray;
+use arrow::array::UInt64Array;
 use arrow::compute;
+use arrow::compute::cast;
 use arrow::compute::concat;
 use arrow::datatypes::DataType;
 
-use arrow::datatypes::Int64Type;
 use arrow::datatypes::SchemaRef;
-use arrow::datatypes::UInt64Type;
 use arrow::record_batch::RecordBatch;
 
 use twox_hash::XxHash64;
 
+use super::PhysicalExprRef;
 use super::PhysicalPlan;
 use super::PhysicalPlanRef;
 use crate::error::ErrorCode;
 use crate::logical_plan::expression::Column;
 use crate::logical_plan::plan::JoinType;
 use crate::logical_plan::schema::NaiveSchema;
-use crate::physical_plan::ColumnExpr;
 
 use crate::Result;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
 use std::hash::Hasher;
 
 use std::sync::Arc;
-use std::sync::Mutex;
 
 /// HashJoin has two phase for join
-/// 1. build phase will build HashMap about outer table using on column as hashval
-///     hashmap: col hash val -> vec<row id>
-/// 2. probe phase will probe all inner table by using on col to check
+/// 1. build phase will build HashMap about the build side using on columns as hashval
+///    hashmap: on cols hash val -> vec<row id>
+/// 2. probe phase will probe all rows of the other side by using on cols to check
+///
+/// 左连接用右表建哈希表、左表探测，其余连接用左表建哈希表、右表探测，
+/// 外连接中探测端没有匹配的行与另一侧的 NULL 一起输出。连接键为 NULL 的行不与任何行匹配
+/// filter 是 ON 中除连接键以外的条件，连接键相等且 filter 为 true 时两行才匹配
 #[derive(Debug)]
 pub struct HashJoin {
     left: PhysicalPlanRef,
     right: PhysicalPlanRef,
     on: Vec<(Column, Column)>,
-    #[allow(unused)]
+    filter: Option<PhysicalExprRef>,
     join_type: JoinType,
     schema: NaiveSchema,
-    /// on col hash val and row id
-    /// chain hash table
-    hashtable: Mutex<HashMap<u64, Vec<usize>>>,
-    /// data, combine all data in one record batch
-    data: Mutex<Option<RecordBatch>>,
 }
 
-macro_rules! build_match {
-    ($LEFT_COL: expr, $TYPE: ty, $SINGLE_BATCH: expr, $HASHTABLE: expr, $WRITE_DT: ident) => {{
-        let left_col = $LEFT_COL
-            .as_any()
-            .downcast_ref::<PrimitiveArray<$TYPE>>()
-            .unwrap();
-
-        // build hashmap
-        for i in 0..$SINGLE_BATCH.num_rows() {
-            let left_val = left_col.value(i);
-            let mut hasher = XxHash64::default();
-            hasher.$WRITE_DT(left_val);
-            let hash_val = hasher.finish();
-            if let Some(vec) = $HASHTABLE.get_mut(&hash_val) {
-                vec.push(i);
-            } else {
-                $HASHTABLE.insert(hash_val, vec![i]);
-            }
-        }
-    }};
+/// 建哈希表一侧的全部数据（合并为一个 batch）、连接键和哈希表
+struct BuildSide {
+    batch: RecordBatch,
+    keys: Vec<ArrayRef>,
+    hashtable: HashMap<u64, Vec<usize>>,
 }
 
-macro_rules! probe_match {
-    ($RIGHT_COL: expr, $LEFT_COL: expr, $TYPE: ty, $RIGHT_BATCH: expr, $HASHTABLE: expr, $OUTER_POS: expr, $INNER_POS: expr, $WRITE_DT: ident) => {{
-        let right_col = $RIGHT_COL.as_any().downcast_ref::<$TYPE>().unwrap();
-        let left_col = $LEFT_COL.as_any().downcast_ref::<$TYPE>().unwrap();
-
-        // probe
-        for i in 0..$RIGHT_BATCH.num_rows() {
-            let right_val = right_col.value(i);
-            let mut hasher = XxHash64::default();
-            hasher.$WRITE_DT(right_val);
-            let hash_val = hasher.finish();
-
-            if let Some(left_pos) = $HASHTABLE.get(&hash_val) {
-                for idx in left_pos {
-                    // hash val same, but we need to check whether real value equal or not
-                    if left_col.value(*idx) == right_col.value(i) {
-                        $OUTER_POS.append_value(*idx as i64)?;
-                        $INNER_POS.append_value(i as i64)?;
+// 把一列连接键写入每一行的 hasher，值为 NULL 的行不再参与匹配
+macro_rules! hash_column {
+    ($KEY: expr, $ARRAY_TYPE: ty, $HASHERS: expr, $TO_BYTES: expr) => {{
+        let array = $KEY.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
+        for (hasher, value) in $HASHERS.iter_mut().zip(array.iter()) {
+            match value {
+                Some(value) => {
+                    if let Some(hasher) = hasher {
+                        hasher.write($TO_BYTES(value).as_ref());
                     }
                 }
+                None => *hasher = None,
             }
         }
     }};
@@ -100,6 +80,7 @@ impl HashJoin {
         left: PhysicalPlanRef,
         right: PhysicalPlanRef,
         on: Vec<(Column, Column)>,
+        filter: Option<PhysicalExprRef>,
         join_type: JoinType,
         schema: NaiveSchema,
     ) -> PhysicalPlanRef {
@@ -107,144 +88,210 @@ impl HashJoin {
             left,
             right,
             on,
+            filter,
             join_type,
             schema,
-            hashtable: Mutex::new(HashMap::new()),
-            data: Mutex::new(None),
         })
     }
 
-    pub fn build(&self) -> Result<Vec<ArrayRef>> {
-        if self.on.is_empty() {
-            return Err(ErrorCode::PlanError(
-                "Inner Join on Conditions can't not be empty".to_string(),
-            ));
-        }
-
-        let left = self.left.execute()?;
-        let single_batch = concat_batches(&self.left.schema().clone().into(), &left)?;
-
-        let (left_col, _) = &self.on[0];
-        let left_col = ColumnExpr::try_create(Some(left_col.name.clone()), None)?;
-        let left_col = left_col.evaluate(&single_batch)?.into_array();
+    // 外连接中需要保留所有行的一侧作为探测端
+    fn build_on_left(&self) -> bool {
+        self.join_type != JoinType::Left
+    }
 
-        let mut hashtable = self.hashtable.lock().unwrap();
-        match left_col.data_type() {
-            DataType::Int64 => {
-                build_match!(left_col, Int64Type, single_batch, hashtable, write_i64)
-            }
-            DataType::UInt64 => {
-                build_match!(left_col, UInt64Type, single_batch, hashtable, write_u64)
-            }
-            DataType::Utf8 => {
-                let left_col = left_col.as_any().downcast_ref::<StringArray>().unwrap();
-
-                // build hashmap
-                for i in 0..single_batch.num_rows() {
-                    let mut hasher = XxHash64::default();
-                    hasher.write(left_col.value(i).as_bytes());
-                    let hash_val = hasher.finish();
-                    if let Some(vec) = hashtable.get_mut(&hash_val) {
-                        vec.push(i);
-                    } else {
-                        hashtable.insert(hash_val, vec![i]);
-                    }
+    // 两侧连接键比较时使用的类型：类型相同时不变，不同的数值类型统一转换为 Float64
+    fn key_types(&self) -> Result<Vec<DataType>> {
+        let is_numeric = |dt: &DataType| matches!(dt, DataType::Int64 | DataType::UInt64 | DataType::Float64);
+        self.on
+            .iter()
+            .map(|(l, r)| {
+                let left = key_field_type(&self.left, l)?;
+                let right = key_field_type(&self.right, r)?;
+                if left == right {
+                    Ok(left)
+                } else if is_numeric(&left) && is_numeric(&right) {
+                    Ok(DataType::Float64)
+                } else {
+                    Err(ErrorCode::PlanError(format!(
+                        "join keys have incompatible types: {} and {}",
+                        left, right
+                    )))
                 }
+            })
+            .collect()
+    }
+
+    fn build(
+        &self,
+        plan: &PhysicalPlanRef,
+        keys: &[&Column],
+        key_types: &[DataType],
+    ) -> Result<BuildSide> {
+        let batches = plan.execute()?;
+        let batch = concat_batches(&plan.schema().clone().into(), &batches)?;
+        let keys = key_columns(plan, keys, key_types, &batch)?;
+
+        let mut hashtable: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (row, hash) in hash_keys(&keys)?.into_iter().enumerate() {
+            if let Some(hash) = hash {
+                hashtable.entry(hash).or_default().push(row);
             }
-            _ => return Err(ErrorCode::NotImplemented),
         }
-
-        *self.data.lock().unwrap() = Some(single_batch);
-        Ok(vec![left_col])
+        Ok(BuildSide {
+            batch,
+            keys,
+            hashtable,
+        })
     }
 
-    pub fn probe(&self, left_cols: Vec<ArrayRef>) -> Result<Vec<RecordBatch>> {
-        let right_batches = self.right.execute()?;
-
-        let (_, right_col) = &self.on[0];
-        let right_col = ColumnExpr::try_create(Some(right_col.name.clone()), None)?;
-        let left_col = &left_cols[0];
-
+    fn probe(
+        &self,
+        build: &BuildSide,
+        plan: &PhysicalPlanRef,
+        keys: &[&Column],
+        key_types: &[DataType],
+    ) -> Result<Vec<RecordBatch>> {
+        let preserve_probe = self.join_type != JoinType::Inner;
         let mut batches = vec![];
 
-        for right_batch in &right_batches {
-            let right_col = right_col.evaluate(right_batch)?.into_array();
-
-            let hashtable = self.hashtable.lock().unwrap();
-
-            let mut outer_pos = Int64Builder::new(left_col.len());
-            let mut inner_pos = Int64Builder::new(right_col.len());
-            match right_col.data_type() {
-                DataType::Int64 => probe_match!(
-                    right_col,
-                    left_col,
-                    PrimitiveArray<Int64Type>,
-                    right_batch,
-                    hashtable,
-                    outer_pos,
-                    inner_pos,
-                    write_i64
-                ),
-                DataType::UInt64 => probe_match!(
-                    right_col,
-                    left_col,
-                    PrimitiveArray<UInt64Type>,
-                    right_batch,
-                    hashtable,
-                    outer_pos,
-                    inner_pos,
-                    write_u64
-                ),
-                DataType::Utf8 => {
-                    let right_col = right_col.as_any().downcast_ref::<StringArray>().unwrap();
-                    let left_col = left_col.as_any().downcast_ref::<StringArray>().unwrap();
-
-                    // probe
-                    for i in 0..right_batch.num_rows() {
-                        let mut hasher = XxHash64::default();
-                        hasher.write(right_col.value(i).as_bytes());
-                        let hash_val = hasher.finish();
-
-                        if let Some(left_pos) = hashtable.get(&hash_val) {
-                            for idx in left_pos {
-                                // hash val same, but we need to check whether real value equal or not
-                                if left_col.value(*idx) == right_col.value(i) {
-                                    outer_pos.append_value(*idx as i64)?;
-                                    inner_pos.append_value(i as i64)?;
-                                }
-                            }
+        for probe_batch in &plan.execute()? {
+            let probe_keys = key_columns(plan, keys, key_types, probe_batch)?;
+            let comparators = build
+                .keys
+                .iter()
+                .zip(&probe_keys)
+                .map(|(build_key, probe_key)| build_compare(build_key.as_ref(), probe_key.as_ref()))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+
+            // 连接键相等的行对 按探测端的行号排列
+            let mut build_pos = Int64Builder::new(probe_batch.num_rows());
+            let mut probe_pos = Int64Builder::new(probe_batch.num_rows());
+            for (row, hash) in hash_keys(&probe_keys)?.into_iter().enumerate() {
+                if let Some(rows) = hash.and_then(|hash| build.hashtable.get(&hash)) {
+                    for &build_row in rows {
+                        // hash val same, but we need to check whether real values equal or not
+                        if comparators.iter().all(|cmp| cmp(build_row, row) == Ordering::Equal) {
+                            build_pos.append_value(build_row as i64)?;
+                            probe_pos.append_value(row as i64)?;
                         }
                     }
                 }
-                _ => return Err(ErrorCode::NotImplemented),
             }
+            let build_pos = build_pos.finish();
+            let probe_pos = probe_pos.finish();
+
+            // 连接条件中不是连接键的部分只对键相等的行对计算，为 NULL 时与 false 一样不匹配
+            let keep = match &self.filter {
+                Some(filter) => {
+                    let candidates = self.joined_batch(&build.batch, &build_pos, probe_batch, &probe_pos)?;
+                    let keep = filter.evaluate(&candidates)?.into_array_of_type(&DataType::Boolean)?;
+                    Some(BooleanArray::from(keep.data().clone()))
+                }
+                None => None,
+            };
+
+            // 外连接中探测端没有匹配的行与 NULL 一起输出
+            let mut matched_build = Int64Builder::new(build_pos.len());
+            let mut matched_probe = Int64Builder::new(probe_pos.len());
+            let mut next = 0;
+            for row in 0..probe_batch.num_rows() {
+                let mut matched = false;
+                while next < probe_pos.len() && probe_pos.value(next) as usize == row {
+                    if keep.as_ref().is_none_or(|keep| keep.is_valid(next) && keep.value(next)) {
+                        matched_build.append_value(build_pos.value(next))?;
+                        matched_probe.append_value(row as i64)?;
+                        matched = true;
+                    }
+                    next += 1;
+                }
+                if !matched && preserve_probe {
+                    matched_build.append_null()?;
+                    matched_probe.append_value(row as i64)?;
+                }
+            }
+            let batch = self.joined_batch(
+                &build.batch,
+                &matched_build.finish(),
+                probe_batch,
+                &matched_probe.finish(),
+            )?;
+            batches.push(batch);
+        }
+
+        Ok(batches)
+    }
 
-            let mut columns = vec![];
+    // 按行号从两侧取出对应的行拼成输出的 batch 行号为 NULL 的位置取出的值也是 NULL
+    fn joined_batch(
+        &self,
+        build_batch: &RecordBatch,
+        build_pos: &Int64Array,
+        probe_batch: &RecordBatch,
+        probe_pos: &Int64Array,
+    ) -> Result<RecordBatch> {
+        let mut build_columns = vec![];
+        for array in build_batch.columns() {
+            build_columns.push(compute::take(array.as_ref(), build_pos, None)?);
+        }
+        let mut probe_columns = vec![];
+        for array in probe_batch.columns() {
+            probe_columns.push(compute::take(array.as_ref(), probe_pos, None)?);
+        }
 
-            let outer_pos = outer_pos.finish();
-            let inner_pos = inner_pos.finish();
+        // add left columns, then right columns
+        let columns = if self.build_on_left() {
+            [build_columns, probe_columns].concat()
+        } else {
+            [probe_columns, build_columns].concat()
+        };
+        Ok(RecordBatch::try_new(SchemaRef::from(self.schema.clone()), columns)?)
+    }
+}
 
-            // add left columns
-            let data = self.data.lock().unwrap();
-            if let Some(outer_table) = &*data {
-                for i in 0..self.left.schema().fields().len() {
-                    let array = outer_table.column(i);
-                    columns.push(compute::take(array.as_ref(), &outer_pos, None)?);
-                }
+fn key_field_type(plan: &PhysicalPlanRef, key: &Column) -> Result<DataType> {
+    let idx = plan.schema().index_of_column(key.table.as_deref(), &key.name)?;
+    Ok(plan.schema().field(idx).data_type().clone())
+}
 
-                // add right columns
-                for i in 0..self.right.schema().fields().len() {
-                    let array = right_batch.column(i);
-                    columns.push(compute::take(array.as_ref(), &inner_pos, None)?);
-                }
+// 取出连接键对应的列 并转换为比较时使用的类型
+fn key_columns(
+    plan: &PhysicalPlanRef,
+    keys: &[&Column],
+    key_types: &[DataType],
+    batch: &RecordBatch,
+) -> Result<Vec<ArrayRef>> {
+    keys.iter()
+        .zip(key_types)
+        .map(|(key, data_type)| {
+            let idx = plan.schema().index_of_column(key.table.as_deref(), &key.name)?;
+            Ok(cast(batch.column(idx), data_type)?)
+        })
+        .collect()
+}
 
-                let batch = RecordBatch::try_new(SchemaRef::from(self.schema.clone()), columns)?;
-                batches.push(batch);
+// 计算每一行所有连接键的哈希值 任意一个键为 NULL 时为 None
+fn hash_keys(keys: &[ArrayRef]) -> Result<Vec<Option<u64>>> {
+    let num_rows = keys.first().map_or(0, |key| key.len());
+    let mut hashers = vec![Some(XxHash64::default()); num_rows];
+    for key in keys {
+        match key.data_type() {
+            DataType::Int64 => hash_column!(key, Int64Array, hashers, i64::to_le_bytes),
+            DataType::UInt64 => hash_column!(key, UInt64Array, hashers, u64::to_le_bytes),
+            DataType::Float64 => hash_column!(key, Float64Array, hashers, f64::to_le_bytes),
+            DataType::Boolean => hash_column!(key, BooleanArray, hashers, |v: bool| [v as u8]),
+            DataType::Utf8 => hash_column!(key, StringArray, hashers, str::as_bytes),
+            other => {
+                return Err(ErrorCode::NotSupported(format!(
+                    "join key of type {} is not supported",
+                    other
+                )))
             }
         }
-
-        Ok(batches)
     }
+    Ok(hashers
+        .into_iter()
+        .map(|hasher| hasher.map(|hasher| hasher.finish()))
+        .collect())
 }
 
 /// Concatenates an array of `RecordBatch` into one batch
@@ -252,6 +299,28 @@ pub fn concat_batches(schema: &SchemaRef, batches: &[RecordBatch]) -> Result<Rec
     if batches.is_empty() {
         return Ok(RecordBatch::new_empty(schema.clone()));
     }
+    // 列数和每一列的类型都必须与目标 schema 一致 列名可以不同
+    for (batch_idx, batch) in batches.iter().enumerate() {
+        if batch.num_columns() != schema.fields().len() {
+            return Err(ErrorCode::LogicalError(format!(
+                "can not concat batches: batch {} has {} columns but the schema has {}",
+                batch_idx,
+                batch.num_columns(),
+                schema.fields().len()
+            )));
+        }
+        for (field, column) in schema.fields().iter().zip(batch.columns()) {
+            if field.data_type() != column.data_type() {
+                return Err(ErrorCode::LogicalError(format!(
+                    "can not concat batches: column `{}` of batch {} is {} but the schema expects {}",
+                    field.name(),
+                    batch_idx,
+                    column.data_type(),
+                    field.data_type()
+                )));
+            }
+        }
+    }
     let mut arrays = Vec::with_capacity(schema.fields().len());
     for i in 0..schema.fields().len() {
         let array = concat(
@@ -271,12 +340,61 @@ impl PhysicalPlan for HashJoin {
     }
 
     fn execute(&self) -> Result<Vec<RecordBatch>> {
-        let left_cols = self.build()?;
-
-        self.probe(left_cols)
+        if self.on.is_empty() {
+            return Err(ErrorCode::PlanError(
+                "Join on Conditions can't not be empty".to_string(),
+            ));
+        }
+        let key_types = self.key_types()?;
+        let (left_keys, right_keys): (Vec<&Column>, Vec<&Column>) =
+            self.on.iter().map(|(l, r)| (l, r)).unzip();
+        if self.build_on_left() {
+            let build = self.build(&self.left, &left_keys, &key_types)?;
+            self.probe(&build, &self.right, &right_keys, &key_types)
+        } else {
+            let build = self.build(&self.right, &right_keys, &key_types)?;
+            self.probe(&build, &self.left, &left_keys, &key_types)
+        }
     }
 
     fn children(&self) -> Result<Vec<PhysicalPlanRef>> {
         Ok(vec![self.left.clone(), self.right.clone()])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::{Field, Schema};
+
+    fn batch(name: &str, column: ArrayRef) -> RecordBatch {
+        RecordBatch::try_from_iter(vec![(name, column)]).unwrap()
+    }
+
+    #[test]
+    fn concat_batches_rejects_batches_that_do_not_match_the_schema() {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true)]));
+        let ints = batch("id", Arc::new(Int64Array::from(vec![1, 2])));
+        let strings = batch("id", Arc::new(StringArray::from(vec!["a"])));
+
+        let err = concat_batches(&schema, &[ints.clone(), strings]).unwrap_err();
+        assert!(
+            format!("{:?}", err).contains("column `id` of batch 1 is Utf8 but the schema expects Int64"),
+            "{:?}",
+            err
+        );
+        let two_columns = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from(vec![3])) as ArrayRef),
+            ("v", Arc::new(Int64Array::from(vec![4])) as ArrayRef),
+        ])
+        .unwrap();
+        let err = concat_batches(&schema, &[two_columns]).unwrap_err();
+        assert!(format!("{:?}", err).contains("batch 0 has 2 columns but the schema has 1"), "{:?}", err);
+
+        // 列名不同但类型一致时可以拼接
+        let renamed = batch("other", Arc::new(Int64Array::from(vec![3])));
+        let result = concat_batches(&schema, &[ints, renamed]).unwrap();
+        assert_eq!(result.num_rows(), 3);
+        assert_eq!(result.schema(), schema);
+    }
+}
@@ -1,8 +1,9 @@
-use super::{PhysicalPlan, PhysicalPlanRef};
+use super::{PhysicalPlan, PhysicalPlanRef, RecordBatchStream};
 use crate::error::Result;
 use crate::logical_plan::schema::NaiveSchema;
 
 use arrow::record_batch::RecordBatch;
+use std::iter;
 use std::sync::Arc;
 
 #[derive(Debug, Clone)]
@@ -23,25 +24,87 @@ impl PhysicalPlan for PhysicalLimitPlan {
     }
 
     fn execute(&self) -> Result<Vec<RecordBatch>> {
-        let batches = self.input.execute()?;
+        self.execute_stream()?.collect()
+    }
+
+    // 取够 n 行之后不再从子计划拉取 batch
+    fn execute_stream(&self) -> Result<RecordBatchStream<'_>> {
+        let mut input = self.input.execute_stream()?;
         let mut n = self.n;
-        let mut ret = vec![];
-        for batch in &batches {
+        Ok(Box::new(iter::from_fn(move || {
             if n == 0 {
-                break;
+                return None;
             }
+            let batch = match input.next()? {
+                Ok(batch) => batch,
+                Err(e) => {
+                    n = 0;
+                    return Some(Err(e));
+                }
+            };
             if batch.num_rows() <= n {
-                ret.push(batch.clone());
                 n -= batch.num_rows();
+                Some(Ok(batch))
             } else {
-                ret.push(batch.slice(0, n));
+                let batch = batch.slice(0, n);
                 n = 0;
-            };
-        }
-        Ok(ret)
+                Some(Ok(batch))
+            }
+        })))
     }
 
     fn children(&self) -> Result<Vec<PhysicalPlanRef>> {
         Ok(vec![self.input.clone()])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::Catalog;
+    use crate::datasource::test_utils::CountingTable;
+    use crate::optimizer::Optimizer;
+    use crate::planner::QueryPlanner;
+    use crate::sql::parser::SQLParser;
+    use crate::sql::planner::SQLPlanner;
+    use crate::utils::value_at;
+
+    fn execute(catalog: &Catalog, sql: &str) -> Result<Vec<RecordBatch>> {
+        let (statement, _) = SQLParser::parse(sql)?;
+        let plan = SQLPlanner::new(catalog).statement_to_plan(statement)?;
+        let plan = Optimizer::default().optimize(plan);
+        QueryPlanner::default().create_physical_plan(&plan)?.execute()
+    }
+
+    fn ids(batches: &[RecordBatch]) -> Vec<String> {
+        let mut ids = vec![];
+        for batch in batches {
+            for row in 0..batch.num_rows() {
+                ids.push(value_at(batch.column(0), row).unwrap());
+            }
+        }
+        ids
+    }
+
+    #[test]
+    fn limit_stops_pulling_batches_once_it_has_enough_rows() -> Result<()> {
+        // 5 个 batch 每个 batch 2 行
+        let source = CountingTable::create(5, 2)?;
+        let mut catalog = Catalog::default();
+        catalog.add_new_table("t".to_string(), source.clone())?;
+
+        let batches = execute(&catalog, "SELECT * FROM t LIMIT 1")?;
+        assert_eq!(ids(&batches), vec!["1"]);
+        assert_eq!(source.batches_pulled(), 1);
+
+        // 满足条件的第一行在第三个 batch 中
+        let batches = execute(&catalog, "SELECT id FROM t WHERE id > 4 LIMIT 1")?;
+        assert_eq!(ids(&batches), vec!["5"]);
+        assert_eq!(source.batches_pulled(), 1 + 3);
+
+        // 没有 LIMIT 时读取全部 batch
+        execute(&catalog, "SELECT id FROM t WHERE id > 4")?;
+        assert_eq!(source.batches_pulled(), 1 + 3 + 5);
+        Ok(())
+    }
+}
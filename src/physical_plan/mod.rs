@@ -3,12 +3,15 @@ mod plan;
 
 mod aggregate;
 mod cross_join;
+mod distinct_on;
 mod hash_join; 
 mod limit;
+mod nested_loop_join;
 mod offset;
 mod projection;
 mod scan;
 mod selection;
+mod sort;
 mod update;     // lyx: add update
 mod insert;
 mod delete;
@@ -16,15 +19,17 @@ mod create_table;
 
 pub use aggregate::*;
 pub use cross_join::*;
+pub use distinct_on::*;
 pub use expression::*;
 pub use hash_join::*;
 pub use limit::*;
-// pub use nested_loop_join::*; 暂时还没使用
+pub use nested_loop_join::*;
 pub use offset::*;
 pub use plan::*;
 pub use projection::*;
 pub use scan::*;
 pub use selection::*;
+pub use sort::*;
 pub use update::*;     // lyx: add update
 pub use insert::*;     // lyx: add insert
 pub use delete::*;     // lyx: add delete
@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use super::hash_join::concat_batches;
+use super::{PhysicalExprRef, PhysicalPlan, PhysicalPlanRef};
+use crate::logical_plan::plan::JoinType;
+use crate::logical_plan::schema::NaiveSchema;
+use crate::Result;
+use arrow::array::{Array, BooleanArray, Int64Array, Int64Builder};
+use arrow::compute;
+use arrow::datatypes::{DataType, SchemaRef};
+use arrow::record_batch::RecordBatch;
+
+/// 嵌套循环连接：对左右两侧的每一对行计算连接谓词，谓词为 true 时两行匹配
+/// 用于连接条件不是等值条件的情况（例如 `a.x < b.y`），谓词为 NULL 与 false 一样不匹配
+/// 左连接保留左侧没有匹配的行，右连接保留右侧没有匹配的行，另一侧的列为 NULL
+#[derive(Debug)]
+pub struct NestedLoopJoinPlan {
+    left: PhysicalPlanRef,
+    right: PhysicalPlanRef,
+    predicate: PhysicalExprRef,
+    join_type: JoinType,
+    schema: NaiveSchema,
+}
+
+impl NestedLoopJoinPlan {
+    pub fn create(
+        left: PhysicalPlanRef,
+        right: PhysicalPlanRef,
+        predicate: PhysicalExprRef,
+        join_type: JoinType,
+        schema: NaiveSchema,
+    ) -> PhysicalPlanRef {
+        Arc::new(Self {
+            left,
+            right,
+            predicate,
+            join_type,
+            schema,
+        })
+    }
+
+    // 把左侧第 row 行与右侧的每一行拼在一起 计算谓词
+    fn evaluate_row(&self, left: &RecordBatch, row: usize, right: &RecordBatch) -> Result<BooleanArray> {
+        let indices = Int64Array::from(vec![row as i64; right.num_rows()]);
+        let mut columns = vec![];
+        for array in left.columns() {
+            columns.push(compute::take(array.as_ref(), &indices, None)?);
+        }
+        columns.extend(right.columns().iter().cloned());
+        let batch = RecordBatch::try_new(SchemaRef::from(self.schema.clone()), columns)?;
+        let predicate = self.predicate.evaluate(&batch)?.into_array_of_type(&DataType::Boolean)?;
+        Ok(BooleanArray::from(predicate.data().clone()))
+    }
+}
+
+impl PhysicalPlan for NestedLoopJoinPlan {
+    fn schema(&self) -> &NaiveSchema {
+        &self.schema
+    }
+
+    fn execute(&self) -> Result<Vec<RecordBatch>> {
+        let left = concat_batches(&self.left.schema().clone().into(), &self.left.execute()?)?;
+        let right = concat_batches(&self.right.schema().clone().into(), &self.right.execute()?)?;
+
+        let mut left_pos = Int64Builder::new(left.num_rows());
+        let mut right_pos = Int64Builder::new(left.num_rows());
+        let mut right_matched = vec![false; right.num_rows()];
+        for row in 0..left.num_rows() {
+            let mut matched = false;
+            if right.num_rows() > 0 {
+                let predicate = self.evaluate_row(&left, row, &right)?;
+                for (right_row, value) in predicate.iter().enumerate() {
+                    if value == Some(true) {
+                        left_pos.append_value(row as i64)?;
+                        right_pos.append_value(right_row as i64)?;
+                        right_matched[right_row] = true;
+                        matched = true;
+                    }
+                }
+            }
+            if !matched && self.join_type == JoinType::Left {
+                left_pos.append_value(row as i64)?;
+                right_pos.append_null()?;
+            }
+        }
+        if self.join_type == JoinType::Right {
+            for (right_row, matched) in right_matched.into_iter().enumerate() {
+                if !matched {
+                    left_pos.append_null()?;
+                    right_pos.append_value(right_row as i64)?;
+                }
+            }
+        }
+
+        // 行号为 NULL 的位置取出的值也是 NULL
+        let left_pos = left_pos.finish();
+        let right_pos = right_pos.finish();
+        let mut columns = vec![];
+        for array in left.columns() {
+            columns.push(compute::take(array.as_ref(), &left_pos, None)?);
+        }
+        for array in right.columns() {
+            columns.push(compute::take(array.as_ref(), &right_pos, None)?);
+        }
+        let batch = RecordBatch::try_new(SchemaRef::from(self.schema.clone()), columns)?;
+        Ok(vec![batch])
+    }
+
+    fn children(&self) -> Result<Vec<PhysicalPlanRef>> {
+        Ok(vec![self.left.clone(), self.right.clone()])
+    }
+}
@@ -1,4 +1,4 @@
-use super::{PhysicalPlan, PhysicalPlanRef};
+use super::{PhysicalPlan, PhysicalPlanRef, RecordBatchStream};
 use crate::error::Result;
 use crate::logical_plan::schema::NaiveSchema;
 
@@ -23,26 +23,32 @@ impl PhysicalPlan for PhysicalOffsetPlan {
     }
 
     fn execute(&self) -> Result<Vec<RecordBatch>> {
-        let batches = self.input.execute()?;
-        let mut n = self.n;
-        let mut ret = vec![];
+        self.execute_stream()?.collect()
+    }
 
-        for batch in &batches {
+    // 跳过前 n 行 之后的 batch 原样输出
+    fn execute_stream(&self) -> Result<RecordBatchStream<'_>> {
+        let input = self.input.execute_stream()?;
+        let mut n = self.n;
+        Ok(Box::new(input.filter_map(move |batch| {
+            let batch = match batch {
+                Ok(batch) => batch,
+                Err(e) => return Some(Err(e)),
+            };
             if n == 0 {
-                ret.push(batch.clone());
-                continue;
+                return Some(Ok(batch));
             }
 
             if n >= batch.num_rows() {
                 n -= batch.num_rows();
-                continue;
+                return None;
             }
 
             let remain = batch.num_rows() - n;
-            ret.push(batch.slice(n, remain));
+            let batch = batch.slice(n, remain);
             n = 0;
-        }
-        Ok(ret)
+            Some(Ok(batch))
+        })))
     }
 
     fn children(&self) -> Result<Vec<PhysicalPlanRef>> {
@@ -10,13 +10,22 @@ use crate::{error::Result, logical_plan::schema::NaiveSchema};
 // schema: 获取物理计划的输出模式（即查询结果的结构）。
 // execute: 执行物理计划并返回结果。
 // children: 获取物理计划的子计划。
-pub trait PhysicalPlan: Debug {
+pub trait PhysicalPlan: Debug + Send + Sync {
     fn schema(&self) -> &NaiveSchema;
 
     fn execute(&self) -> Result<Vec<RecordBatch>>;
 
+    /// 按 batch 逐个产生结果，下游不再取数据时上游也不再计算（例如 LIMIT 取够了行数）
+    /// 默认先执行整个计划再逐个返回，支持流式执行的算子覆盖这个方法
+    fn execute_stream(&self) -> Result<RecordBatchStream<'_>> {
+        Ok(Box::new(self.execute()?.into_iter().map(Ok)))
+    }
+
     #[allow(unused)]    // 在优化中需要使用到
     fn children(&self) -> Result<Vec<PhysicalPlanRef>>;
 }
 
 pub type PhysicalPlanRef = Arc<dyn PhysicalPlan>;
+
+/// 流式执行的结果 每次产生一个 RecordBatch
+pub type RecordBatchStream<'a> = Box<dyn Iterator<Item = Result<RecordBatch>> + 'a>;
@@ -2,12 +2,14 @@ use std::iter::Iterator;
 use std::sync::Arc;
 
 use super::plan::PhysicalPlan;
-use crate::error::Result;
+use crate::error::{ErrorCode, Result};
 use crate::logical_plan::schema::NaiveSchema;
 use crate::physical_plan::PhysicalExprRef;
 use crate::physical_plan::PhysicalPlanRef;
+use crate::physical_plan::RecordBatchStream;
 use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
+use rayon::prelude::*;
 #[derive(Debug, Clone)]
 pub struct ProjectionPlan {
     input: PhysicalPlanRef,
@@ -29,6 +31,28 @@ impl ProjectionPlan {
     }
 }
 
+impl ProjectionPlan {
+    // 对一个 batch 计算所有投影表达式
+    fn project_batch(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let mut columns = vec![];
+        for (expr, field) in self.expr.iter().zip(self.schema.fields()) {
+            // 计算列出错时在错误中给出它来自的表达式
+            let column = expr.evaluate(batch).map_err(|e| match field.source_expr() {
+                Some(source_expr) => ErrorCode::LogicalError(format!(
+                    "failed to compute column `{}` from `{}`: {:?}",
+                    field.name(),
+                    source_expr,
+                    e
+                )),
+                None => e,
+            })?;
+            // 常量 NULL 需要按投影字段声明的类型生成数组
+            columns.push(column.into_array_of_type(field.data_type())?);
+        }
+        Ok(RecordBatch::try_new(SchemaRef::from(self.schema.clone()), columns)?)
+    }
+}
+
 impl PhysicalPlan for ProjectionPlan {
     fn schema(&self) -> &NaiveSchema {
         &self.schema
@@ -41,25 +65,21 @@ impl PhysicalPlan for ProjectionPlan {
         if self.schema.fields().is_empty() {
             Ok(input)
         } else {
-            let batches = input
-                .iter()
-                .map(|batch| {
-                    let columns = self
-                        .expr
-                        .iter()
-                        // TODO(veeupup): remove unwrap
-                        .map(|expr| expr.evaluate(batch).unwrap())
-                        .collect::<Vec<_>>();
-                    let columns = columns
-                        .iter()
-                        .map(|column| column.clone().into_array())
-                        .collect::<Vec<_>>();
-                    // TODO(veeupup): remove unwrap
-                    // let projection_schema = self.schema.into();
-                    RecordBatch::try_new(SchemaRef::from(self.schema.clone()), columns).unwrap()
-                })
-                .collect::<Vec<_>>();
-            Ok(batches)
+            // 各个 batch 相互独立 在当前线程池中并行计算投影
+            input
+                .par_iter()
+                .map(|batch| self.project_batch(batch))
+                .collect::<Result<Vec<_>>>()
+        }
+    }
+
+    // 流式执行时逐个 batch 计算投影
+    fn execute_stream(&self) -> Result<RecordBatchStream<'_>> {
+        let input = self.input.execute_stream()?;
+        if self.schema.fields().is_empty() {
+            Ok(input)
+        } else {
+            Ok(Box::new(input.map(move |batch| self.project_batch(&batch?))))
         }
     }
 
@@ -67,3 +87,48 @@ impl PhysicalPlan for ProjectionPlan {
         Ok(vec![self.input.clone()])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::Catalog;
+    use crate::datasource::test_utils::CountingTable;
+    use crate::optimizer::Optimizer;
+    use crate::planner::QueryPlanner;
+    use crate::sql::parser::SQLParser;
+    use crate::sql::planner::SQLPlanner;
+    use crate::utils::value_at;
+
+    fn values(batch: &RecordBatch) -> Vec<String> {
+        (0..batch.num_rows())
+            .map(|row| value_at(batch.column(0), row).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn projection_over_selection_streams_one_batch_at_a_time() -> Result<()> {
+        // 4 个 batch 每个 batch 2 行
+        let source = CountingTable::create(4, 2)?;
+        let mut catalog = Catalog::default();
+        catalog.add_new_table("t".to_string(), source.clone())?;
+        let (statement, _) = SQLParser::parse("SELECT id + 10 FROM t WHERE id > 2")?;
+        let plan = SQLPlanner::new(&catalog).statement_to_plan(statement)?;
+        let plan = QueryPlanner::default().create_physical_plan(&Optimizer::default().optimize(plan))?;
+
+        // 每取出一个结果 batch 只从表中读取一个 batch
+        let mut stream = plan.execute_stream()?;
+        assert!(values(&stream.next().unwrap()?).is_empty());
+        assert_eq!(source.batches_pulled(), 1);
+        assert_eq!(values(&stream.next().unwrap()?), vec!["13", "14"]);
+        assert_eq!(source.batches_pulled(), 2);
+        let rest = stream.collect::<Result<Vec<_>>>()?;
+        assert_eq!(rest.len(), 2);
+        assert_eq!(source.batches_pulled(), 4);
+
+        // execute 收集流式执行的结果
+        let batches = plan.execute()?;
+        let all = batches.iter().flat_map(values).collect::<Vec<_>>();
+        assert_eq!(all, vec!["13", "14", "15", "16", "17", "18"]);
+        Ok(())
+    }
+}
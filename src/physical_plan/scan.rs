@@ -3,30 +3,52 @@ use std::sync::Arc;
 use crate::datasource::TableRef;
 use crate::error::Result;
 use crate::logical_plan::schema::NaiveSchema;
-use arrow::record_batch::RecordBatch;
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
 
 use crate::physical_plan::PhysicalPlan;
 use crate::physical_plan::PhysicalPlanRef;
+use crate::physical_plan::RecordBatchStream;
 
 #[derive(Debug, Clone)]
 pub struct ScanPlan {
     source: TableRef,
     projection: Option<Vec<usize>>,
+    // 投影之后的输出 schema
+    schema: NaiveSchema,
 }
 
 impl ScanPlan {
-    pub fn create(source: TableRef, projection: Option<Vec<usize>>) -> PhysicalPlanRef {
-        Arc::new(Self { source, projection })
+    pub fn create(source: TableRef, projection: Option<Vec<usize>>, schema: NaiveSchema) -> PhysicalPlanRef {
+        Arc::new(Self {
+            source,
+            projection,
+            schema,
+        })
     }
 }
 
 impl PhysicalPlan for ScanPlan {
     fn schema(&self) -> &NaiveSchema {
-        self.source.schema()
+        &self.schema
     }
 
     fn execute(&self) -> Result<Vec<RecordBatch>> {
-        self.source.scan(self.projection.clone())
+        self.execute_stream()?.collect()
+    }
+
+    fn execute_stream(&self) -> Result<RecordBatchStream<'_>> {
+        let batches = self.source.scan_stream(self.projection.clone())?;
+        // 输出使用带表名限定的列名 与投影等算子的输出保持一致（即使投影被优化掉）
+        // 没有列的 RecordBatch（例如 OneRowTable）需要显式指定行数
+        let schema = SchemaRef::from(self.schema.clone());
+        Ok(Box::new(batches.map(move |batch| {
+            let batch = batch?;
+            let mut options = RecordBatchOptions::default();
+            options.row_count = Some(batch.num_rows());
+            let columns = batch.columns().to_vec();
+            Ok(RecordBatch::try_new_with_options(schema.clone(), columns, &options)?)
+        })))
     }
 
     fn children(&self) -> Result<Vec<PhysicalPlanRef>> {
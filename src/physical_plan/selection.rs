@@ -1,13 +1,17 @@
 use std::sync::Arc;
 
-use super::{PhysicalExprRef, PhysicalPlan, PhysicalPlanRef};
+use super::{PhysicalExprRef, PhysicalPlan, PhysicalPlanRef, RecordBatchStream};
+use crate::datatype::ColumnValue;
+use crate::logical_plan::expression::ScalarValue;
 use crate::logical_plan::schema::NaiveSchema;
 use crate::Result;
 use arrow::array::{
-    Float64Array, Float64Builder, Int64Array, Int64Builder, StringArray, StringBuilder,
-    UInt64Array, UInt64Builder,
+    Date32Array, Date32Builder, Date64Array, Date64Builder, Float64Array, Float64Builder, Int64Array, Int64Builder, StringArray, StringBuilder,
+    UInt64Array, UInt64Builder, ArrayRef, new_empty_array,
 };
-use arrow::record_batch::RecordBatch;
+use arrow::compute::filter;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use rayon::prelude::*;
 use arrow::{
     array::{Array, BooleanArray, BooleanBuilder},
     datatypes::DataType,
@@ -30,74 +34,101 @@ macro_rules! build_array_by_predicate {
         let array = $COLUMN.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
         let mut builder = <$ARRAY_BUILDER>::new(array.len());
         let iter = $PREDICATE.iter().zip(array.iter());
+        // 谓词为 NULL（例如与空值比较）的行与 false 一样被过滤掉
         for (valid, val) in iter {
-            match valid {
-                Some(valid) => {
-                    if valid {
-                        builder.append_option(val)?;
-                    }
-                }
-                None => builder.append_option(None)?,
+            if valid == Some(true) {
+                builder.append_option(val)?;
             }
         }
         Arc::new(builder.finish())
     }};
 }
 
+impl SelectionPlan {
+    // 对每个 batch 分别计算谓词并保留满足条件的行
+    fn filter_batch(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let predicate = match self.expr.evaluate(batch)? {
+            // 常量谓词（例如没有空值的 batch 上的 IS NOT NULL）不需要逐行过滤 整个 batch 保留或者丢弃
+            ColumnValue::Const(ScalarValue::Boolean(Some(true)), _) => {
+                return self.output_batch(batch.columns().to_vec(), batch.num_rows());
+            }
+            ColumnValue::Const(ScalarValue::Boolean(_) | ScalarValue::Null, _) => {
+                let columns = batch.columns().iter().map(|col| new_empty_array(col.data_type())).collect();
+                return self.output_batch(columns, 0);
+            }
+            predicate => predicate.into_array_of_type(&DataType::Boolean)?,
+        };
+        let predicate = predicate.as_any().downcast_ref::<BooleanArray>().unwrap();
+
+        let mut columns = vec![];
+        for col in batch.columns() {
+            let dt = col.data_type();
+            let column: Arc<dyn Array> = match dt {
+                DataType::Boolean => {
+                    build_array_by_predicate!(col, predicate, BooleanArray, BooleanBuilder)
+                }
+                DataType::UInt64 => {
+                    build_array_by_predicate!(col, predicate, UInt64Array, UInt64Builder)
+                }
+                DataType::Int64 => {
+                    build_array_by_predicate!(col, predicate, Int64Array, Int64Builder)
+                }
+                DataType::Float64 => {
+                    build_array_by_predicate!(col, predicate, Float64Array, Float64Builder)
+                }
+                DataType::Date32 => {
+                    build_array_by_predicate!(col, predicate, Date32Array, Date32Builder)
+                }
+                DataType::Date64 => {
+                    build_array_by_predicate!(col, predicate, Date64Array, Date64Builder)
+                }
+                DataType::Utf8 => {
+                    let array = col.as_any().downcast_ref::<StringArray>().unwrap();
+                    let mut builder = StringBuilder::new(array.len());
+                    let iter = predicate.iter().zip(array.iter());
+                    for (valid, val) in iter {
+                        // 只有 valid 为 Some(true) 的行满足选择条件，false 和 NULL 都不加入到数组中
+                        if valid == Some(true) {
+                            builder.append_option(val)?;
+                        }
+                    }
+                    Arc::new(builder.finish())
+                }
+                // 其他类型（例如 Decimal）使用 arrow 的 filter kernel，谓词为 NULL 的行同样被过滤掉
+                _ => filter(col.as_ref(), predicate)?,
+            };
+            columns.push(column);
+        }
+        // 没有列时（例如没有 FROM 的 SELECT）行数只能由谓词确定
+        let num_rows = predicate.iter().filter(|valid| *valid == Some(true)).count();
+        // 生成过滤后的列数组
+        self.output_batch(columns, num_rows)
+    }
+
+    fn output_batch(&self, columns: Vec<ArrayRef>, num_rows: usize) -> Result<RecordBatch> {
+        let mut options = RecordBatchOptions::default();
+        options.row_count = Some(num_rows);
+        Ok(RecordBatch::try_new_with_options(Arc::new(self.schema().clone().into()), columns, &options)?)
+    }
+}
+
 impl PhysicalPlan for SelectionPlan {
     fn schema(&self) -> &NaiveSchema {
         self.input.schema()
     }
 
     fn execute(&self) -> Result<Vec<RecordBatch>> {
-        let input = self.input.execute()?;
-        let predicate = self.expr.evaluate(&input[0])?.into_array();
-        let predicate = predicate.as_any().downcast_ref::<BooleanArray>().unwrap();
-
-        let mut batches = vec![];
+        // 各个 batch 相互独立 在当前线程池中并行过滤
+        self.input
+            .execute()?
+            .par_iter()
+            .map(|batch| self.filter_batch(batch))
+            .collect()
+    }
 
-        for batch in &input {
-            let mut columns = vec![];
-            for col in batch.columns() {
-                let dt = col.data_type();
-                let column: Arc<dyn Array> = match dt {
-                    DataType::Boolean => {
-                        build_array_by_predicate!(col, predicate, BooleanArray, BooleanBuilder)
-                    }
-                    DataType::UInt64 => {
-                        build_array_by_predicate!(col, predicate, UInt64Array, UInt64Builder)
-                    }
-                    DataType::Int64 => {
-                        build_array_by_predicate!(col, predicate, Int64Array, Int64Builder)
-                    }
-                    DataType::Float64 => {
-                        build_array_by_predicate!(col, predicate, Float64Array, Float64Builder)
-                    }
-                    DataType::Utf8 => {
-                        let array = col.as_any().downcast_ref::<StringArray>().unwrap();
-                        let mut builder = StringBuilder::new(array.len());
-                        let iter = predicate.iter().zip(array.iter());
-                        for (valid, val) in iter {
-                            match valid {
-                                Some(valid) => {    // 如果 valid 为 Some(true)，即该行满足选择条件
-                                    if valid {
-                                        builder.append_option(val)?;
-                                    }
-                                }    // 如果 valid 为 Some(false)，即该行不满足选择条件，则不需要加入到数组中
-                                None => builder.append_option(None::<&str>)?,
-                            }
-                        }
-                        Arc::new(builder.finish())
-                    }
-                    _ => unimplemented!(),
-                };
-                columns.push(column);
-            }
-            let record_batch =      // 生成过滤后的列数组
-                RecordBatch::try_new(Arc::new(self.schema().clone().into()), columns)?;
-            batches.push(record_batch);
-        }
-        Ok(batches)
+    fn execute_stream(&self) -> Result<RecordBatchStream<'_>> {
+        let input = self.input.execute_stream()?;
+        Ok(Box::new(input.map(move |batch| self.filter_batch(&batch?))))
     }
 
     // children 方法返回当前物理计划的子计划。UpdatePlan 的子计划就是它的输入计划。
@@ -0,0 +1,70 @@
+use super::{concat_batches, PhysicalExprRef, PhysicalPlan, PhysicalPlanRef};
+use crate::error::Result;
+use crate::logical_plan::schema::NaiveSchema;
+
+use arrow::array::UInt64Array;
+use arrow::compute::kernels::sort::{lexsort_to_indices, SortColumn, SortOptions};
+use arrow::compute::take;
+use arrow::record_batch::RecordBatch;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct PhysicalSortExpr {
+    pub expr: PhysicalExprRef,
+    pub options: SortOptions,
+}
+
+#[derive(Debug, Clone)]
+pub struct PhysicalSortPlan {
+    input: PhysicalPlanRef,
+    sort_exprs: Vec<PhysicalSortExpr>,
+}
+
+impl PhysicalSortPlan {
+    pub fn create(input: PhysicalPlanRef, sort_exprs: Vec<PhysicalSortExpr>) -> PhysicalPlanRef {
+        Arc::new(Self { input, sort_exprs })
+    }
+}
+
+impl PhysicalPlan for PhysicalSortPlan {
+    fn schema(&self) -> &NaiveSchema {
+        self.input.schema()
+    }
+
+    fn execute(&self) -> Result<Vec<RecordBatch>> {
+        let batches = self.input.execute()?;
+        if batches.is_empty() {
+            return Ok(batches);
+        }
+        // 排序需要看到全部数据 先将所有batch合并成一个
+        let single_batch = concat_batches(&batches[0].schema(), &batches)?;
+        if single_batch.num_rows() == 0 {
+            return Ok(vec![single_batch]);
+        }
+
+        let mut sort_columns = vec![];
+        for sort_expr in &self.sort_exprs {
+            sort_columns.push(SortColumn {
+                values: sort_expr.expr.evaluate(&single_batch)?.into_array(),
+                options: Some(sort_expr.options),
+            });
+        }
+        // lexsort 不保证稳定 最后按原来的行号排序，排序键相同的行保持输入中的顺序
+        let row_numbers = UInt64Array::from_iter_values(0..single_batch.num_rows() as u64);
+        sort_columns.push(SortColumn {
+            values: Arc::new(row_numbers),
+            options: None,
+        });
+        let indices = lexsort_to_indices(&sort_columns, None)?;
+
+        let mut columns = vec![];
+        for column in single_batch.columns() {
+            columns.push(take(column.as_ref(), &indices, None)?);
+        }
+        Ok(vec![RecordBatch::try_new(single_batch.schema(), columns)?])
+    }
+
+    fn children(&self) -> Result<Vec<PhysicalPlanRef>> {
+        Ok(vec![self.input.clone()])
+    }
+}
@@ -1,7 +1,8 @@
 use crate::logical_plan::expression::AggregateFunc;
-use crate::logical_plan::schema::NaiveSchema;
+use crate::logical_plan::expression::ScalarValue;
 use crate::physical_plan::CrossJoin;
 use crate::physical_plan::HashJoin;
+use crate::physical_plan::NestedLoopJoinPlan;
 
 use crate::physical_plan::avg::Avg;
 use crate::physical_plan::count::Count;
@@ -10,181 +11,252 @@ use crate::physical_plan::min::Min;
 use crate::physical_plan::sum::Sum;
 use crate::physical_plan::PhysicalAggregatePlan;
 use crate::physical_plan::PhysicalBinaryExpr;
+use crate::physical_plan::PhysicalIsNullExpr;
+use crate::physical_plan::PhysicalNotExpr;
+use crate::physical_plan::PhysicalInSubqueryExpr;
+use crate::physical_plan::PhysicalScalarFunctionExpr;
+use crate::physical_plan::PhysicalCaseExpr;
+use crate::physical_plan::PhysicalDistinctOnPlan;
 use crate::physical_plan::PhysicalExprRef;
 use crate::physical_plan::PhysicalLimitPlan;
 use crate::physical_plan::PhysicalLiteralExpr;
 use crate::physical_plan::PhysicalOffsetPlan;
 use crate::physical_plan::PhysicalPlanRef;
+use crate::physical_plan::PhysicalSortExpr;
+use crate::physical_plan::PhysicalSortPlan;
 use crate::physical_plan::SelectionPlan;
 use crate::physical_plan::UpdatePlan;   // lyx 新增一个UpdatePlan
 use crate::physical_plan::InsertPlan;   // lyx 新增一个InsertPlan
 use crate::physical_plan::DeletePlan;   // lyx 新增一个DeletePlan
 use crate::physical_plan::CreateTablePlan;   // lyx 新增一个CreateTablePlan
+use crate::datasource::OneRowTable;
+use std::sync::Arc;
+use arrow::compute::SortOptions;
+use arrow::datatypes::DataType;
+use crate::logical_plan::schema::{NaiveField, NaiveSchema};
 use crate::{
     error::{ErrorCode, Result},
     logical_plan::{
         expression::{Column, LogicalExpr},
-        plan::LogicalPlan,
+        plan::{LogicalPlan, TableScan},
     },
     physical_plan::{ColumnExpr, ProjectionPlan, ScanPlan},
 };
 
 // 查询规划器（QueryPlanner）通过递归的方式，将不同类型的逻辑计划（LogicalPlan）
 // 转换为对应的物理计划（PhysicalPlan），即为每个逻辑操作（例如 TableScan、Projection、Join 等）生成相应的物理执行计划。
-pub struct QueryPlanner;
+pub struct QueryPlanner {
+    // 分组聚合最多允许的分组数
+    max_groups: usize,
+}
+
+impl Default for QueryPlanner {
+    fn default() -> Self {
+        Self {
+            max_groups: PhysicalAggregatePlan::DEFAULT_MAX_GROUPS,
+        }
+    }
+}
 
 impl QueryPlanner {
+    pub fn with_max_groups(max_groups: usize) -> Self {
+        Self { max_groups }
+    }
+
+    // DELETE 的物理计划 执行时可以直接得到删除后的新表和删除的行数
+    pub fn create_delete_plan(&self, plan: &LogicalPlan) -> Result<Arc<DeletePlan>> {
+        let delete = match plan {
+            LogicalPlan::Delete(delete) => delete,
+            _ => return Err(ErrorCode::LogicalError("expected a DELETE plan".to_string())),
+        };
+        let input = self.create_physical_plan(&delete.input)?;
+        let conditions = self.create_physical_expression(&delete.conditions, plan)?;
+        Ok(DeletePlan::create(input, conditions, delete.source.clone()))
+    }
+
     // 核心方法，根据传入的逻辑计划生成物理计划。
     // 它通过模式匹配（match）对不同类型的逻辑计划进行处理，返回相应的物理计划。
-    pub fn create_physical_plan(plan: &LogicalPlan) -> Result<PhysicalPlanRef> {
+    pub fn create_physical_plan(&self, plan: &LogicalPlan) -> Result<PhysicalPlanRef> {
         match plan {
             // 调用 ScanPlan::create 方法，生成一个物理表扫描计划。
             // ScanPlan 需要提供表的源和可选的列投影。
             LogicalPlan::TableScan(table_scan) => Ok(ScanPlan::create(
                 table_scan.source.clone(),
                 table_scan.projection.clone(),
+                table_scan.schema.clone(),
             )),
             LogicalPlan::CreateTable(create_table) => {
                 Ok(CreateTablePlan::create(create_table.schema.clone()))
             }
-            LogicalPlan::Delete(delete) => {
-                let input = Self::create_physical_plan(&delete.input)?;
-                let conditions = Self::create_physical_expression(&delete.conditions, plan)?;
-                Ok(DeletePlan::create(input, conditions, delete.source.clone()))
-            }
+            LogicalPlan::Delete(_) => Ok(self.create_delete_plan(plan)?),
             LogicalPlan::Insert(insert) => {
-                let input = Self::create_physical_plan(&insert.input)?;
-                Ok(InsertPlan::create( insert.source.clone(), input))
+                let input = self.create_physical_plan(&insert.input)?;
+                // VALUES 中的值不能引用表中的列 以没有列的表作为输入生成表达式
+                let no_columns = LogicalPlan::TableScan(TableScan::new(OneRowTable::create()));
+                let mut values = vec![];
+                for row in &insert.values {
+                    values.push(
+                        row.iter()
+                            .map(|value| self.create_physical_expression(value, &no_columns))
+                            .collect::<Result<Vec<_>>>()?,
+                    );
+                }
+                Ok(InsertPlan::create(insert.columns.clone(), values, input))
             }
             LogicalPlan::Update(update) => {
-                let input = Self::create_physical_plan(&update.input)?;
-                let conditions = Self::create_physical_expression(&update.conditions, plan)?;
-                Ok(UpdatePlan::create(input, conditions,update.assignments.clone()))
+                let input = self.create_physical_plan(&update.input)?;
+                let conditions = self.create_physical_expression(&update.conditions, plan)?;
+                let mut assignments = vec![];
+                for (column, value) in &update.assignments {
+                    let idx = update.input.schema().index_of_column(None, column)?;
+                    assignments.push((idx, self.create_physical_expression(value, plan)?));
+                }
+                Ok(UpdatePlan::create(input, conditions, assignments))
             }
             // Projection 表示一个列选择操作（即 SELECT 子句中的列）。
             // 输入包括输入计划、列的表达式、和输出的字段模式
             LogicalPlan::Projection(proj) => {
-                let input = Self::create_physical_plan(&proj.input)?;
+                let input = self.create_physical_plan(&proj.input)?;
                 let proj_expr = proj
                     .exprs
                     .iter()
-                    .map(|expr| Self::create_physical_expression(expr, &proj.input).unwrap())
-                    .collect::<Vec<_>>();
-                let fields = proj
-                    .exprs
-                    .iter()
-                    .map(|expr| expr.data_field(proj.input.as_ref()).unwrap())
-                    .collect::<Vec<_>>();
-                let proj_schema = NaiveSchema::new(fields);
-                Ok(ProjectionPlan::create(input, proj_schema, proj_expr))
+                    .map(|expr| self.create_physical_expression(expr, &proj.input))
+                    .collect::<Result<Vec<_>>>()?;
+                // 输出模式直接使用逻辑计划中的schema（例如 CTE 会改写字段的限定名）
+                Ok(ProjectionPlan::create(input, proj.schema.clone(), proj_expr))
             }
             LogicalPlan::Limit(limit) => {
-                let plan = Self::create_physical_plan(&limit.input)?;
+                let plan = self.create_physical_plan(&limit.input)?;
                 Ok(PhysicalLimitPlan::create(plan, limit.n))
             }
             LogicalPlan::Offset(offset) => {
-                let plan = Self::create_physical_plan(&offset.input)?;
+                let plan = self.create_physical_plan(&offset.input)?;
                 Ok(PhysicalOffsetPlan::create(plan, offset.n))
             }
+            LogicalPlan::Sort(sort) => {
+                let input = self.create_physical_plan(&sort.input)?;
+                let mut sort_exprs = vec![];
+                for sort_expr in &sort.order_by {
+                    sort_exprs.push(PhysicalSortExpr {
+                        expr: self.create_physical_expression(&sort_expr.expr, &sort.input)?,
+                        options: SortOptions {
+                            descending: !sort_expr.asc,
+                            nulls_first: sort_expr.nulls_first,
+                        },
+                    });
+                }
+                Ok(PhysicalSortPlan::create(input, sort_exprs))
+            }
+            LogicalPlan::DistinctOn(distinct_on) => {
+                let input = self.create_physical_plan(&distinct_on.input)?;
+                let mut on_expr = vec![];
+                for expr in &distinct_on.on_expr {
+                    on_expr.push(self.create_physical_expression(expr, &distinct_on.input)?);
+                }
+                Ok(PhysicalDistinctOnPlan::create(input, on_expr))
+            }
             // 对于连接操作，代码生成 HashJoin 物理计划。HashJoin 是一种高效的连接算法，它使用哈希表来实现连接。
             LogicalPlan::Join(join) => {
-                let left = Self::create_physical_plan(&join.left)?;
-                let right = Self::create_physical_plan(&join.right)?;
+                let left = self.create_physical_plan(&join.left)?;
+                let right = self.create_physical_plan(&join.right)?;
+                // 没有等值连接键时对每一对行计算连接条件 使用嵌套循环连接
+                if let (true, Some(filter)) = (join.on.is_empty(), &join.filter) {
+                    let predicate = self.create_physical_expression(filter, plan)?;
+                    return Ok(NestedLoopJoinPlan::create(
+                        left,
+                        right,
+                        predicate,
+                        join.join_type,
+                        join.schema.clone(),
+                    ));
+                }
+                let filter = match &join.filter {
+                    Some(filter) => Some(self.create_physical_expression(filter, plan)?),
+                    None => None,
+                };
                 // 这里目前是使用的哈希连接算法，后续可以考虑改用其他算法。
                 Ok(HashJoin::create(
                     left,
                     right,
                     join.on.clone(),
+                    filter,
                     join.join_type,
                     join.schema.clone(),
                 ))
             }
             LogicalPlan::Filter(filter) => {
-                let predicate = Self::create_physical_expression(&filter.predicate, plan)?;
-                let input = Self::create_physical_plan(&filter.input)?;
+                let predicate = self.create_physical_expression(&filter.predicate, plan)?;
+                let input = self.create_physical_plan(&filter.input)?;
                 Ok(SelectionPlan::create(input, predicate))
             }
             // 聚合操作，处理聚合函数Count、Sum、Avg、Max、Min。
             LogicalPlan::Aggregate(aggr) => {
                 let mut group_exprs = vec![];
                 for group_expr in &aggr.group_expr {
-                    group_exprs.push(Self::create_physical_expression(group_expr, &aggr.input)?);
+                    group_exprs.push(self.create_physical_expression(group_expr, &aggr.input)?);
+                }
+
+                // 聚合算子按列读取参数：参数不是列时（例如 sum(a * 2)），先通过投影在输入后面追加计算出的列
+                let input_fields = aggr.input.schema().fields().clone();
+                let mut arg_fields = input_fields.clone();
+                let mut arg_exprs = vec![];
+                for i in 0..input_fields.len() {
+                    arg_exprs.push(ColumnExpr::try_create(None, Some(i))?);
                 }
 
                 let mut aggr_ops = vec![];
                 for aggr_expr in &aggr.aggr_expr {
-                    let aggr_op = match aggr_expr.fun {
-                        AggregateFunc::Count => {
-                            let expr =
-                                Self::create_physical_expression(&aggr_expr.args, &aggr.input)?;
-                            let col_expr = expr.as_any().downcast_ref::<ColumnExpr>();
-                            if let Some(col_expr) = col_expr {
-                                Count::create(col_expr.clone())
-                            } else {
-                                return Err(ErrorCode::PlanError(
-                                    "Aggregate Func should have a column in it".to_string(),
-                                ));
-                            }
-                        }
-                        AggregateFunc::Sum => {
-                            let expr =
-                                Self::create_physical_expression(&aggr_expr.args, &aggr.input)?;
-                            let col_expr = expr.as_any().downcast_ref::<ColumnExpr>();
-                            if let Some(col_expr) = col_expr {
-                                Sum::create(col_expr.clone())
-                            } else {
-                                return Err(ErrorCode::PlanError(
-                                    "Aggregate Func should have a column in it".to_string(),
-                                ));
-                            }
-                        }
-                        AggregateFunc::Avg => {
-                            let expr =
-                                Self::create_physical_expression(&aggr_expr.args, &aggr.input)?;
-                            let col_expr = expr.as_any().downcast_ref::<ColumnExpr>();
-                            if let Some(col_expr) = col_expr {
-                                Avg::create(col_expr.clone())
-                            } else {
-                                return Err(ErrorCode::PlanError(
-                                    "Aggregate Func should have a column in it".to_string(),
-                                ));
-                            }
-                        }
-                        AggregateFunc::Min => {
-                            let expr =
-                                Self::create_physical_expression(&aggr_expr.args, &aggr.input)?;
-                            let col_expr = expr.as_any().downcast_ref::<ColumnExpr>();
-                            if let Some(col_expr) = col_expr {
-                                Min::create(col_expr.clone())
-                            } else {
-                                return Err(ErrorCode::PlanError(
-                                    "Aggregate Func should have a column in it".to_string(),
-                                ));
-                            }
-                        }
-                        AggregateFunc::Max => {
-                            let expr =
-                                Self::create_physical_expression(&aggr_expr.args, &aggr.input)?;
-                            let col_expr = expr.as_any().downcast_ref::<ColumnExpr>();
-                            if let Some(col_expr) = col_expr {
-                                Max::create(col_expr.clone())
-                            } else {
-                                return Err(ErrorCode::PlanError(
-                                    "Aggregate Func should have a column in it".to_string(),
-                                ));
+                    let (expr, arg_field) = match aggr_expr.args.as_ref() {
+                        // count(*) 统计所有的行 参数换成不为空的常量
+                        LogicalExpr::Wildcard => (
+                            PhysicalLiteralExpr::create(ScalarValue::Int64(Some(1))),
+                            NaiveField::new(None, "*", DataType::Int64, false),
+                        ),
+                        args => (
+                            self.create_physical_expression(args, &aggr.input)?,
+                            args.data_field(&aggr.input)?,
+                        ),
+                    };
+                    let arg_type = arg_field.data_type().clone();
+                    let col_expr = match expr.as_any().downcast_ref::<ColumnExpr>() {
+                        Some(col_expr) => col_expr.clone(),
+                        None => {
+                            arg_fields.push(arg_field);
+                            arg_exprs.push(expr);
+                            ColumnExpr {
+                                name: None,
+                                idx: Some(arg_exprs.len() - 1),
                             }
                         }
                     };
+                    let aggr_op = match aggr_expr.fun {
+                        AggregateFunc::Count => Count::create(col_expr, aggr_expr.distinct),
+                        AggregateFunc::Sum => Sum::create(col_expr, arg_type),
+                        AggregateFunc::Avg => Avg::create(col_expr),
+                        AggregateFunc::Min => Min::create(col_expr, arg_type),
+                        AggregateFunc::Max => Max::create(col_expr, arg_type),
+                    };
                     aggr_ops.push(aggr_op);
                 }
 
-                let input = Self::create_physical_plan(&aggr.input)?;
-                Ok(PhysicalAggregatePlan::create(group_exprs, aggr_ops, input))
+                let input = self.create_physical_plan(&aggr.input)?;
+                let input = if arg_fields.len() > input_fields.len() {
+                    ProjectionPlan::create(input, NaiveSchema::new(arg_fields), arg_exprs)
+                } else {
+                    input
+                };
+                Ok(PhysicalAggregatePlan::create(
+                    group_exprs,
+                    aggr_ops,
+                    input,
+                    aggr.schema.clone(),
+                    self.max_groups,
+                ))
             }
             // 对于交叉连接，即没有指定连接条件的连接，我们直接使用笛卡尔积的方式进行连接
             LogicalPlan::CrossJoin(join) => {
-                let left = Self::create_physical_plan(&join.left)?;
-                let right = Self::create_physical_plan(&join.right)?;
+                let left = self.create_physical_plan(&join.left)?;
+                let right = self.create_physical_plan(&join.right)?;
                 Ok(CrossJoin::create(
                     left,
                     right,
@@ -197,34 +269,76 @@ impl QueryPlanner {
 
     // 将查询中存在的逻辑表达式LogicalExpr转换为物理表达式PhysicalExpr
     pub fn create_physical_expression(
+        &self,
         expr: &LogicalExpr,
         input: &LogicalPlan,
     ) -> Result<PhysicalExprRef> {
         match expr {
-            LogicalExpr::Alias(_, _) => todo!(),
+            // 别名只影响输出字段名，直接计算内部表达式
+            LogicalExpr::Alias(expr, _) => self.create_physical_expression(expr, input),
             // 对于列引用，我们需要找到对应的列索引，并生成 ColumnExpr。 这是最简单的情况，也是我们目前所需的。
-            LogicalExpr::Column(Column { name, .. }) => {
-                for (idx, field) in input.schema().fields().iter().enumerate() {
-                    if field.name() == name {
-                        return ColumnExpr::try_create(None, Some(idx));
-                    }
-                }
-                Err(ErrorCode::ColumnNotExists(format!(
-                    "column `{}` not exists",
-                    name
-                )))
+            // 带表名的列（如 t.id）还需要匹配字段的限定名
+            LogicalExpr::Column(Column { table, name }) => {
+                let idx = input.schema().index_of_column(table.as_deref(), name)?;
+                ColumnExpr::try_create(None, Some(idx))
             }
             // 对于常量表达式，我们生成一个 PhysicalLiteralExpr。
             LogicalExpr::Literal(scalar_val) => Ok(PhysicalLiteralExpr::create(scalar_val.clone())),
             // 对于二元表达式，我们递归地生成左右子表达式，并生成 PhysicalBinaryExpr。
             LogicalExpr::BinaryExpr(bin_expr) => {
-                let left = Self::create_physical_expression(bin_expr.left.as_ref(), input)?;
-                let right = Self::create_physical_expression(bin_expr.right.as_ref(), input)?;
+                let left = self.create_physical_expression(bin_expr.left.as_ref(), input)?;
+                let right = self.create_physical_expression(bin_expr.right.as_ref(), input)?;
                 let phy_bin_expr = PhysicalBinaryExpr::create(left, bin_expr.op.clone(), right);
                 Ok(phy_bin_expr)
             }
-            LogicalExpr::AggregateFunction(_) => todo!(),
-            LogicalExpr::Wildcard => todo!(),
+            LogicalExpr::IsNull(expr) => Ok(PhysicalIsNullExpr::create(
+                self.create_physical_expression(expr, input)?,
+                false,
+            )),
+            LogicalExpr::IsNotNull(expr) => Ok(PhysicalIsNullExpr::create(
+                self.create_physical_expression(expr, input)?,
+                true,
+            )),
+            LogicalExpr::Not(expr) => Ok(PhysicalNotExpr::create(
+                self.create_physical_expression(expr, input)?,
+            )),
+            LogicalExpr::ScalarFunction(func) => Ok(PhysicalScalarFunctionExpr::create(
+                func.fun,
+                func.args
+                    .iter()
+                    .map(|arg| self.create_physical_expression(arg, input))
+                    .collect::<Result<Vec<_>>>()?,
+            )),
+            LogicalExpr::InSubquery(in_subquery) => Ok(PhysicalInSubqueryExpr::create(
+                self.create_physical_expression(&in_subquery.expr, input)?,
+                self.create_physical_plan(&in_subquery.subquery)?,
+                in_subquery.negated,
+            )),
+            // 各分支的结果在执行时转换为 CASE 的输出类型
+            LogicalExpr::Case { when_then, else_expr } => {
+                let data_type = expr.data_field(input)?.data_type().clone();
+                let mut physical_when_then = vec![];
+                for (when, then) in when_then {
+                    physical_when_then.push((
+                        self.create_physical_expression(when, input)?,
+                        self.create_physical_expression(then, input)?,
+                    ));
+                }
+                let else_expr = match else_expr {
+                    Some(expr) => Some(self.create_physical_expression(expr, input)?),
+                    None => None,
+                };
+                Ok(PhysicalCaseExpr::create(physical_when_then, else_expr, data_type))
+            }
+            // 预处理语句执行前只替换 WHERE 中的占位符
+            LogicalExpr::Placeholder(param) => Err(ErrorCode::PlanError(format!(
+                "parameter ${} can only be used in WHERE",
+                param
+            ))),
+            // 聚合函数只能出现在聚合计划中，通配符在规划投影时已经展开
+            LogicalExpr::AggregateFunction(_) | LogicalExpr::Wildcard => Err(ErrorCode::PlanError(
+                format!("{:?} can not be used here", expr),
+            )),
         }
     }
 }
@@ -2,22 +2,637 @@
 // 利用外部的crate，这并不是rust标准库的一部分
 // 需要在Cargo.toml中进行声明它是项目的依赖 sqlparser = "0.9.0"
 use sqlparser::{   
-    ast::Statement,            // 解析后的 SQL 抽象语法树（AST）的主要结构
-    dialect::GenericDialect,   // SQL通用方言，支持标准SQL语法 需要解析特定数据库的 SQL，可以替换为对应的方言（如 PostgreSqlDialect）
+    ast::{Expr, Select, SetExpr, Statement},  // 解析后的 SQL 抽象语法树（AST）的主要结构
+    dialect::{keywords::Keyword, GenericDialect},   // SQL通用方言，支持标准SQL语法 需要解析特定数据库的 SQL，可以替换为对应的方言（如 PostgreSqlDialect）
     parser::{Parser, ParserError},
-    tokenizer::Tokenizer,   // 词法分析器
+    tokenizer::{Token, Tokenizer, Whitespace},   // 词法分析器
 };
 
+use crate::error::{ErrorCode, Result};
+use crate::logical_plan::check_values_arity;
+
+/// sqlparser 0.9 不支持 `SELECT DISTINCT ON (...)`，解析前去掉 `ON (...)`，
+/// 去掉之后的 Select 和它的去重键一起保存在这里，SQLPlanner 规划 Select 时按内容查找去重键
+#[derive(Debug, Clone, Default)]
+pub struct DistinctOnKeys(Vec<(Select, Vec<Expr>)>);
+
+impl DistinctOnKeys {
+    pub fn get(&self, select: &Select) -> Option<&[Expr]> {
+        self.0
+            .iter()
+            .find(|(s, _)| s == select)
+            .map(|(_, keys)| keys.as_slice())
+    }
+}
+
 /// SQL Parser
 pub struct SQLParser;   // 空结构体，没有内部字段，仅作为命名空间来定义相关的方法
 
 impl SQLParser {
-    // 成功时返回 SQL AST（statement） 失败时返回ParserError并描述遇到的问题
-    pub fn parse(sql: &str) -> Result<Statement, ParserError> {
-        let dialect = GenericDialect {}; 
+    // 成功时返回 SQL AST（statement）以及其中 DISTINCT ON 的去重键 失败时返回ParserError并描述遇到的问题
+    pub fn parse(sql: &str) -> Result<(Statement, DistinctOnKeys)> {
+        let tokens = Self::tokenize(sql)?;
+        Self::check_placeholders(&tokens)?;
+        Self::parse_tokens(tokens)
+    }
+
+    // 预处理语句使用 允许 `?`、`$1` 形式的参数占位符，`?` 按出现的顺序从 1 开始编号
+    pub fn parse_with_placeholders(sql: &str) -> Result<(Statement, DistinctOnKeys)> {
+        let tokens = Self::mark_placeholders(Self::tokenize(sql)?)?;
+        Self::parse_tokens(tokens)
+    }
+
+    fn tokenize(sql: &str) -> Result<Vec<Token>> {
+        let dialect = GenericDialect {};
         let mut tokenizer = Tokenizer::new(&dialect, sql);
-        let tokens = tokenizer.tokenize()?; //  SQL 字符串分解为标记（tokens） ? 操作符会在词法分析失败时提前返回错误。
+        let tokens = tokenizer.tokenize().map_err(ParserError::from)?; //  SQL 字符串分解为标记（tokens） ? 操作符会在词法分析失败时提前返回错误。
+        Ok(tokens)
+    }
+
+    fn parse_tokens(tokens: Vec<Token>) -> Result<(Statement, DistinctOnKeys)> {
+        let dialect = GenericDialect {};
+        Self::check_values_rows(&tokens)?;
+        let tokens = Self::strip_lock_clauses(tokens);
+        let tokens = Self::rewrite_empty_in_lists(tokens);
+        let tokens = Self::rewrite_ilike(tokens)?;
+        let tokens = Self::rewrite_substring_commas(tokens);
+        let (tokens, distinct_on) = Self::extract_distinct_on(tokens)?;
         let mut parser = Parser::new(tokens, &dialect);
-        parser.parse_statement()     // 解析结果是AST 类型是Statement::Query
+        let statement = parser.parse_statement()?;     // 解析结果是AST 类型是Statement::Query
+        Ok((statement, distinct_on))
+    }
+
+    /// 将脚本按分号拆分成多条语句，返回每条语句及其起始行号（从 1 开始）。
+    /// 引号内的分号和 `--` 注释中的分号不会拆分语句，空语句会被忽略
+    pub fn split_statements(script: &str) -> Vec<(usize, String)> {
+        let mut statements = vec![];
+        let mut current = String::new();
+        let mut start_line = None;
+        let mut line = 1;
+        let mut quote: Option<char> = None;
+        let mut chars = script.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match quote {
+                // 引号内的内容原样保留 连续两个引号表示转义
+                Some(q) => {
+                    current.push(c);
+                    if c == q {
+                        if chars.peek() == Some(&q) {
+                            current.push(chars.next().unwrap());
+                        } else {
+                            quote = None;
+                        }
+                    }
+                }
+                None => match c {
+                    '\'' | '"' => {
+                        quote = Some(c);
+                        current.push(c);
+                    }
+                    '-' if chars.peek() == Some(&'-') => {
+                        // 跳过行注释 保留换行用于计算行号
+                        while let Some(&next) = chars.peek() {
+                            if next == '\n' {
+                                break;
+                            }
+                            chars.next();
+                        }
+                        continue;
+                    }
+                    ';' => {
+                        if let Some(start) = start_line.take() {
+                            statements.push((start, current.trim().to_string()));
+                        }
+                        current.clear();
+                        continue;
+                    }
+                    _ => current.push(c),
+                },
+            }
+            if start_line.is_none() && !c.is_whitespace() {
+                start_line = Some(line);
+            }
+            if c == '\n' {
+                line += 1;
+            }
+        }
+        if let Some(start) = start_line {
+            statements.push((start, current.trim().to_string()));
+        }
+        statements
+    }
+
+    // 不支持 `?`、`$1` 形式的参数占位符，在解析前给出明确的错误，而不是解析器的语法错误
+    fn check_placeholders(tokens: &[Token]) -> Result<()> {
+        let has_placeholder = tokens.iter().enumerate().any(|(i, token)| match token {
+            Token::Char('?') => true,
+            Token::Char('$') => matches!(tokens.get(i + 1), Some(Token::Number(_, _))),
+            _ => false,
+        });
+        if has_placeholder {
+            return Err(ErrorCode::NotSupported(
+                "parameter placeholders are not supported".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    // sqlparser 0.9 对 `VALUES` 后没有任何行或者某一行为 `()` 只给出语法错误，
+    // 这里在解析前统计每一行值的个数，交给与规划时相同的 check_values_arity 给出明确的错误
+    fn check_values_rows(tokens: &[Token]) -> Result<()> {
+        let significant = tokens
+            .iter()
+            .filter(|token| !matches!(token, Token::Whitespace(_)))
+            .collect::<Vec<_>>();
+        let values_at = match significant
+            .iter()
+            .position(|token| matches!(token, Token::Word(w) if w.keyword == Keyword::VALUES))
+        {
+            Some(values_at) => values_at,
+            None => return Ok(()),
+        };
+        // 只看最外层的括号 括号前面是 VALUES 或者逗号时才是一行值，函数调用 `now()` 不受影响
+        let rows = &significant[values_at + 1..];
+        let mut row_lens = vec![];
+        let mut depth = 0;
+        for (i, token) in rows.iter().enumerate() {
+            match token {
+                Token::LParen => {
+                    if depth == 0 {
+                        row_lens.push(if rows.get(i + 1) == Some(&&Token::RParen) { 0 } else { 1 });
+                    }
+                    depth += 1;
+                }
+                Token::RParen => depth -= 1,
+                Token::Comma if depth == 1 => {
+                    if let Some(len) = row_lens.last_mut() {
+                        *len += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        check_values_arity(&row_lens, None)
+    }
+
+    // 把 `?` 和 `$n` 改写为 `$n` 形式的数值 token，解析后是 `Value::Number("$n")`，规划时转换为 LogicalExpr::Placeholder。
+    // tokenizer 不会产生以 `$` 开头的数值 不会与用户写的数值或者列名冲突
+    fn mark_placeholders(tokens: Vec<Token>) -> Result<Vec<Token>> {
+        let mut rewritten = Vec::with_capacity(tokens.len());
+        let mut next_param = 1;
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            let param = match &token {
+                Token::Char('?') => {
+                    let param = next_param;
+                    next_param += 1;
+                    param
+                }
+                Token::Char('$') if matches!(tokens.peek(), Some(Token::Number(_, _))) => {
+                    let number = match tokens.next() {
+                        Some(Token::Number(number, _)) => number,
+                        _ => unreachable!(),
+                    };
+                    match number.parse::<usize>() {
+                        Ok(param) if param > 0 => param,
+                        _ => {
+                            return Err(ErrorCode::PlanError(format!(
+                                "invalid parameter placeholder ${}",
+                                number
+                            )))
+                        }
+                    }
+                }
+                _ => {
+                    rewritten.push(token);
+                    continue;
+                }
+            };
+            rewritten.push(Token::Number(format!("${}", param), false));
+        }
+        Ok(rewritten)
+    }
+
+    // sqlparser 0.9 不支持 `SELECT ... FOR UPDATE` 等行锁子句，这里没有并发的事务 不需要加锁，直接去掉：
+    // FOR [NO KEY] UPDATE | FOR [KEY] SHARE [OF table [, ...]] [NOWAIT | SKIP LOCKED]，可以出现多次。
+    // 只去掉语句末尾的行锁子句，出现在其他位置时保留 交给 sqlparser 报错
+    fn strip_lock_clauses(tokens: Vec<Token>) -> Vec<Token> {
+        let significant = tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, token)| !matches!(token, Token::Whitespace(_)))
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        let is_word = |k: usize, name: &str| {
+            significant.get(k).is_some_and(|&idx| {
+                matches!(&tokens[idx], Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case(name))
+            })
+        };
+        let is_token = |k: usize, token: Token| significant.get(k).is_some_and(|&idx| tokens[idx] == token);
+        let is_name = |k: usize| significant.get(k).is_some_and(|&idx| matches!(tokens[idx], Token::Word(_)));
+
+        // 每个行锁子句在 significant 中的范围 [start, end)
+        let mut clauses = vec![];
+        let mut i = 0;
+        while i < significant.len() {
+            let strength = if !is_word(i, "FOR") {
+                0
+            } else if is_word(i + 1, "UPDATE") || is_word(i + 1, "SHARE") {
+                1
+            } else if is_word(i + 1, "KEY") && is_word(i + 2, "SHARE") {
+                2
+            } else if is_word(i + 1, "NO") && is_word(i + 2, "KEY") && is_word(i + 3, "UPDATE") {
+                3
+            } else {
+                0
+            };
+            if strength == 0 {
+                i += 1;
+                continue;
+            }
+            let mut j = i + 1 + strength;
+            if is_word(j, "OF") && is_name(j + 1) {
+                j += 2;
+                loop {
+                    // schema.table 或者 `, table`
+                    if (is_token(j, Token::Period) || is_token(j, Token::Comma)) && is_name(j + 1) {
+                        j += 2;
+                    } else {
+                        break;
+                    }
+                }
+            }
+            if is_word(j, "NOWAIT") {
+                j += 1;
+            } else if is_word(j, "SKIP") && is_word(j + 1, "LOCKED") {
+                j += 2;
+            }
+            clauses.push((i, j));
+            i = j;
+        }
+
+        // 从语句末尾（忽略结尾的分号）向前 只有紧挨着的行锁子句才去掉
+        let mut end = significant.len();
+        if end > 0 && is_token(end - 1, Token::SemiColon) {
+            end -= 1;
+        }
+        let mut remove = vec![false; tokens.len()];
+        while let Some(&(start, clause_end)) = clauses.last() {
+            if clause_end != end {
+                break;
+            }
+            for flag in &mut remove[significant[start]..=significant[clause_end - 1]] {
+                *flag = true;
+            }
+            clauses.pop();
+            end = start;
+        }
+        tokens
+            .into_iter()
+            .zip(remove)
+            .filter(|(_, remove)| !remove)
+            .map(|(token, _)| token)
+            .collect()
+    }
+
+    // 二元运算符左操作数的起始位置（significant 中的序号），operand_end 是运算符的位置。
+    // 向前查找，遇到同一层的左括号、逗号、比较运算符或者 AND、WHERE 等关键字时停止
+    fn operand_start(tokens: &[Token], significant: &[usize], operand_end: usize) -> usize {
+        let mut start = operand_end;
+        let mut depth = 0;
+        while start > 0 {
+            match &tokens[significant[start - 1]] {
+                Token::RParen => depth += 1,
+                Token::LParen if depth == 0 => break,
+                Token::LParen => depth -= 1,
+                // CASE ... END 作为一个整体
+                Token::Word(w) if w.keyword == Keyword::END => depth += 1,
+                Token::Word(w) if w.keyword == Keyword::CASE && depth > 0 => depth -= 1,
+                _ if depth > 0 => {}
+                Token::Comma
+                | Token::SemiColon
+                | Token::Eq
+                | Token::Neq
+                | Token::Lt
+                | Token::Gt
+                | Token::LtEq
+                | Token::GtEq => break,
+                Token::Word(w)
+                    if matches!(
+                        w.keyword,
+                        Keyword::AND
+                            | Keyword::OR
+                            | Keyword::NOT
+                            | Keyword::IS
+                            | Keyword::SELECT
+                            | Keyword::DISTINCT
+                            | Keyword::WHERE
+                            | Keyword::HAVING
+                            | Keyword::ON
+                            | Keyword::BY
+                            | Keyword::SET
+                            | Keyword::CASE
+                            | Keyword::WHEN
+                            | Keyword::THEN
+                            | Keyword::ELSE
+                    ) =>
+                {
+                    break
+                }
+                _ => {}
+            }
+            start -= 1;
+        }
+        start
+    }
+
+    // sqlparser 0.9 不接受 `IN ()`：把 `expr IN ()` 整体改写为 FALSE，`expr NOT IN ()` 改写为 TRUE。
+    fn rewrite_empty_in_lists(tokens: Vec<Token>) -> Vec<Token> {
+        let is_keyword = |token: &Token, keyword: Keyword| matches!(token, Token::Word(w) if w.keyword == keyword);
+        let significant = tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, token)| !matches!(token, Token::Whitespace(_)))
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        // 每个需要改写的范围：(左操作数的起始位置, 右括号的位置, 是否为 NOT IN)
+        let mut spans: Vec<(usize, usize, bool)> = vec![];
+        for (pos, window) in significant.windows(3).enumerate() {
+            let is_empty_in = is_keyword(&tokens[window[0]], Keyword::IN)
+                && tokens[window[1]] == Token::LParen
+                && tokens[window[2]] == Token::RParen;
+            if !is_empty_in {
+                continue;
+            }
+            let negated = pos > 0 && is_keyword(&tokens[significant[pos - 1]], Keyword::NOT);
+            let operand_end = if negated { pos - 1 } else { pos };
+            let start = Self::operand_start(&tokens, &significant, operand_end);
+            // 没有左操作数时保持原样 由解析器报错
+            if start < operand_end {
+                spans.push((significant[start], window[2], negated));
+            }
+        }
+        if spans.is_empty() {
+            return tokens;
+        }
+        let mut rewritten = Vec::with_capacity(tokens.len());
+        let mut spans = spans.into_iter().peekable();
+        let mut skip_until = None;
+        for (idx, token) in tokens.into_iter().enumerate() {
+            if let Some(end) = skip_until {
+                if idx <= end {
+                    continue;
+                }
+                skip_until = None;
+            }
+            // 嵌套的 `IN ()` 已经包含在外层的范围中
+            while spans.peek().is_some_and(|&(start, _, _)| start < idx) {
+                spans.next();
+            }
+            match spans.peek() {
+                Some(&(start, end, negated)) if start == idx => {
+                    rewritten.push(Token::make_keyword(if negated { "TRUE" } else { "FALSE" }));
+                    skip_until = Some(end);
+                }
+                _ => rewritten.push(token),
+            }
+        }
+        rewritten
+    }
+
+    // sqlparser 0.9 不支持 ILIKE：把 `a [NOT] ILIKE b` 改写为 `LOWER(a) [NOT] LIKE LOWER(b)`，两侧都转为小写后按 LIKE 匹配。
+    // 左操作数由 operand_start 向前查找，右操作数交给解析器按 LIKE 的优先级解析一个表达式来确定范围
+    fn rewrite_ilike(mut tokens: Vec<Token>) -> Result<Vec<Token>> {
+        // 与 sqlparser 中 LIKE 的优先级相同
+        const LIKE_PRECEDENCE: u8 = 20;
+        let is_ilike = |token: &Token| {
+            matches!(token, Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case("ILIKE"))
+        };
+        loop {
+            let significant = tokens
+                .iter()
+                .enumerate()
+                .filter(|(_, token)| !matches!(token, Token::Whitespace(_)))
+                .map(|(idx, _)| idx)
+                .collect::<Vec<_>>();
+            let pos = match significant.iter().position(|&idx| is_ilike(&tokens[idx])) {
+                Some(pos) => pos,
+                None => return Ok(tokens),
+            };
+            let negated = pos > 0 && matches!(&tokens[significant[pos - 1]], Token::Word(w) if w.keyword == Keyword::NOT);
+            let operand_end = if negated { pos - 1 } else { pos };
+            let start = Self::operand_start(&tokens, &significant, operand_end);
+            if start == operand_end {
+                return Err(ErrorCode::ParserError(ParserError::ParserError(
+                    "Expected an expression before ILIKE".to_string(),
+                )));
+            }
+
+            let dialect = GenericDialect {};
+            let mut parser = Parser::new(tokens[significant[pos] + 1..].to_vec(), &dialect);
+            parser.parse_subexpr(LIKE_PRECEDENCE)?;
+            let mut remaining = 0;
+            while parser.next_token() != Token::EOF {
+                remaining += 1;
+            }
+            let pattern_end = significant[significant.len() - 1 - remaining];
+
+            let lower = |operand: &[Token]| {
+                let mut wrapped = vec![Token::make_word("LOWER", None), Token::LParen];
+                wrapped.extend_from_slice(operand);
+                wrapped.push(Token::RParen);
+                wrapped
+            };
+            let mut rewritten = tokens[..significant[start]].to_vec();
+            rewritten.extend(lower(&tokens[significant[start]..=significant[operand_end - 1]]));
+            rewritten.push(Token::Whitespace(Whitespace::Space));
+            if negated {
+                rewritten.push(Token::make_keyword("NOT"));
+                rewritten.push(Token::Whitespace(Whitespace::Space));
+            }
+            rewritten.push(Token::make_keyword("LIKE"));
+            rewritten.push(Token::Whitespace(Whitespace::Space));
+            rewritten.extend(lower(&tokens[significant[pos] + 1..=pattern_end]));
+            rewritten.extend_from_slice(&tokens[pattern_end + 1..]);
+            tokens = rewritten;
+        }
+    }
+
+    // sqlparser 0.9 只接受 `SUBSTRING(expr FROM start FOR len)`，
+    // 逗号分隔参数的 `SUBSTRING(expr, start, len)` 把 SUBSTRING 改为普通的标识符，按普通函数调用解析
+    fn rewrite_substring_commas(mut tokens: Vec<Token>) -> Vec<Token> {
+        for idx in 0..tokens.len() {
+            let is_substring = matches!(&tokens[idx], Token::Word(w) if w.keyword == Keyword::SUBSTRING);
+            if !is_substring {
+                continue;
+            }
+            let open = tokens
+                .iter()
+                .skip(idx + 1)
+                .position(|token| !matches!(token, Token::Whitespace(_)))
+                .map(|offset| idx + 1 + offset);
+            let open = match open {
+                Some(open) if tokens[open] == Token::LParen => open,
+                _ => continue,
+            };
+            let mut depth = 0;
+            let mut has_comma = false;
+            for token in tokens.iter().skip(open) {
+                match token {
+                    Token::LParen => depth += 1,
+                    Token::RParen => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    Token::Comma if depth == 1 => {
+                        has_comma = true;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            if has_comma {
+                if let Token::Word(word) = &mut tokens[idx] {
+                    word.keyword = Keyword::NoKeyword;
+                }
+            }
+        }
+        tokens
+    }
+
+    // 去掉每个 `SELECT DISTINCT ON (...)` 中的 `ON (...)`，查询的任意位置（CTE、子查询、UNION 的每一侧）都可以出现。
+    // 之后单独解析每个 `SELECT DISTINCT` 得到去掉 `ON (...)` 后的 Select，与括号内的去重键对应起来
+    fn extract_distinct_on(tokens: Vec<Token>) -> Result<(Vec<Token>, DistinctOnKeys)> {
+        let is_keyword = |token: &Token, keyword: Keyword| matches!(token, Token::Word(w) if w.keyword == keyword);
+        let next_significant = |tokens: &[Token], from: usize| {
+            (from..tokens.len()).find(|&idx| !matches!(tokens[idx], Token::Whitespace(_)))
+        };
+
+        // 1. 去掉 `ON (...)`，记录每个 `SELECT DISTINCT` 在剩余 tokens 中的位置以及它的去重键
+        let mut rest = Vec::with_capacity(tokens.len());
+        let mut selects: Vec<(usize, Option<Vec<Token>>)> = vec![];
+        let mut idx = 0;
+        while idx < tokens.len() {
+            rest.push(tokens[idx].clone());
+            if !is_keyword(&tokens[idx], Keyword::SELECT) {
+                idx += 1;
+                continue;
+            }
+            let select_at = rest.len() - 1;
+            let distinct = match next_significant(&tokens, idx + 1) {
+                Some(distinct) if is_keyword(&tokens[distinct], Keyword::DISTINCT) => distinct,
+                _ => {
+                    idx += 1;
+                    continue;
+                }
+            };
+            rest.extend_from_slice(&tokens[idx + 1..=distinct]);
+            idx = distinct + 1;
+            let on = next_significant(&tokens, idx).filter(|&on| is_keyword(&tokens[on], Keyword::ON));
+            let open = on
+                .and_then(|on| next_significant(&tokens, on + 1))
+                .filter(|&open| tokens[open] == Token::LParen);
+            let open = match open {
+                Some(open) => open,
+                None => {
+                    selects.push((select_at, None));
+                    continue;
+                }
+            };
+            // 找到与左括号匹配的右括号
+            let close = Self::matching_paren(&tokens, open).ok_or_else(|| {
+                ParserError::ParserError("Expected ), found: EOF".to_string())
+            })?;
+            selects.push((select_at, Some(tokens[open + 1..close].to_vec())));
+            idx = close + 1;
+        }
+        if selects.iter().all(|(_, on)| on.is_none()) {
+            return Ok((rest, DistinctOnKeys::default()));
+        }
+
+        // 2. 单独解析每个 `SELECT DISTINCT`，得到与完整语句中相同的 Select
+        let dialect = GenericDialect {};
+        let mut parsed: Vec<(Select, Option<Vec<Expr>>)> = vec![];
+        for (select_at, on_tokens) in selects {
+            let end = Self::select_end(&rest, select_at);
+            let query = Parser::new(rest[select_at..end].to_vec(), &dialect).parse_query()?;
+            let select = match query.body {
+                SetExpr::Select(select) => *select,
+                _ => continue,
+            };
+            let keys = match on_tokens {
+                Some(on_tokens) => Some(Parser::new(on_tokens, &dialect).parse_comma_separated(Parser::parse_expr)?),
+                None => None,
+            };
+            parsed.push((select, keys));
+        }
+
+        // 3. 只按内容查找去重键，内容相同但去重键不同的两个 Select 无法区分
+        let mut keys = vec![];
+        for (select, on) in &parsed {
+            let conflict = parsed.iter().any(|(other, other_on)| other == select && other_on != on);
+            if conflict {
+                return Err(ErrorCode::NotSupported(format!(
+                    "the same SELECT DISTINCT appears with different DISTINCT ON keys: {}",
+                    select
+                )));
+            }
+            if let Some(on) = on {
+                keys.push((select.clone(), on.clone()));
+            }
+        }
+        Ok((rest, DistinctOnKeys(keys)))
+    }
+
+    // 与 open 处的左括号匹配的右括号
+    fn matching_paren(tokens: &[Token], open: usize) -> Option<usize> {
+        let mut depth = 0;
+        for (idx, token) in tokens.iter().enumerate().skip(open) {
+            match token {
+                Token::LParen => depth += 1,
+                Token::RParen => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(idx);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    // 从 start 处的 SELECT 开始的 Select 在哪里结束：同一层的集合运算、ORDER BY、LIMIT 等子句，
+    // 包住它的右括号，分号或者语句的结尾
+    fn select_end(tokens: &[Token], start: usize) -> usize {
+        let mut depth = 0;
+        for (idx, token) in tokens.iter().enumerate().skip(start) {
+            match token {
+                Token::LParen => depth += 1,
+                Token::RParen if depth == 0 => return idx,
+                Token::RParen => depth -= 1,
+                Token::SemiColon if depth == 0 => return idx,
+                Token::Word(w) if depth == 0 => {
+                    let ends_select = matches!(
+                        w.keyword,
+                        Keyword::UNION
+                            | Keyword::EXCEPT
+                            | Keyword::INTERSECT
+                            | Keyword::ORDER
+                            | Keyword::LIMIT
+                            | Keyword::OFFSET
+                            | Keyword::FETCH
+                    );
+                    if ends_select {
+                        return idx;
+                    }
+                }
+                _ => {}
+            }
+        }
+        tokens.len()
     }
 }
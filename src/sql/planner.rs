@@ -1,4 +1,5 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 use crate::logical_plan::schema::NaiveField;
 use sqlparser::ast::ColumnDef;
@@ -7,20 +8,23 @@ use sqlparser::ast::{
     BinaryOperator, Expr, FunctionArg, Join, JoinConstraint, JoinOperator, SetExpr,
     Statement, TableWithJoins, Assignment,     
 };
-use sqlparser::ast::Offset;
-use sqlparser::ast::{Ident, ObjectName, SelectItem, TableFactor, Value};
-use sqlparser::ast::ColumnOption;
+use sqlparser::ast::{Fetch, Offset, OrderByExpr, Query};
+use sqlparser::ast::{Ident, ObjectName, SelectItem, TableFactor, UnaryOperator, Value};
+use sqlparser::ast::{ColumnOption, TableConstraint};
 
 use crate::error::ErrorCode;
 use crate::logical_plan::expression::{
-    BinaryExpr, Column, LogicalExpr, Operator, ScalarValue,
+    AggregateFunction, BinaryExpr, Column, InSubquery, LogicalExpr, Operator, ScalarFunction,
+    ScalarValue,
 };
 use crate::logical_plan::literal::lit;
-use crate::logical_plan::plan::{JoinType, TableScan, CreateTable};
+use crate::logical_plan::plan::{JoinType, TableScan, CreateTable, SortExpr};
 
 use crate::logical_plan::schema::NaiveSchema;
+use crate::datasource::OneRowTable;
+use crate::sql::parser::DistinctOnKeys;
 use crate::{
-    catalog::Catalog,
+    catalog::{Catalog, View},
     error::Result,
     logical_plan::{plan::LogicalPlan, DataFrame},
 };
@@ -28,35 +32,55 @@ use crate::{
 // SQLPlanner: convert statement to logical plan
 pub struct SQLPlanner<'a> {
     catalog: &'a Catalog,   // 引用一个 Catalog，用来管理数据库中的表和视图。
+    ctes: HashMap<String, LogicalPlan>,   // 当前查询中 WITH 定义的公共表表达式，查表时优先于catalog
+    integer_division: bool,   // 为 true 时两个整数之间的 `/` 是整数除法（结果截断），否则结果为 Float64
+    expanding_views: Vec<String>,   // 正在展开的视图 用于发现引用自身的视图
+    distinct_on: DistinctOnKeys,   // SQLParser 从语句中取出的 DISTINCT ON 去重键
 }
  
 impl<'a> SQLPlanner<'a> {
     pub fn new(catalog: &'a Catalog) -> Self {
-        Self { catalog }
+        Self {
+            catalog,
+            ctes: HashMap::new(),
+            integer_division: false,
+            expanding_views: vec![],
+            distinct_on: DistinctOnKeys::default(),
+        }
+    }
+
+    // 两个整数之间的 `/` 使用整数除法
+    pub fn with_integer_division(mut self, integer_division: bool) -> Self {
+        self.integer_division = integer_division;
+        self
+    }
+
+    // 语句中 DISTINCT ON 的去重键 由 SQLParser 和语句一起返回
+    pub fn with_distinct_on(mut self, distinct_on: DistinctOnKeys) -> Self {
+        self.distinct_on = distinct_on;
+        self
     }
 
     // ****执行update语句的时候会卡在这里 说明update语句是没有实现的 
     pub fn statement_to_plan(&self, statement: Statement) -> Result<LogicalPlan> {
         match statement {      // match匹配语句
             // -----select语句-----
-            Statement::Query(query) => {      // 明确的匹配模式
-                let plan = self.set_expr_to_plan(query.body)?;   
-                // 首先执行offset，再执行limit
-                let plan = self.offset(plan, query.offset)?;
-                self.limit(plan, query.limit)
-            }
+            Statement::Query(query) => self.query_to_plan(*query),     // 明确的匹配模式
 
             // -----create语句-----  name cloumns 重点需要考虑的三个变量 暂时没考虑约束
             Statement::CreateTable{or_replace:_,temporary:_, external:_, if_not_exists:_, name,columns,constraints:_, hive_distribution:_, hive_formats:_, table_properties:_, with_options:_, file_format:_, location:_, query:_, without_rowid:_, like:_} => {
                 let table_name = Self::normalize_sql_object_name(&name);
-                let schema = Self::columns_to_naive_schema(&columns);
+                let schema = Self::columns_to_naive_schema(&table_name, &columns);
                 // 处理其他的参数，将其组装到一个查询计划中
                 self.plan_create(table_name, schema)
             }
 
             // -----drop语句----- 
+            // 只需要确认表存在 不读取表的数据 结果与建表相同是一个空表
             Statement::Drop{object_type:_, if_exists:_, names, cascade:_, purge:_} => {   
-                self.parse_table_new(&names[0])
+                let table_name = Self::normalize_sql_object_name(&names[0]);
+                let schema = self.catalog.get_table_schema(&table_name)?;
+                self.plan_create(table_name, schema)
             }
 
             // -----update语句----- 
@@ -87,35 +111,159 @@ impl<'a> SQLPlanner<'a> {
                 self.plan_delete(&table_name, selection, plan)
             }
 
-            _ => unimplemented!(),    // 通配符匹配模式，最初用来捕获所有不属于上述statement值 表明我们还没有实现😭
+            // 通配符匹配模式，捕获所有不属于上述statement值 表明我们还没有实现😭
+            other => Err(ErrorCode::NotSupported(format!(
+                "statement is not supported: {}",
+                other
+            ))),
+        }
+    }
+
+    fn query_to_plan(&self, query: Query) -> Result<LogicalPlan> {
+        // WITH 子句：依次规划每个 CTE，后面的 CTE 以及查询主体都可以引用前面定义的 CTE
+        if let Some(with) = query.with {
+            if with.recursive {
+                return Err(ErrorCode::NotSupported(
+                    "WITH RECURSIVE is not supported".to_string(),
+                ));
+            }
+            let mut planner = SQLPlanner {
+                catalog: self.catalog,
+                ctes: self.ctes.clone(),
+                integer_division: self.integer_division,
+                expanding_views: self.expanding_views.clone(),
+                distinct_on: self.distinct_on.clone(),
+            };
+            for cte in with.cte_tables {
+                if !cte.alias.columns.is_empty() {
+                    return Err(ErrorCode::NotSupported(
+                        "column list in WITH is not supported".to_string(),
+                    ));
+                }
+                let name = normalize_ident(&cte.alias.name);
+                let plan = planner.query_to_plan(cte.query)?;
+                let plan = DataFrame::new(plan).alias(&name).logical_plan();
+                planner.ctes.insert(name, plan);
+            }
+            return planner.query_to_plan(Query { with: None, ..query });
         }
+
+        let plan = self.set_expr_to_plan(query.body, query.order_by)?;
+        // 首先执行offset，再执行limit
+        let plan = self.offset(plan, query.offset)?;
+        let plan = self.limit(plan, query.limit)?;
+        // FETCH FIRST n ROWS ONLY 是 ANSI 标准中的 LIMIT 写法，同样在 offset 之后执行
+        self.fetch(plan, query.fetch)
     }
 
     // 传入的是query_body，是select的主体部分，SetExpr类型，包含select的各种子句
-    fn set_expr_to_plan(&self, set_expr: SetExpr) -> Result<LogicalPlan> {
+    // order_by 在这里一起处理，因为 DISTINCT ON 需要在投影之前排序
+    fn set_expr_to_plan(&self, set_expr: SetExpr, order_by: Vec<OrderByExpr>) -> Result<LogicalPlan> {
         match set_expr {
             // 匹配第一个部分Select(Box<Select>)
             SetExpr::Select(select) => {
+                let distinct_on = self.distinct_on.get(&select).map(|keys| keys.to_vec());
+                if select.from.is_empty() && select.projection.contains(&SelectItem::Wildcard) {
+                    return Err(ErrorCode::PlanError(
+                        "SELECT * with no tables specified is not valid".to_string(),
+                    ));
+                }
                 let plans = self.plan_from_tables(select.from)?;   // 将1.表及其2.连接关系解析为LogicalPlan
 
                 let plan = self.plan_selection(select.selection, plans)?;  // where语句的处理，筛选符合条件的行
 
                 let select_exprs = self.prepare_select_exprs(&plan, &select.projection)?; 
-                // filter aggregate expr, these exps should not pass to projection
-                let aggr_exprs_haystack = select_exprs;
-                let (aggr_exprs, project_exprs) = self.find_agrr_exprs(&aggr_exprs_haystack);
-                let plan = if aggr_exprs.is_empty() {
-                    plan
+                let having = match &select.having {
+                    Some(expr) => Some(self.sql_to_expr(expr)?),
+                    None => None,
+                };
+                // 聚合函数由 Aggregate 节点计算，之后的投影、HAVING 和 ORDER BY 引用聚合输出中的列
+                // ORDER BY 中没有投影出来的聚合函数同样需要计算，排序之后由投影去掉
+                let mut sort_exprs = self.sort_exprs(&order_by)?;
+                let mut aggr_funcs = vec![];
+                let sort_iter = sort_exprs.iter().map(|sort| &sort.expr);
+                for expr in select_exprs.iter().chain(having.iter()).chain(sort_iter) {
+                    collect_aggregates(expr, &mut aggr_funcs);
+                }
+                let (plan, project_exprs) = if aggr_funcs.is_empty() && select.group_by.is_empty() {
+                    if having.is_some() {
+                        return Err(ErrorCode::PlanError(
+                            "HAVING requires GROUP BY or an aggregate function".to_string(),
+                        ));
+                    }
+                    (plan, select_exprs)
                 } else {
-                    self.plan_from_aggregate(plan, aggr_exprs, select.group_by)?    
+                    let mut group_exprs = vec![];
+                    for expr in &select.group_by {
+                        group_exprs.push(self.sql_to_expr(expr)?);
+                    }
+                    let aggr_plan = self.plan_from_aggregate(plan.clone(), group_exprs.clone(), aggr_funcs)?;
+                    let mut project_exprs = vec![];
+                    for expr in &select_exprs {
+                        project_exprs.push(rewrite_for_aggregate(expr, &plan, &aggr_plan, &group_exprs)?);
+                    }
+                    // ORDER BY 中的分组表达式和聚合函数同样引用聚合输出中的列，无法改写的（例如输出列的别名）保持不变
+                    for sort in sort_exprs.iter_mut() {
+                        if let Ok(expr) = rewrite_for_aggregate(&sort.expr, &plan, &aggr_plan, &group_exprs) {
+                            sort.expr = expr;
+                        }
+                    }
+                    // HAVING 在聚合之后过滤分组 其中的每个聚合函数都改写为聚合输出中的列，
+                    // 所以可以在多个聚合结果之间做运算（例如 sum(x) / count(*) > 100）
+                    let aggr_plan = match &having {
+                        Some(expr) => {
+                            let predicate = rewrite_for_aggregate(expr, &plan, &aggr_plan, &group_exprs)?;
+                            DataFrame::new(aggr_plan).filter(predicate)?.logical_plan()
+                        }
+                        None => aggr_plan,
+                    };
+                    (aggr_plan, project_exprs)
                 };
 
+                // DISTINCT ON：先排序，再保留每个去重键的第一行，最后投影
+                if let Some(keys) = distinct_on {
+                    let plan = self.order_by(plan, sort_exprs);
+                    let mut on_expr = vec![];
+                    for expr in &keys {
+                        on_expr.push(self.sql_to_expr(expr)?);
+                    }
+                    let plan = DataFrame::new(plan).distinct_on(on_expr).logical_plan();
+                    return self.plan_from_projection(plan, project_exprs);
+                }
+
                 // process the SELECT expressions, with wildcards expanded
-                let plan = self.plan_from_projection(plan, project_exprs)?;
+                // ORDER BY 引用了没有投影出来的列时，在投影之前排序，再做投影
+                let projected = self.plan_from_projection(plan.clone(), project_exprs.clone())?;
+                if sort_exprs.iter().any(|sort| sort.expr.data_field(&projected).is_err()) {
+                    if select.distinct {
+                        return Err(ErrorCode::PlanError(
+                            "for SELECT DISTINCT, ORDER BY expressions must appear in select list"
+                                .to_string(),
+                        ));
+                    }
+                    let plan = DataFrame::new(plan).sort(sort_exprs).logical_plan();
+                    return self.plan_from_projection(plan, project_exprs);
+                }
+
+                let plan = projected;
+                let plan = if select.distinct {
+                    DataFrame::new(plan).distinct().logical_plan()
+                } else {
+                    plan
+                };
 
-                Ok(plan)
+                Ok(self.order_by(plan, sort_exprs))
             }
-            _ => todo!(),
+            // 括号中的查询，例如 `(SELECT ...) LIMIT 5`：先规划内部的查询，外层的 ORDER BY 作用在其结果上
+            SetExpr::Query(query) => {
+                let plan = self.query_to_plan(*query)?;
+                let sort_exprs = self.sort_exprs(&order_by)?;
+                Ok(self.order_by(plan, sort_exprs))
+            }
+            other => Err(ErrorCode::NotSupported(format!(
+                "query body is not supported: {}",
+                other
+            ))),
         }
     }
 
@@ -123,35 +271,11 @@ impl<'a> SQLPlanner<'a> {
     fn plan_from_aggregate(
         &self,
         plan: LogicalPlan,
-        aggr_exprs: Vec<LogicalExpr>,
-        group_by: Vec<Expr>,
+        group_by_exprs: Vec<LogicalExpr>,
+        aggr_func: Vec<AggregateFunction>,
     ) -> Result<LogicalPlan> {
-        let mut group_by_exprs = vec![];
-        for expr in &group_by {
-            group_by_exprs.push(self.sql_to_expr(expr)?);
-        }
-
-        let mut aggr_func = vec![];
-        for aggr_expr in &aggr_exprs {
-            if let LogicalExpr::AggregateFunction(aggr) = aggr_expr {
-                aggr_func.push(aggr.clone());
-            }
-        }
-
         let df = DataFrame::new(plan);
-        Ok(df.aggregate(group_by_exprs, aggr_func).logical_plan())
-    }
-
-    fn find_agrr_exprs(&self, exprs: &[LogicalExpr]) -> (Vec<LogicalExpr>, Vec<LogicalExpr>) {
-        let mut aggr_exprs = vec![];    // 聚合函数列
-        let mut project_exprs = vec![]; // 普通列
-        for expr in exprs {
-            match expr {
-                LogicalExpr::AggregateFunction(_) => aggr_exprs.push(expr.clone()),
-                _ => project_exprs.push(expr.clone()),
-            }
-        }
-        (aggr_exprs, project_exprs)
+        Ok(df.aggregate(group_by_exprs, aggr_func)?.logical_plan())
     }
 
     fn prepare_select_exprs(
@@ -174,8 +298,15 @@ impl<'a> SQLPlanner<'a> {
     fn select_item_to_expr(&self, sql: &SelectItem) -> Result<LogicalExpr> {
         match sql {
             SelectItem::UnnamedExpr(expr) => self.sql_to_expr(expr),
+            SelectItem::ExprWithAlias { expr, alias } => Ok(LogicalExpr::Alias(
+                Box::new(self.sql_to_expr(expr)?),
+                normalize_ident(alias),
+            )),
             SelectItem::Wildcard => Ok(LogicalExpr::Wildcard),
-            _ => unimplemented!(),
+            other => Err(ErrorCode::NotSupported(format!(
+                "select item is not supported: {}",
+                other
+            ))),
         }
     }
 
@@ -184,22 +315,58 @@ impl<'a> SQLPlanner<'a> {
             LogicalExpr::Wildcard => schema
                 .fields()
                 .iter()
-                .map(|f| LogicalExpr::column(None, f.name().to_string()))
+                .map(|f| LogicalExpr::column(f.qualifier().cloned(), f.name().to_string()))
                 .collect::<Vec<LogicalExpr>>(),
             _ => vec![expr.clone()],
         }
     }
 
+    // 实现order by 未指定 NULLS FIRST/LAST 时与 PostgreSQL 一致：升序空值在后，降序空值在前
+    fn order_by(&self, plan: LogicalPlan, sort_exprs: Vec<SortExpr>) -> LogicalPlan {
+        if sort_exprs.is_empty() {
+            return plan;
+        }
+        DataFrame::new(plan).sort(sort_exprs).logical_plan()
+    }
+
+    fn sort_exprs(&self, order_by: &[OrderByExpr]) -> Result<Vec<SortExpr>> {
+        let mut sort_exprs = vec![];
+        for order_by_expr in order_by {
+            let asc = order_by_expr.asc.unwrap_or(true);
+            sort_exprs.push(SortExpr {
+                expr: self.sql_to_expr(&order_by_expr.expr)?,
+                asc,
+                nulls_first: order_by_expr.nulls_first.unwrap_or(!asc),
+            });
+        }
+        Ok(sort_exprs)
+    }
+
     // 实现limit 指定返回的行数
     fn limit(&self, plan: LogicalPlan, limit: Option<Expr>) -> Result<LogicalPlan> {
         match limit {
             Some(limit_expr) => {
-                let n = match self.sql_to_expr(&limit_expr)? {
-                    LogicalExpr::Literal(ScalarValue::Int64(Some(n))) => Ok(n as usize),
-                    _ => Err(ErrorCode::PlanError(
-                        "Unexpected expression for LIMIT clause".to_string(),
-                    )),
-                }?;
+                let n = self.row_count(&limit_expr, "LIMIT")?;
+                Ok(DataFrame { plan }.limit(n).logical_plan())
+            }
+            None => Ok(plan),
+        }
+    }
+
+    // 实现fetch 将 FETCH { FIRST | NEXT } n { ROW | ROWS } ONLY 转换为limit
+    fn fetch(&self, plan: LogicalPlan, fetch: Option<Fetch>) -> Result<LogicalPlan> {
+        match fetch {
+            Some(fetch) => {
+                if fetch.with_ties || fetch.percent {
+                    return Err(ErrorCode::NotSupported(
+                        "FETCH with PERCENT or WITH TIES is not supported".to_string(),
+                    ));
+                }
+                // 省略数量时（FETCH FIRST ROW ONLY）默认只取一行
+                let n = match fetch.quantity {
+                    Some(quantity) => self.row_count(&quantity, "FETCH")?,
+                    None => 1,
+                };
                 Ok(DataFrame { plan }.limit(n).logical_plan())
             }
             None => Ok(plan),
@@ -210,26 +377,43 @@ impl<'a> SQLPlanner<'a> {
     fn offset(&self, plan: LogicalPlan, offset: Option<Offset>) -> Result<LogicalPlan> {
         match offset {
             Some(offset) => {
-                let n = match self.sql_to_expr(&offset.value)? {
-                    LogicalExpr::Literal(ScalarValue::Int64(Some(n))) => Ok(n as usize),
-                    _ => Err(ErrorCode::PlanError(
-                        "Unexpected expression for Offset clause".to_string(),
-                    )),
-                }?;
+                let n = self.row_count(&offset.value, "OFFSET")?;
                 Ok(DataFrame { plan }.offset(n).logical_plan())
             }
             None => Ok(plan),
         }
     }
 
+    // LIMIT、OFFSET、FETCH 的行数只能是非负的整数常量
+    fn row_count(&self, expr: &Expr, clause: &str) -> Result<usize> {
+        let n = match self.sql_to_expr(expr)? {
+            LogicalExpr::Literal(ScalarValue::Int64(Some(n))) => usize::try_from(n).ok(),
+            LogicalExpr::Literal(ScalarValue::UInt64(Some(n))) => usize::try_from(n).ok(),
+            // 超出 i64 范围的数值被解析为 Float64
+            LogicalExpr::Literal(_) => None,
+            _ => {
+                return Err(ErrorCode::PlanError(format!(
+                    "Unexpected expression for {} clause",
+                    clause
+                )))
+            }
+        };
+        n.ok_or_else(|| {
+            ErrorCode::PlanError(format!(
+                "{} must be a non-negative integer that fits in usize, got {}",
+                clause, expr
+            ))
+        })
+    }
+
     /* From子句的入口函数及核心处理 可能需要处理TableWithJoins表示的一个表/多表连接关系 */
-    // from 向量的长度为 0，表示 SQL 查询没有指定任何表。这时，代码使用 todo!() 触发一个占位符错误，并提示未实现该功能的情况。
+    // from 向量的长度为 0，表示 SQL 查询没有指定任何表（如 `SELECT 1 + 1`），这时扫描只有一行的 OneRowTable。
     // from 向量的长度大于 0，代码会遍历 from 中的每一个 TableWithJoins（即每个表及其可能存在连接），
     // 并调用 self.plan_table_with_joins(t) 方法来生成每个表的逻辑计划。
     // 最终，使用 collect 将所有生成的逻辑计划收集到一个向量中，返回一个 Result<Vec<LogicalPlan>>。
     fn plan_from_tables(&self, from: Vec<TableWithJoins>) -> Result<Vec<LogicalPlan>> {
         match from.len() {    
-            0 => todo!("support select with no from"),   
+            0 => Ok(vec![LogicalPlan::TableScan(TableScan::new(OneRowTable::create()))]),
             _ => from
                 .iter()
                 .map(|t| self.plan_table_with_joins(t))
@@ -294,6 +478,20 @@ impl<'a> SQLPlanner<'a> {
 
                 let mut filters = vec![];
                 extract_join_keys(&expr, &mut keys, &mut filters);   // 从表达式中提取键和值
+                // 连接键按 (左表的列, 右表的列) 排列（ON 中可以写成 right.col = left.col），
+                // 两列来自同一侧的等值条件作为普通的过滤条件
+                let oriented = find_join_keys(left.schema(), right.schema(), &keys);
+                for (l, r) in &keys {
+                    let is_key = oriented.iter().any(|(a, b)| (a == l && b == r) || (a == r && b == l));
+                    if !is_key {
+                        filters.push(LogicalExpr::BinaryExpr(BinaryExpr {
+                            left: Box::new(LogicalExpr::Column(l.clone())),
+                            op: Operator::Eq,
+                            right: Box::new(LogicalExpr::Column(r.clone())),
+                        }));
+                    }
+                }
+                let keys = oriented;
 
                 let left_keys = keys.iter().map(|pair| pair.0.clone()).collect();
                 let right_keys = keys.iter().map(|pair| pair.1.clone()).collect();
@@ -302,18 +500,15 @@ impl<'a> SQLPlanner<'a> {
                     let join =
                         DataFrame::new(left).join(&right, join_type, (left_keys, right_keys))?;
                     Ok(join.logical_plan())
-                } else if join_type == JoinType::Inner {   // 有过滤条件 且是 INNER JOIN 说明当前只实现了InnerJoin
-                    let join =
-                        DataFrame::new(left).join(&right, join_type, (left_keys, right_keys))?;
-                    let join = join.filter(     // 使用 filter 方法将过滤条件应用到连接结果上 使用fold函数将多个过滤条件合并在一起
-                        filters
-                            .iter()
-                            .skip(1)
-                            .fold(filters[0].clone(), |acc, e| acc.and(e.clone())),
-                    );
-                    Ok(join.logical_plan())
                 } else {
-                    Err(ErrorCode::NotImplemented)    // 当前只实现了InnerJoin的方式
+                    // 其余的条件作为连接的一部分，而不是连接之后的过滤：
+                    // 外连接中不满足条件的行仍然与 NULL 一起保留。没有等值连接键时（例如 `a.x < b.y`）使用嵌套循环连接
+                    let filter = filters
+                        .iter()
+                        .skip(1)
+                        .fold(filters[0].clone(), |acc, e| acc.and(e.clone()));
+                    let join = DataFrame::new(left).join_on(&right, join_type, (left_keys, right_keys), filter)?;
+                    Ok(join.logical_plan())
                 }
             }    // 如果没有连接条件 即不存在on 直接进行连接操作，left_keys 和 right_keys 都为空
             JoinConstraint::None => {
@@ -327,26 +522,86 @@ impl<'a> SQLPlanner<'a> {
     // 解析单个表的基本信息，生成对应的 LogicalPlan。
     fn parse_table(&self, relation: &TableFactor) -> Result<LogicalPlan> {
         match &relation {
-            TableFactor::Table { name, .. } => {
+            // 表有别名时（如 `FROM employee e`）所有列使用别名作为限定名 之后只能通过 e.column 引用
+            TableFactor::Table { name, alias, .. } => {
                 let table_name = Self::normalize_sql_object_name(name);
-                let source = self.catalog.get_table(&table_name)?;
-                Ok(LogicalPlan::TableScan(TableScan {
-                    source,
-                    projection: None,
-                }))
+                let plan = match self.ctes.get(&table_name) {
+                    Some(plan) => plan.clone(),
+                    None => match self.catalog.get_view(&table_name) {
+                        Some(view) => self.view_to_plan(&table_name, view)?,
+                        None => {
+                            let source = self.catalog.get_table(&table_name)?;
+                            LogicalPlan::TableScan(TableScan::new(source))
+                        }
+                    },
+                };
+                match alias {
+                    Some(alias) => {
+                        if !alias.columns.is_empty() {
+                            return Err(ErrorCode::NotSupported(
+                                "column list in table alias is not supported".to_string(),
+                            ));
+                        }
+                        let name = normalize_ident(&alias.name);
+                        Ok(DataFrame::new(plan).alias(&name).logical_plan())
+                    }
+                    None => Ok(plan),
+                }
+            }
+            // FROM 中的子查询：有别名时所有列使用别名作为限定名，否则保留子查询中各列原本的限定名
+            TableFactor::Derived {
+                lateral,
+                subquery,
+                alias,
+            } => {
+                if *lateral {
+                    return Err(ErrorCode::NotSupported(
+                        "LATERAL subquery is not supported".to_string(),
+                    ));
+                }
+                let plan = self.query_to_plan(*subquery.clone())?;
+                match alias {
+                    Some(alias) => {
+                        let name = normalize_ident(&alias.name);
+                        Ok(DataFrame::new(plan).alias(&name).logical_plan())
+                    }
+                    None => Ok(plan),
+                }
             }
-            _ => unimplemented!(),
+            other => Err(ErrorCode::NotSupported(format!(
+                "table factor is not supported: {}",
+                other
+            ))),
         }
     }
 
+    // 展开视图：每次引用时按视图的定义重新规划，所以总是读取表当前的数据
+    // 视图的列使用视图名作为限定名，视图的定义中看不到外层查询 WITH 定义的 CTE
+    fn view_to_plan(&self, name: &str, view: View) -> Result<LogicalPlan> {
+        if self.expanding_views.iter().any(|view| view == name) {
+            return Err(ErrorCode::PlanError(format!(
+                "view `{}` references itself directly or through other views",
+                name
+            )));
+        }
+        let mut expanding_views = self.expanding_views.clone();
+        expanding_views.push(name.to_string());
+        let planner = SQLPlanner {
+            catalog: self.catalog,
+            ctes: HashMap::new(),
+            integer_division: self.integer_division,
+            expanding_views,
+            distinct_on: view.distinct_on,
+        };
+        let plan = planner.query_to_plan(view.query)?;
+        Ok(DataFrame::new(plan).alias(name).logical_plan())
+    }
+
     // 除了select以外 其他查询语句都需要使用这个 因为传入的参数有所不同
     fn parse_table_new(&self, name: &ObjectName) -> Result<LogicalPlan> {
         let table_name = Self::normalize_sql_object_name(name);
         let source = self.catalog.get_table(&table_name)?;
-        let plan = LogicalPlan::TableScan(TableScan {
-            source,
-            projection: None,
-        });
+        let plan = LogicalPlan::TableScan(TableScan::new(source));
     
         // 返回一个包含单个逻辑计划的向量
         Ok(plan)
@@ -360,10 +615,14 @@ impl<'a> SQLPlanner<'a> {
         plan: LogicalPlan
     ) -> Result<LogicalPlan> {
         let df = DataFrame::new(plan);
+        let mut exprs = vec![];
+        for assignment in &assignments {
+            exprs.push((normalize_ident(&assignment.id), self.sql_to_expr(&assignment.value)?));
+        }
         match selection {
             Some(expr) => {
-                let conditions = self.sql_to_expr(&expr)?;
-                Ok(df.update(conditions, assignments)?.logical_plan())
+                let conditions = self.where_to_expr(&expr)?;
+                Ok(df.update(conditions, exprs)?.logical_plan())
             }
             None => {
                 Err(ErrorCode::NotImplemented)
@@ -372,7 +631,8 @@ impl<'a> SQLPlanner<'a> {
     }
     
     // ---createTable专属---
-    pub fn columns_to_naive_schema(columns: &Vec<ColumnDef>) -> NaiveSchema {
+    // 列以表名为限定名 与 csv 表一样可以用 表名.列名 引用
+    pub fn columns_to_naive_schema(table_name: &str, columns: &[ColumnDef]) -> NaiveSchema {
         let fields: Vec<NaiveField> = columns
             .iter()
             .map(|column| {
@@ -384,15 +644,35 @@ impl<'a> SQLPlanner<'a> {
                     sqlparser::ast::DataType::Decimal(_, _) => ArrowDataType::Decimal(10, 2), // 假设为10,2精度
                     _ => ArrowDataType::Utf8, // 默认类型为 Utf8
                 };
-                let nullable = column.options.iter().any(|opt| matches!(opt.option, ColumnOption::Null));
+                // 没有 NOT NULL 或 PRIMARY KEY 约束的列可以为空
+                let nullable = !column.options.iter().any(|opt| {
+                    matches!(opt.option, ColumnOption::NotNull | ColumnOption::Unique { is_primary: true })
+                });
                 let name = column.name.to_string();
-                NaiveField::new(None, &name, data_type, nullable)
+                NaiveField::new(Some(table_name), &name, data_type, nullable)
             })
             .collect();
     
         NaiveSchema::new(fields)
     }
 
+    // ---createTable专属---
+    // 收集 UNIQUE / PRIMARY KEY 约束，每个约束是一组列名，包括列上的约束和表级的约束
+    pub fn unique_constraints(columns: &[ColumnDef], constraints: &[TableConstraint]) -> Vec<Vec<String>> {
+        let mut unique = vec![];
+        for column in columns {
+            if column.options.iter().any(|opt| matches!(opt.option, ColumnOption::Unique { .. })) {
+                unique.push(vec![column.name.to_string()]);
+            }
+        }
+        for constraint in constraints {
+            if let TableConstraint::Unique { columns, .. } = constraint {
+                unique.push(columns.iter().map(|column| column.to_string()).collect());
+            }
+        }
+        unique
+    }
+
     // ---createTable专属---
     fn plan_create(
         &self, 
@@ -405,14 +685,28 @@ impl<'a> SQLPlanner<'a> {
         }))
     }
 
+    // VALUES 中的每个值都按表达式规划 所以可以写负数、算术表达式和函数调用
     fn plan_insert(
         &self, 
         columns: Vec<Ident>, 
         source: SetExpr,
         plan: LogicalPlan
     ) -> Result<LogicalPlan> {
+        let rows = match source {
+            SetExpr::Values(values) => values.0,
+            other => {
+                return Err(ErrorCode::NotSupported(format!(
+                    "INSERT only supports VALUES, found: {}",
+                    other
+                )))
+            }
+        };
+        let mut values = vec![];
+        for row in &rows {
+            values.push(row.iter().map(|expr| self.sql_to_expr(expr)).collect::<Result<Vec<_>>>()?);
+        }
         let df = DataFrame::new(plan);
-        Ok(df.insert(columns, source)?.logical_plan())
+        Ok(df.insert(columns, values)?.logical_plan())
     }
 
     fn plan_delete(
@@ -421,12 +715,20 @@ impl<'a> SQLPlanner<'a> {
         selection: Option<Expr>,
         plan: LogicalPlan
     ) -> Result<LogicalPlan> {
-        let name = Self::normalize_sql_object_name(table_name);
-        let source = self.catalog.get_table(&name)?;
+        // 删除的目标表就是 plan 扫描的表 不需要再从 catalog 中获取一次
+        let source = match &plan {
+            LogicalPlan::TableScan(scan) => scan.source.clone(),
+            _ => {
+                return Err(ErrorCode::PlanError(format!(
+                    "DELETE target `{}` is not a table",
+                    Self::normalize_sql_object_name(table_name)
+                )))
+            }
+        };
         let df = DataFrame::new(plan);
         match selection {
             Some(expr) => {
-                let conditions = self.sql_to_expr(&expr)?;
+                let conditions = self.where_to_expr(&expr)?;
                 Ok(df.delete(source, conditions)?.logical_plan())
             }
             None => {
@@ -456,66 +758,61 @@ impl<'a> SQLPlanner<'a> {
                 for plan in &plans {
                     fields.extend_from_slice(plan.schema().fields());
                 }
-                let filter_expr = self.sql_to_expr(&expr)?;
+                let filter_expr = self.where_to_expr(&expr)?;
 
                 // look for expressions of the form `<column> = <column>`
                 let mut possible_join_keys = vec![];
                 extract_possible_join_keys(&filter_expr, &mut possible_join_keys)?;
 
+                // 每一步在剩余的表中找到第一个能与已连接结果（left）匹配上连接键的表进行连接，
+                // 这样连接键不必出现在 FROM 中相邻的两个表之间
                 let mut all_join_keys = HashSet::new();
                 let mut left = plans[0].clone();
-                for right in plans.iter().skip(1) {
-                    let left_schema = left.schema();
-                    let right_schema = right.schema();
-                    let mut join_keys = vec![];
-                    for (l, r) in &possible_join_keys {
-                        if left_schema
-                            .field_with_unqualified_name(l.name.as_str())
-                            .is_ok()
-                            && right_schema
-                                .field_with_unqualified_name(r.name.as_str())
-                                .is_ok()
-                        {
-                            join_keys.push((l.clone(), r.clone()));
-                        } else if left_schema
-                            .field_with_unqualified_name(r.name.as_str())
-                            .is_ok()
-                            && right_schema
-                                .field_with_unqualified_name(l.name.as_str())
-                                .is_ok()
-                        {
-                            join_keys.push((r.clone(), l.clone()));
+                let mut remaining = plans.iter().skip(1).collect::<Vec<_>>();
+                while !remaining.is_empty() {
+                    let next = remaining.iter().enumerate().find_map(|(i, right)| {
+                        let join_keys =
+                            find_join_keys(left.schema(), right.schema(), &possible_join_keys);
+                        if join_keys.is_empty() {
+                            None
+                        } else {
+                            Some((i, join_keys))
                         }
-                    }
-                    if !join_keys.is_empty() {
-                        let left_keys: Vec<Column> =
-                            join_keys.iter().map(|(l, _)| l.clone()).collect();
-                        let right_keys: Vec<Column> =
-                            join_keys.iter().map(|(_, r)| r.clone()).collect();
-                        let df = DataFrame::new(left);
-                        left = df
-                            .join(right, JoinType::Inner, (left_keys, right_keys))?
-                            .logical_plan();
-                    } else {
-                        return Err(ErrorCode::NotImplemented);
-                    }
+                    });
+                    // 剩余的表都没有连接键（例如 `FROM a, b WHERE a.x > b.y`）时与第一个剩余的表做交叉连接，
+                    // WHERE 中的条件在连接之后整体作为过滤条件
+                    let (i, join_keys) = next.unwrap_or((0, vec![]));
+                    let right = remaining.remove(i);
+
+                    let left_keys: Vec<Column> =
+                        join_keys.iter().map(|(l, _)| l.clone()).collect();
+                    let right_keys: Vec<Column> =
+                        join_keys.iter().map(|(_, r)| r.clone()).collect();
+                    let join_type = if join_keys.is_empty() { JoinType::Cross } else { JoinType::Inner };
+                    let df = DataFrame::new(left);
+                    left = df
+                        .join(right, join_type, (left_keys, right_keys))?
+                        .logical_plan();
 
                     all_join_keys.extend(join_keys);
                 }
                 // remove join expressions from filter
                 match remove_join_expressions(&filter_expr, &all_join_keys)? {
                     Some(filter_expr) => {
-                        Ok(DataFrame::new(left).filter(filter_expr).logical_plan())
+                        Ok(DataFrame::new(left).filter(filter_expr)?.logical_plan())
                     }
                     _ => Ok(left),
                 }
             }
+            // 没有 WHERE 时 FROM 中的多个表依次做交叉连接
             None => {
-                if plans.len() == 1 {
-                    Ok(plans[0].clone())
-                } else {
-                    Err(ErrorCode::NotImplemented)
+                let mut left = plans[0].clone();
+                for right in &plans[1..] {
+                    left = DataFrame::new(left)
+                        .join(right, JoinType::Cross, (vec![], vec![]))?
+                        .logical_plan();
                 }
+                Ok(left)
             }
         }
     }
@@ -536,17 +833,189 @@ impl<'a> SQLPlanner<'a> {
     fn sql_to_expr(&self, sql: &Expr) -> Result<LogicalExpr> {
         match sql {
             Expr::Value(Value::Boolean(n)) => Ok(lit(*n)), // 布尔值
+            // 预处理语句的参数占位符在解析时被改写为 `$n` 形式的数值
+            Expr::Value(Value::Number(n, _)) if n.starts_with('$') => match n[1..].parse::<usize>() {
+                Ok(param) => Ok(LogicalExpr::Placeholder(param)),
+                Err(_) => Err(ErrorCode::PlanError(format!("invalid parameter placeholder {}", n))),
+            },
             Expr::Value(Value::Number(n, _)) => match n.parse::<i64>() {
                 Ok(n) => Ok(lit(n)),   // 数值解析为i64
-                Err(_) => Ok(lit(n.parse::<f64>().unwrap())),   // 否则尝试解析为f64
+                Err(_) => match n.parse::<f64>() {   // 否则尝试解析为f64
+                    Ok(n) => Ok(lit(n)),
+                    Err(_) => Err(ErrorCode::PlanError(format!("invalid number: {}", n))),
+                },
             },
-            Expr::Value(Value::SingleQuotedString(ref s)) => Ok(lit(s.clone())), // 单引号字符串值
+            // 单引号字符串值 tokenizer 已将 '' 还原为单个引号；N'...' 形式同样按字符串处理
+            Expr::Value(Value::SingleQuotedString(ref s))
+            | Expr::Value(Value::NationalStringLiteral(ref s)) => Ok(lit(s.clone())),
             Expr::Value(Value::Null) => Ok(LogicalExpr::Literal(ScalarValue::Null)),   
             // 单个标识符（例如列名 id）被转换为 LogicalExpr::column，表示逻辑计划中的列。
             Expr::Identifier(id) => Ok(LogicalExpr::column(None, normalize_ident(id))),
 
             // 二元操作符
             Expr::BinaryOp { left, op, right } => self.parse_sql_binary_op(left, op, right),
+            // 带操作数的 CASE x WHEN v THEN r 改写为 CASE WHEN x = v THEN r
+            Expr::Case {
+                operand,
+                conditions,
+                results,
+                else_result,
+            } => {
+                let operand = match operand {
+                    Some(operand) => Some(self.sql_to_expr(operand)?),
+                    None => None,
+                };
+                let mut when_then = vec![];
+                for (condition, result) in conditions.iter().zip(results) {
+                    let condition = self.sql_to_expr(condition)?;
+                    let condition = match &operand {
+                        Some(operand) => LogicalExpr::BinaryExpr(BinaryExpr {
+                            left: Box::new(operand.clone()),
+                            op: Operator::Eq,
+                            right: Box::new(condition),
+                        }),
+                        None => condition,
+                    };
+                    when_then.push((condition, self.sql_to_expr(result)?));
+                }
+                let else_expr = match else_result {
+                    Some(expr) => Some(Box::new(self.sql_to_expr(expr)?)),
+                    None => None,
+                };
+                Ok(LogicalExpr::Case {
+                    when_then,
+                    else_expr,
+                })
+            }
+            // BETWEEN 改写为 expr >= low AND expr <= high，NOT BETWEEN 改写为 expr < low OR expr > high
+            Expr::Between {
+                expr,
+                negated,
+                low,
+                high,
+            } => {
+                let expr = self.sql_to_expr(expr)?;
+                let (low_op, high_op, op) = if *negated {
+                    (Operator::Lt, Operator::Gt, Operator::Or)
+                } else {
+                    (Operator::GtEq, Operator::LtEq, Operator::And)
+                };
+                Ok(LogicalExpr::BinaryExpr(BinaryExpr {
+                    left: Box::new(LogicalExpr::BinaryExpr(BinaryExpr {
+                        left: Box::new(expr.clone()),
+                        op: low_op,
+                        right: Box::new(self.sql_to_expr(low)?),
+                    })),
+                    op,
+                    right: Box::new(LogicalExpr::BinaryExpr(BinaryExpr {
+                        left: Box::new(expr),
+                        op: high_op,
+                        right: Box::new(self.sql_to_expr(high)?),
+                    })),
+                }))
+            }
+            Expr::IsNull(expr) => Ok(LogicalExpr::IsNull(Box::new(self.sql_to_expr(expr)?))),
+            Expr::IsNotNull(expr) => Ok(LogicalExpr::IsNotNull(Box::new(self.sql_to_expr(expr)?))),
+            // 括号只影响解析时的结合顺序
+            Expr::Nested(expr) => self.sql_to_expr(expr),
+            Expr::UnaryOp {
+                op: UnaryOperator::Not,
+                expr,
+            } => Ok(LogicalExpr::Not(Box::new(self.sql_to_expr(expr)?))),
+            Expr::UnaryOp {
+                op: UnaryOperator::Plus,
+                expr,
+            } => self.sql_to_expr(expr),
+            // 负数常量直接取反，其他表达式改写为 -1 * expr
+            Expr::UnaryOp {
+                op: UnaryOperator::Minus,
+                expr,
+            } => match self.sql_to_expr(expr)? {
+                LogicalExpr::Literal(ScalarValue::Int64(Some(v))) => {
+                    Ok(LogicalExpr::Literal(ScalarValue::Int64(Some(-v))))
+                }
+                LogicalExpr::Literal(ScalarValue::Float64(Some(v))) => {
+                    Ok(LogicalExpr::Literal(ScalarValue::Float64(Some(-v))))
+                }
+                expr => Ok(LogicalExpr::BinaryExpr(BinaryExpr {
+                    left: Box::new(LogicalExpr::Literal(ScalarValue::Int64(Some(-1)))),
+                    op: Operator::Multiply,
+                    right: Box::new(expr),
+                })),
+            },
+            // IN 改写为 expr = v1 OR expr = v2 ...，NOT IN 改写为 expr != v1 AND expr != v2 ...
+            // expr 为 NULL 时结果为 NULL，不会匹配任何行
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let expr = self.sql_to_expr(expr)?;
+                let (cmp_op, op) = if *negated {
+                    (Operator::NotEq, Operator::And)
+                } else {
+                    (Operator::Eq, Operator::Or)
+                };
+                let mut in_expr: Option<LogicalExpr> = None;
+                for value in list {
+                    let cmp = LogicalExpr::BinaryExpr(BinaryExpr {
+                        left: Box::new(expr.clone()),
+                        op: cmp_op.clone(),
+                        right: Box::new(self.sql_to_expr(value)?),
+                    });
+                    in_expr = Some(match in_expr {
+                        Some(left) => LogicalExpr::BinaryExpr(BinaryExpr {
+                            left: Box::new(left),
+                            op: op.clone(),
+                            right: Box::new(cmp),
+                        }),
+                        None => cmp,
+                    });
+                }
+                // 空列表：IN () 恒为 false，NOT IN () 恒为 true
+                Ok(in_expr.unwrap_or(LogicalExpr::Literal(ScalarValue::Boolean(Some(*negated)))))
+            }
+            // SUBSTRING(expr FROM start FOR len)，省略 FROM 时从第一个字符开始
+            Expr::Substring {
+                expr,
+                substring_from,
+                substring_for,
+            } => {
+                if substring_from.is_none() && substring_for.is_none() {
+                    return Err(ErrorCode::PlanError(
+                        "substring expects 2 to 3 arguments, found 1".to_string(),
+                    ));
+                }
+                let mut args = vec![self.sql_to_expr(expr)?];
+                args.push(match substring_from {
+                    Some(from) => self.sql_to_expr(from)?,
+                    None => lit(1_i64),
+                });
+                if let Some(len) = substring_for {
+                    args.push(self.sql_to_expr(len)?);
+                }
+                LogicalExpr::try_create_scalar_func("substring", &args)
+            }
+            // 子查询单独规划 执行时再计算它的结果
+            Expr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => {
+                let subquery = self.query_to_plan(*subquery.clone())?;
+                let columns = subquery.schema().fields().len();
+                if columns != 1 {
+                    return Err(ErrorCode::PlanError(format!(
+                        "subquery in IN must return exactly one column, got {}",
+                        columns
+                    )));
+                }
+                Ok(LogicalExpr::InSubquery(InSubquery {
+                    expr: Box::new(self.sql_to_expr(expr)?),
+                    subquery: Arc::new(subquery),
+                    negated: *negated,
+                }))
+            }
             // 复合标识符 支持带表名的列（如 table.column）
             Expr::CompoundIdentifier(ids) => {
                 let mut var_names = ids.iter().map(|id| id.value.clone()).collect::<Vec<_>>();
@@ -577,24 +1046,59 @@ impl<'a> SQLPlanner<'a> {
                 for arg in &function.args {
                     let arg = match arg {
                         FunctionArg::Named { name: _, arg } => self.sql_to_expr(arg),
+                        // count(*)
+                        FunctionArg::Unnamed(Expr::Wildcard) => Ok(LogicalExpr::Wildcard),
                         FunctionArg::Unnamed(arg) => self.sql_to_expr(arg),
                     }?;
                     args.push(arg);
                 }
 
 
+                if args.iter().any(|arg| matches!(arg, LogicalExpr::Wildcard))
+                    && !name.eq_ignore_ascii_case("count")
+                {
+                    return Err(ErrorCode::PlanError(format!(
+                        "only count supports `*` as the argument, found: {}",
+                        function
+                    )));
+                }
+                if function.distinct && !name.eq_ignore_ascii_case("count") {
+                    return Err(ErrorCode::NotSupported(format!(
+                        "DISTINCT is only supported in count, found: {}",
+                        function
+                    )));
+                }
+
                 // 聚合函数
-                if let Ok(func) = LogicalExpr::try_create_aggregate_func(&name, &args) {
+                if let Ok(func) = LogicalExpr::try_create_aggregate_func(&name, &args, function.distinct) {
                     return Ok(func);
                 };
 
-                Err(ErrorCode::NoMatchFunction(format!(
-                    "Not find match func: {}",
-                    name
-                )))
+                // 标量函数 参数个数不对等错误直接返回
+                match LogicalExpr::try_create_scalar_func(&name, &args) {
+                    Err(ErrorCode::NoMatchFunction(_)) => Err(ErrorCode::NoMatchFunction(format!(
+                        "Not find match func: {}",
+                        name
+                    ))),
+                    result => result,
+                }
             }
-            _ => todo!(),
+            other => Err(ErrorCode::NotSupported(format!(
+                "expression is not supported: {}",
+                other
+            ))),
+        }
+    }
+
+    // WHERE 子句中的条件 聚合函数只能出现在 HAVING 中
+    fn where_to_expr(&self, sql: &Expr) -> Result<LogicalExpr> {
+        let expr = self.sql_to_expr(sql)?;
+        if contains_aggregate(&expr) {
+            return Err(ErrorCode::PlanError(
+                "aggregate functions are not allowed in WHERE; use HAVING".to_string(),
+            ));
         }
+        Ok(expr)
     }
 
     fn parse_sql_binary_op(
@@ -613,11 +1117,19 @@ impl<'a> SQLPlanner<'a> {
             BinaryOperator::Plus => Operator::Plus,
             BinaryOperator::Minus => Operator::Minus,
             BinaryOperator::Multiply => Operator::Multiply,
+            BinaryOperator::Divide if self.integer_division => Operator::IntegerDivide,
             BinaryOperator::Divide => Operator::Divide,
             BinaryOperator::Modulus => Operator::Modulos,
             BinaryOperator::And => Operator::And,
             BinaryOperator::Or => Operator::Or,
-            _ => unimplemented!(),
+            BinaryOperator::Like => Operator::Like,
+            BinaryOperator::NotLike => Operator::NotLike,
+            other => {
+                return Err(ErrorCode::NotSupported(format!(
+                    "binary operator is not supported: {}",
+                    other
+                )))
+            }
         };
         Ok(LogicalExpr::BinaryExpr(BinaryExpr {
             left: Box::new(self.sql_to_expr(left)?),
@@ -645,7 +1157,8 @@ fn extract_join_keys(
     match expr {
         LogicalExpr::BinaryExpr(BinaryExpr { left, op, right }) => match op {
             Operator::Eq => match (left.as_ref(), right.as_ref()) {
-                (LogicalExpr::Column(l), LogicalExpr::Column(r)) => {
+                // 同一列与自身相等（例如 a = a）不是连接条件，只过滤掉该列为 NULL 的行
+                (LogicalExpr::Column(l), LogicalExpr::Column(r)) if l != r => {
                     accum.push((l.clone(), r.clone()));
                 }
                 _other => {
@@ -656,15 +1169,9 @@ fn extract_join_keys(
                 extract_join_keys(left, accum, accum_filter);
                 extract_join_keys(right, accum, accum_filter);
             }
-            _other
-                if matches!(**left, LogicalExpr::Column(_))
-                    || matches!(**right, LogicalExpr::Column(_)) =>
-            {
-                accum_filter.push(expr.clone());
-            }
+            // 其余条件（包括 OR）作为一个整体 不能拆开
             _other => {
-                extract_join_keys(left, accum, accum_filter);
-                extract_join_keys(right, accum, accum_filter);
+                accum_filter.push(expr.clone());
             }
         },
         _other => {
@@ -673,12 +1180,63 @@ fn extract_join_keys(
     }
 }
 
+/// 表达式中是否包含聚合函数
+fn contains_aggregate(expr: &LogicalExpr) -> bool {
+    match expr {
+        LogicalExpr::AggregateFunction(_) => true,
+        LogicalExpr::Alias(expr, _)
+        | LogicalExpr::IsNull(expr)
+        | LogicalExpr::IsNotNull(expr)
+        | LogicalExpr::Not(expr) => {
+            contains_aggregate(expr)
+        }
+        LogicalExpr::BinaryExpr(BinaryExpr { left, right, .. }) => {
+            contains_aggregate(left) || contains_aggregate(right)
+        }
+        LogicalExpr::InSubquery(in_subquery) => contains_aggregate(&in_subquery.expr),
+        LogicalExpr::ScalarFunction(func) => func.args.iter().any(contains_aggregate),
+        LogicalExpr::Case { when_then, else_expr } => {
+            when_then
+                .iter()
+                .any(|(when, then)| contains_aggregate(when) || contains_aggregate(then))
+                || else_expr.as_deref().is_some_and(contains_aggregate)
+        }
+        LogicalExpr::Column(_)
+        | LogicalExpr::Literal(_)
+        | LogicalExpr::Placeholder(_)
+        | LogicalExpr::Wildcard => false,
+    }
+}
+
+/// 从候选的等值条件中找出一端在 left、另一端在 right 中的列对，按 (left, right) 的顺序返回
+fn find_join_keys(
+    left_schema: &NaiveSchema,
+    right_schema: &NaiveSchema,
+    possible_join_keys: &[(Column, Column)],
+) -> Vec<(Column, Column)> {
+    let has_column = |schema: &NaiveSchema, col: &Column| {
+        schema
+            .index_of_column(col.table.as_deref(), &col.name)
+            .is_ok()
+    };
+    let mut join_keys = vec![];
+    for (l, r) in possible_join_keys {
+        if has_column(left_schema, l) && has_column(right_schema, r) {
+            join_keys.push((l.clone(), r.clone()));
+        } else if has_column(left_schema, r) && has_column(right_schema, l) {
+            join_keys.push((r.clone(), l.clone()));
+        }
+    }
+    join_keys
+}
+
 /// 提取连接键
 fn extract_possible_join_keys(expr: &LogicalExpr, accum: &mut Vec<(Column, Column)>) -> Result<()> {
     match expr {
         LogicalExpr::BinaryExpr(BinaryExpr { left, op, right }) => match op {
             Operator::Eq => match (left.as_ref(), right.as_ref()) {
-                (LogicalExpr::Column(l), LogicalExpr::Column(r)) => {
+                // a = a 作为普通的过滤条件保留
+                (LogicalExpr::Column(l), LogicalExpr::Column(r)) if l != r => {
                     accum.push((l.clone(), r.clone()));
                     Ok(())
                 }
@@ -728,3 +1286,132 @@ fn remove_join_expressions(
         _ => Ok(Some(expr.clone())),
     }
 }
+
+// 收集表达式中出现的聚合函数 相同的聚合函数只计算一次
+fn collect_aggregates(expr: &LogicalExpr, aggr_funcs: &mut Vec<AggregateFunction>) {
+    match expr {
+        LogicalExpr::AggregateFunction(func) => {
+            let key = format!("{:?}", func);
+            if !aggr_funcs.iter().any(|f| format!("{:?}", f) == key) {
+                aggr_funcs.push(func.clone());
+            }
+        }
+        LogicalExpr::Alias(expr, _)
+        | LogicalExpr::IsNull(expr)
+        | LogicalExpr::IsNotNull(expr)
+        | LogicalExpr::Not(expr) => {
+            collect_aggregates(expr, aggr_funcs)
+        }
+        LogicalExpr::BinaryExpr(BinaryExpr { left, right, .. }) => {
+            collect_aggregates(left, aggr_funcs);
+            collect_aggregates(right, aggr_funcs);
+        }
+        LogicalExpr::InSubquery(in_subquery) => collect_aggregates(&in_subquery.expr, aggr_funcs),
+        LogicalExpr::ScalarFunction(func) => {
+            for arg in &func.args {
+                collect_aggregates(arg, aggr_funcs);
+            }
+        }
+        LogicalExpr::Case { when_then, else_expr } => {
+            for (when, then) in when_then {
+                collect_aggregates(when, aggr_funcs);
+                collect_aggregates(then, aggr_funcs);
+            }
+            if let Some(expr) = else_expr {
+                collect_aggregates(expr, aggr_funcs);
+            }
+        }
+        _ => {}
+    }
+}
+
+// 将聚合查询中 SELECT/HAVING 的表达式改写为引用聚合输出的列：
+// 聚合函数和分组表达式替换为对应的输出列，其余的列必须是分组列
+fn rewrite_for_aggregate(
+    expr: &LogicalExpr,
+    aggr_input: &LogicalPlan,
+    aggr_plan: &LogicalPlan,
+    group_exprs: &[LogicalExpr],
+) -> Result<LogicalExpr> {
+    let key = format!("{:?}", expr);
+    if let Some(i) = group_exprs.iter().position(|g| format!("{:?}", g) == key) {
+        let field = aggr_plan.schema().field(i);
+        return Ok(LogicalExpr::column(field.qualifier().cloned(), field.name().clone()));
+    }
+    match expr {
+        LogicalExpr::AggregateFunction(func) => {
+            let field = func.data_field(aggr_input)?;
+            Ok(LogicalExpr::column(None, field.name().clone()))
+        }
+        LogicalExpr::Column(Column { table, name }) => {
+            if expr.data_field(aggr_plan).is_ok() {
+                return Ok(expr.clone());
+            }
+            let name = match table {
+                Some(table) => format!("{}.{}", table, name),
+                None => name.clone(),
+            };
+            Err(ErrorCode::PlanError(format!(
+                "column `{}` must appear in the GROUP BY clause or be used in an aggregate function",
+                name
+            )))
+        }
+        LogicalExpr::Alias(inner, alias) => Ok(LogicalExpr::Alias(
+            Box::new(rewrite_for_aggregate(inner, aggr_input, aggr_plan, group_exprs)?),
+            alias.clone(),
+        )),
+        LogicalExpr::BinaryExpr(BinaryExpr { left, op, right }) => {
+            Ok(LogicalExpr::BinaryExpr(BinaryExpr {
+                left: Box::new(rewrite_for_aggregate(left, aggr_input, aggr_plan, group_exprs)?),
+                op: op.clone(),
+                right: Box::new(rewrite_for_aggregate(right, aggr_input, aggr_plan, group_exprs)?),
+            }))
+        }
+        LogicalExpr::IsNull(inner) => Ok(LogicalExpr::IsNull(Box::new(rewrite_for_aggregate(
+            inner,
+            aggr_input,
+            aggr_plan,
+            group_exprs,
+        )?))),
+        LogicalExpr::IsNotNull(inner) => Ok(LogicalExpr::IsNotNull(Box::new(
+            rewrite_for_aggregate(inner, aggr_input, aggr_plan, group_exprs)?,
+        ))),
+        LogicalExpr::Not(inner) => Ok(LogicalExpr::Not(Box::new(rewrite_for_aggregate(
+            inner,
+            aggr_input,
+            aggr_plan,
+            group_exprs,
+        )?))),
+        LogicalExpr::InSubquery(in_subquery) => Ok(LogicalExpr::InSubquery(InSubquery {
+            expr: Box::new(rewrite_for_aggregate(
+                &in_subquery.expr,
+                aggr_input,
+                aggr_plan,
+                group_exprs,
+            )?),
+            ..in_subquery.clone()
+        })),
+        LogicalExpr::ScalarFunction(func) => Ok(LogicalExpr::ScalarFunction(ScalarFunction {
+            fun: func.fun,
+            args: func
+                .args
+                .iter()
+                .map(|arg| rewrite_for_aggregate(arg, aggr_input, aggr_plan, group_exprs))
+                .collect::<Result<Vec<_>>>()?,
+        })),
+        LogicalExpr::Case { when_then, else_expr } => {
+            let rewrite = |expr| rewrite_for_aggregate(expr, aggr_input, aggr_plan, group_exprs);
+            Ok(LogicalExpr::Case {
+                when_then: when_then
+                    .iter()
+                    .map(|(when, then)| Ok((rewrite(when)?, rewrite(then)?)))
+                    .collect::<Result<Vec<_>>>()?,
+                else_expr: match else_expr {
+                    Some(expr) => Some(Box::new(rewrite(expr)?)),
+                    None => None,
+                },
+            })
+        }
+        _ => Ok(expr.clone()),
+    }
+}
@@ -1,7 +1,132 @@
 use crate::error::ErrorCode;
 use crate::error::Result;
+use arrow::array::{
+    Array, ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray, UInt64Array,
+};
+use arrow::datatypes::DataType;
+use arrow::util::display::array_value_to_string;
 use arrow::{record_batch::RecordBatch, util::pretty};
 
 pub fn print_result(result: &[RecordBatch]) -> Result<()> {
     pretty::print_batches(result).map_err(ErrorCode::ArrowError)
 }
+
+/// 打印 `format_result_limited` 的结果
+pub fn print_result_limited(result: &[RecordBatch], max_rows: usize) -> Result<()> {
+    println!("{}", format_result_limited(result, max_rows)?);
+    Ok(())
+}
+
+/// 将结果格式化为表格，只显示前 max_rows 行，超出的部分在表格后显示 `... (M more rows)`，max_rows 为 0 时不限制
+pub fn format_result_limited(result: &[RecordBatch], max_rows: usize) -> Result<String> {
+    let total: usize = result.iter().map(|batch| batch.num_rows()).sum();
+    if max_rows == 0 || total <= max_rows {
+        return format_batches(result);
+    }
+    let mut shown = vec![];
+    let mut remaining = max_rows;
+    for batch in result {
+        if remaining == 0 {
+            break;
+        }
+        let len = batch.num_rows().min(remaining);
+        shown.push(batch.slice(0, len));
+        remaining -= len;
+    }
+    Ok(format!("{}\n... ({} more rows)", format_batches(&shown)?, total - max_rows))
+}
+
+fn format_batches(result: &[RecordBatch]) -> Result<String> {
+    let table = pretty::pretty_format_batches(result).map_err(ErrorCode::ArrowError)?;
+    Ok(table.to_string())
+}
+
+/// 将数组中第 idx 个值转换为字符串 空值返回 None
+pub fn value_at(array: &ArrayRef, idx: usize) -> Option<String> {
+    if array.is_null(idx) {
+        return None;
+    }
+    match array.data_type() {
+        DataType::Null => None,
+        DataType::Int64 => Some(downcast::<Int64Array>(array).value(idx).to_string()),
+        DataType::UInt64 => Some(downcast::<UInt64Array>(array).value(idx).to_string()),
+        DataType::Float64 => Some(downcast::<Float64Array>(array).value(idx).to_string()),
+        DataType::Boolean => Some(downcast::<BooleanArray>(array).value(idx).to_string()),
+        DataType::Utf8 => Some(downcast::<StringArray>(array).value(idx).to_string()),
+        // 其他类型交给 arrow 的格式化
+        _ => array_value_to_string(array, idx).ok(),
+    }
+}
+
+fn downcast<T: 'static>(array: &ArrayRef) -> &T {
+    array.as_any().downcast_ref::<T>().unwrap()
+}
+
+/// 将查询结果按行转换为字符串 空值显示为 `NULL`
+pub fn batches_to_strings(batches: &[RecordBatch]) -> Result<Vec<Vec<String>>> {
+    let mut rows = vec![];
+    for batch in batches {
+        for row in 0..batch.num_rows() {
+            let cells = batch
+                .columns()
+                .iter()
+                .map(|column| value_at(column, row).unwrap_or_else(|| "NULL".to_string()))
+                .collect();
+            rows.push(cells);
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use arrow::array::{Date32Array, NullArray};
+
+    fn values(array: ArrayRef) -> Vec<Option<String>> {
+        (0..array.len()).map(|idx| value_at(&array, idx)).collect()
+    }
+
+    fn expected(values: &[Option<&str>]) -> Vec<Option<String>> {
+        values.iter().map(|v| v.map(str::to_string)).collect()
+    }
+
+    fn ids(values: &[i64]) -> RecordBatch {
+        let array: ArrayRef = Arc::new(Int64Array::from(values.to_vec()));
+        RecordBatch::try_from_iter(vec![("id", array)]).unwrap()
+    }
+
+    #[test]
+    fn format_result_limited_truncates_across_batches() {
+        let result = vec![ids(&[1, 2]), ids(&[3, 4, 5])];
+        let table = format_result_limited(&result, 3).unwrap();
+        assert_eq!(
+            table,
+            ["+----+", "| id |", "+----+", "| 1  |", "| 2  |", "| 3  |", "+----+", "... (2 more rows)"].join("\n")
+        );
+        // 不超过 max_rows 或 max_rows 为 0 时不显示页脚
+        assert!(!format_result_limited(&result, 5).unwrap().contains("more rows"));
+        assert_eq!(format_result_limited(&result, 0).unwrap().lines().count(), 9);
+    }
+
+    #[test]
+    fn value_at_each_type_with_nulls() {
+        let int64: ArrayRef = Arc::new(Int64Array::from(vec![Some(-1), None]));
+        assert_eq!(values(int64), expected(&[Some("-1"), None]));
+        let uint64: ArrayRef = Arc::new(UInt64Array::from(vec![None, Some(18446744073709551615)]));
+        assert_eq!(values(uint64), expected(&[None, Some("18446744073709551615")]));
+        let float64: ArrayRef = Arc::new(Float64Array::from(vec![Some(0.5), None, Some(2.0)]));
+        assert_eq!(values(float64), expected(&[Some("0.5"), None, Some("2")]));
+        let boolean: ArrayRef = Arc::new(BooleanArray::from(vec![Some(true), Some(false), None]));
+        assert_eq!(values(boolean), expected(&[Some("true"), Some("false"), None]));
+        let utf8: ArrayRef = Arc::new(StringArray::from(vec![Some("a"), None, Some("")]));
+        assert_eq!(values(utf8), expected(&[Some("a"), None, Some("")]));
+        let null: ArrayRef = Arc::new(NullArray::new(2));
+        assert_eq!(values(null), expected(&[None, None]));
+        // 其他类型使用 arrow 的格式化
+        let date32: ArrayRef = Arc::new(Date32Array::from(vec![Some(0), None]));
+        assert_eq!(values(date32), expected(&[Some("1970-01-01"), None]));
+    }
+}
@@ -0,0 +1,254 @@
+mod common;
+
+use arrow::datatypes::DataType;
+use simple_db::SimpleDB;
+
+use common::{db, rows};
+
+#[test]
+fn group_by_beyond_max_groups_is_an_error() {
+    let mut db = db();
+    let sql = "SELECT department_id, count(*), sum(rank) FROM employee GROUP BY department_id";
+
+    db.set_max_groups(2);
+    let err = db.query(sql).unwrap_err();
+    assert!(format!("{:?}", err).contains("too many groups"), "{:?}", err);
+
+    // 分组数恰好等于上限时可以执行
+    db.set_max_groups(3);
+    let expected = rows(&[&["1", "2", "1"], &["2", "2", "1"], &["3", "1", "2"]]);
+    assert_eq!(db.query(sql).unwrap(), expected);
+
+    // 0 恢复为默认上限
+    db.set_max_groups(0);
+    assert_eq!(db.query(sql).unwrap(), expected);
+}
+
+#[test]
+fn grouping_columns_are_emitted_with_the_aggregates() {
+    let mut db = db();
+    let result = db
+        .query("SELECT department_id, avg(rank) FROM employee GROUP BY department_id ORDER BY department_id")
+        .unwrap();
+    assert_eq!(result, rows(&[&["1", "0.5"], &["2", "0.5"], &["3", "2"]]));
+    // 分组列可以出现在聚合结果之后
+    let reordered = db
+        .query("SELECT count(id), rank FROM employee GROUP BY rank ORDER BY rank")
+        .unwrap();
+    assert_eq!(reordered, rows(&[&["2", "0"], &["2", "1"], &["1", "2"]]));
+}
+
+#[test]
+fn count_of_a_string_column_is_an_integer() {
+    let mut db = db();
+    let schema = db
+        .query_schema("SELECT count(name), count(DISTINCT name) FROM employee")
+        .unwrap();
+    for field in schema.fields() {
+        assert_eq!(field.data_type(), &DataType::Int64, "{}", field.name());
+    }
+    assert_eq!(db.query("SELECT count(name) FROM employee").unwrap(), rows(&[&["5"]]));
+}
+
+#[test]
+fn limit_applies_to_the_groups() {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE g (k int, v int)").unwrap();
+    db.run_sql("INSERT INTO g VALUES (1, 1), (2, 1), (3, 1), (4, 1), (5, 1), (6, 1), (7, 1), (8, 1), (1, 2)")
+        .unwrap();
+
+    let limited = db.query("SELECT k, count(*) FROM g GROUP BY k LIMIT 5").unwrap();
+    assert_eq!(limited.len(), 5);
+    // 计划中 Limit 在 Aggregate 之上
+    let plan = db.query("EXPLAIN SELECT k, count(*) FROM g GROUP BY k LIMIT 5").unwrap();
+    let position = |node: &str| plan.iter().position(|line| line[0].trim() == node).unwrap();
+    assert!(position("Limit:") < position("Aggregate:"));
+    let sorted = db
+        .query("SELECT k, count(*) FROM g GROUP BY k ORDER BY k DESC LIMIT 5 OFFSET 5")
+        .unwrap();
+    assert_eq!(sorted, rows(&[&["3", "1"], &["2", "1"], &["1", "2"]]));
+}
+
+#[test]
+fn literal_alongside_count_without_group_by() {
+    let mut db = db();
+    let result = db
+        .query("SELECT 'total' AS label, count(*), 1 + 1 FROM employee")
+        .unwrap();
+    assert_eq!(result, rows(&[&["total", "5", "2"]]));
+}
+
+#[test]
+fn group_by_two_columns_sums_each_combination() {
+    let mut db = db();
+    db.run_sql("INSERT INTO employee VALUES (6, 'kim', 1, 1)").unwrap();
+    let result = db
+        .query("SELECT department_id, rank, sum(id) FROM employee GROUP BY department_id, rank")
+        .unwrap();
+    assert_eq!(
+        result,
+        rows(&[&["1", "1", "7"], &["1", "0", "2"], &["2", "0", "3"], &["2", "1", "4"], &["3", "2", "5"]])
+    );
+}
+
+#[test]
+fn null_grouping_keys_form_their_own_group() {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE t (dept varchar, rank int, v int)").unwrap();
+    db.run_sql("INSERT INTO t VALUES ('a', 1, 1), (NULL, 1, 2), ('a', 1, 3), (NULL, 2, 4)").unwrap();
+
+    let result = db.query("SELECT dept, count(*), sum(v) FROM t GROUP BY dept").unwrap();
+    assert_eq!(result, rows(&[&["a", "2", "4"], &["NULL", "2", "6"]]));
+    // 复合分组键中 null 与其他列的取值一起区分分组
+    let result = db
+        .query("SELECT dept, rank, sum(v) FROM t GROUP BY dept, rank")
+        .unwrap();
+    assert_eq!(result, rows(&[&["a", "1", "4"], &["NULL", "1", "2"], &["NULL", "2", "4"]]));
+}
+
+#[test]
+fn aggregate_functions_in_where_are_rejected() {
+    let mut db = db();
+    for sql in [
+        "SELECT id FROM employee WHERE count(*) > 1",
+        "SELECT department_id FROM employee WHERE rank > 0 AND sum(rank) > 1 GROUP BY department_id",
+    ] {
+        let err = db.query(sql).unwrap_err();
+        assert_eq!(
+            format!("{:?}", err),
+            "PlanError(\"aggregate functions are not allowed in WHERE; use HAVING\")"
+        );
+    }
+    // 放在 HAVING 中可以执行
+    let result = db
+        .query("SELECT department_id FROM employee GROUP BY department_id HAVING sum(rank) > 1")
+        .unwrap();
+    assert_eq!(result, rows(&[&["3"]]));
+}
+
+#[test]
+fn conditional_sum_over_a_case_expression() {
+    let mut db = db();
+    let result = db
+        .query(
+            "SELECT sum(CASE WHEN rank > 0 THEN 1 ELSE 0 END), count(CASE WHEN rank = 0 THEN id END) \
+             FROM employee",
+        )
+        .unwrap();
+    assert_eq!(result, rows(&[&["3", "2"]]));
+    let grouped = db
+        .query(
+            "SELECT rank, sum(CASE WHEN department_id = 2 THEN id ELSE 0 END) FROM employee \
+             GROUP BY rank ORDER BY rank",
+        )
+        .unwrap();
+    assert_eq!(grouped, rows(&[&["0", "3"], &["1", "4"], &["2", "0"]]));
+}
+
+#[test]
+fn grouped_counts_are_ordered_by_the_group_key() {
+    let mut db = db();
+    let result = db
+        .query("SELECT department_id, count(*) FROM employee GROUP BY department_id ORDER BY department_id DESC")
+        .unwrap();
+    assert_eq!(result, rows(&[&["3", "1"], &["2", "2"], &["1", "2"]]));
+    // 排序键也可以是聚合的结果
+    let result = db
+        .query("SELECT rank, count(*) FROM employee GROUP BY rank ORDER BY count(*), rank DESC")
+        .unwrap();
+    assert_eq!(result, rows(&[&["2", "1"], &["1", "2"], &["0", "2"]]));
+}
+
+#[test]
+fn count_of_person_names_is_an_integer_column() {
+    let mut db = db();
+    let batches = db.run_sql("SELECT count(name) FROM person").unwrap();
+    // 声明的类型与实际产生的数组一致
+    assert_eq!(batches[0].schema().field(0).data_type(), batches[0].column(0).data_type());
+    assert_eq!(db.query("SELECT count(name) FROM person").unwrap(), rows(&[&["4"]]));
+}
+
+#[test]
+fn count_distinct_ignores_nulls_and_resets_per_group() {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE t (g int, v int, s varchar)").unwrap();
+    db.run_sql("INSERT INTO t VALUES (1, 1, 'a'), (1, 1, 'b'), (1, NULL, 'a'), (2, 3, NULL), (2, 4, 'c')")
+        .unwrap();
+    let result = db
+        .query("SELECT g, count(DISTINCT v), count(DISTINCT s), count(v), count(*) FROM t GROUP BY g ORDER BY g")
+        .unwrap();
+    assert_eq!(result, rows(&[&["1", "1", "2", "2", "3"], &["2", "2", "1", "2", "2"]]));
+    let result = db.query("SELECT count(DISTINCT v), count(DISTINCT s) FROM t").unwrap();
+    assert_eq!(result, rows(&[&["3", "3"]]));
+}
+
+#[test]
+fn having_compares_counts_with_integer_literals() {
+    let mut db = db();
+    let result = db
+        .query("SELECT department_id, count(*) FROM employee GROUP BY department_id HAVING count(*) > 1 ORDER BY department_id")
+        .unwrap();
+    assert_eq!(result, rows(&[&["1", "2"], &["2", "2"]]));
+    let result = db
+        .query("SELECT department_id FROM employee GROUP BY department_id HAVING count(*) = 1")
+        .unwrap();
+    assert_eq!(result, rows(&[&["3"]]));
+    // 字面量在左侧同样可以比较
+    let result = db
+        .query("SELECT department_id FROM employee GROUP BY department_id HAVING 2 <= count(*) ORDER BY department_id")
+        .unwrap();
+    assert_eq!(result, rows(&[&["1"], &["2"]]));
+}
+
+#[test]
+fn sum_keeps_the_type_of_its_input() {
+    let mut db = db();
+    let batches = db
+        .run_sql("SELECT sum(id), sum(id * 1.5) FROM employee")
+        .unwrap();
+    assert_eq!(batches[0].column(0).data_type(), &DataType::Int64);
+    assert_eq!(batches[0].column(1).data_type(), &DataType::Float64);
+    assert_eq!(
+        db.query("SELECT sum(id), sum(id * 1.5) FROM employee").unwrap(),
+        rows(&[&["15", "22.5"]])
+    );
+    // count 的结果为 Int64，对其求和仍为 Int64
+    let sql = "SELECT sum(c) FROM (SELECT count(*) AS c FROM employee GROUP BY department_id) t";
+    let batches = db.run_sql(sql).unwrap();
+    assert_eq!(batches[0].column(0).data_type(), &DataType::Int64);
+    assert_eq!(db.query(sql).unwrap(), rows(&[&["5"]]));
+}
+
+/// dept 1 有一行，dept 2 的行数最多但 v 的和最小，dept 3 的 v 的和最大
+fn groups() -> SimpleDB {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE g (dept int, v int)").unwrap();
+    db.run_sql("INSERT INTO g VALUES (1, 5), (2, 1), (3, 10), (2, 1), (3, 10), (2, 1)").unwrap();
+    db
+}
+
+#[test]
+fn order_by_an_aggregate_that_is_not_selected() {
+    let mut db = groups();
+    let sql = "SELECT dept FROM g GROUP BY dept ORDER BY count(*) DESC";
+    // 排序用的聚合结果不出现在输出中
+    assert_eq!(db.query_schema(sql).unwrap().fields().len(), 1);
+    assert_eq!(db.query(sql).unwrap(), rows(&[&["2"], &["3"], &["1"]]));
+    assert_eq!(
+        db.query("SELECT dept FROM g GROUP BY dept ORDER BY sum(v)").unwrap(),
+        rows(&[&["2"], &["1"], &["3"]])
+    );
+}
+
+#[test]
+fn having_combines_aggregates_that_are_not_selected() {
+    let mut db = groups();
+    // dept 1 的行数不够，dept 2 的和不够，只有 dept 3 同时满足两个条件
+    let sql = "SELECT dept FROM g GROUP BY dept HAVING sum(v) > 4 AND count(*) > 1";
+    assert_eq!(db.query_schema(sql).unwrap().fields().len(), 1);
+    assert_eq!(db.query(sql).unwrap(), rows(&[&["3"]]));
+    assert_eq!(
+        db.query("SELECT dept FROM g GROUP BY dept HAVING sum(v) > 4 OR count(*) > 2 ORDER BY dept").unwrap(),
+        rows(&[&["1"], &["2"], &["3"]])
+    );
+}
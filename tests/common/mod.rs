@@ -0,0 +1,30 @@
+#![allow(dead_code)]
+
+use std::env;
+use std::path::PathBuf;
+
+use simple_db::{CsvConfig, SimpleDB};
+
+/// 注册了 data 目录下示例表的数据库
+pub fn db() -> SimpleDB {
+    let mut db = SimpleDB::default();
+    for table in ["employee", "department", "rank", "person", "knows"] {
+        db.create_csv_table(table, &format!("data/{}.csv", table), CsvConfig::default())
+            .unwrap();
+    }
+    db
+}
+
+/// 把期望的结果写成 `&[&[&str]]` 方便与 `SimpleDB::query` 的结果比较
+pub fn rows(expected: &[&[&str]]) -> Vec<Vec<String>> {
+    expected
+        .iter()
+        .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+        .collect()
+}
+
+/// 测试用的临时文件路径 文件名带上进程号 避免并行执行的测试互相覆盖
+pub fn temp_path(name: &str) -> String {
+    let path: PathBuf = env::temp_dir().join(format!("simple_db_{}_{}", std::process::id(), name));
+    path.to_string_lossy().into_owned()
+}
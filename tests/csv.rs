@@ -0,0 +1,107 @@
+mod common;
+
+use std::fs;
+
+use arrow::datatypes::{DataType, Field, Schema};
+use simple_db::{CsvConfig, SimpleDB};
+
+use common::{db, rows, temp_path};
+
+fn schema(name_nullable: bool) -> Schema {
+    Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, name_nullable),
+    ])
+}
+
+#[test]
+fn loading_nulls_into_a_not_null_column_is_an_error() {
+    let path = temp_path("not_null.csv");
+    fs::write(&path, "id,name\n1,a\n2,\n3,c\n").unwrap();
+
+    let mut db = SimpleDB::default();
+    let err = db
+        .create_csv_table_with_schema("t", &path, schema(false), CsvConfig::default())
+        .unwrap_err();
+    let message = format!("{:?}", err);
+    assert!(message.starts_with("LogicalError"), "{}", message);
+    assert!(message.contains("column `name` is declared NOT NULL but row 2 is null"), "{}", message);
+
+    // 可为空的列允许空值
+    db.create_csv_table_with_schema("t", &path, schema(true), CsvConfig::default())
+        .unwrap();
+    assert_eq!(db.query("SELECT id FROM t").unwrap(), rows(&[&["1"], &["2"], &["3"]]));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn errors_and_plans_name_the_csv_table() {
+    let mut db = db();
+    db.run_sql("CREATE UNIQUE INDEX department_id ON department (id)").unwrap();
+    let err = db.run_sql("INSERT INTO department VALUES (2, 'Sales')").unwrap_err();
+    assert!(format!("{:?}", err).contains("duplicate value (2) for column (department.id)"), "{:?}", err);
+    let err = db.run_sql("CREATE UNIQUE INDEX rank_id ON employee (rank)").unwrap_err();
+    assert!(format!("{:?}", err).contains("for column (employee.rank)"), "{:?}", err);
+
+    // 连接的两个 CSV 表在计划中按注册的表名区分
+    let plan = db
+        .query("EXPLAIN SELECT e.name FROM employee e JOIN department d ON e.department_id = d.id")
+        .unwrap();
+    let plan = plan.iter().map(|row| row.join(" ")).collect::<Vec<_>>().join("\n");
+    assert!(plan.contains("\"employee\"") && plan.contains("\"department\""), "{}", plan);
+    assert!(!plan.contains("CsvTable"), "{}", plan);
+}
+
+#[test]
+fn reload_reads_rows_added_to_the_file() {
+    let path = temp_path("reload.csv");
+    fs::write(&path, "id,name\n1,a\n").unwrap();
+    let mut db = SimpleDB::default();
+    db.create_csv_table("t", &path, CsvConfig::default()).unwrap();
+    db.run_sql("INSERT INTO t VALUES (9, 'memory only')").unwrap();
+
+    fs::write(&path, "id,name\n1,a\n2,b\n").unwrap();
+    db.run_sql("RELOAD TABLE t").unwrap();
+    // 重新读取文件 内存中插入的行被丢弃
+    assert_eq!(db.query("SELECT id, name FROM t ORDER BY id").unwrap(), rows(&[&["1", "a"], &["2", "b"]]));
+
+    fs::write(&path, "id,name\n1,a\n2,b\n3,c\n").unwrap();
+    db.reload_csv_table("t").unwrap();
+    assert_eq!(db.query("SELECT count(*) FROM t").unwrap(), rows(&[&["3"]]));
+    assert!(db.run_sql("RELOAD TABLE nope").is_err());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn trim_strips_padding_from_string_cells() {
+    let path = temp_path("trim.csv");
+    fs::write(&path, "id,name\n1, Alice \n2,Bob  \n").unwrap();
+    let mut db = SimpleDB::default();
+    db.create_csv_table("padded", &path, CsvConfig::default()).unwrap();
+    assert_eq!(db.query("SELECT id FROM padded WHERE name = 'Alice'").unwrap(), rows(&[]));
+
+    let config = CsvConfig { trim: true, ..CsvConfig::default() };
+    db.create_csv_table("trimmed", &path, config).unwrap();
+    assert_eq!(db.query("SELECT id FROM trimmed WHERE name = 'Alice'").unwrap(), rows(&[&["1"]]));
+    assert_eq!(db.query("SELECT length(name) FROM trimmed WHERE id = 2").unwrap(), rows(&[&["3"]]));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn a_directory_of_csv_files_is_the_union_of_their_rows() {
+    let dir = temp_path("csv_dir");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    fs::write(format!("{}/part-1.csv", dir), "id,name\n1,a\n2,b\n").unwrap();
+    fs::write(format!("{}/part-2.csv", dir), "id,name\n3,c\n").unwrap();
+    // 不是 .csv 的文件不会被读取
+    fs::write(format!("{}/notes.txt", dir), "id,name\n9,z\n").unwrap();
+
+    let mut db = SimpleDB::default();
+    db.create_csv_table_from_dir("t", &dir, CsvConfig::default()).unwrap();
+    assert_eq!(
+        db.query("SELECT t.id, name FROM t ORDER BY id").unwrap(),
+        rows(&[&["1", "a"], &["2", "b"], &["3", "c"]])
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
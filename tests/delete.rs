@@ -0,0 +1,54 @@
+mod common;
+
+use simple_db::{CsvConfig, SimpleDB};
+
+use common::{db, rows};
+
+#[test]
+fn delete_reports_the_count_and_keeps_qualified_names() {
+    let mut db = db();
+    let deleted = db.query("DELETE FROM employee WHERE rank = 0").unwrap();
+    assert_eq!(deleted, rows(&[&["2"]]));
+
+    let remaining = db
+        .query("SELECT employee.id, employee.name FROM employee WHERE employee.rank > 0 ORDER BY employee.id")
+        .unwrap();
+    assert_eq!(remaining, rows(&[&["1", "vee"], &["4", "jack"], &["5", "mike"]]));
+
+    // 没有匹配的行时删除 0 行
+    assert_eq!(db.query("DELETE FROM employee WHERE id > 100").unwrap(), rows(&[&["0"]]));
+}
+
+#[test]
+fn delete_matches_rows_in_every_batch() {
+    let mut db = SimpleDB::default();
+    // 每个 batch 两行 五行数据分成三个 batch，被删除的行分布在第一个和最后一个 batch 中
+    let csv_conf = CsvConfig {
+        batch_size: 2,
+        ..CsvConfig::default()
+    };
+    db.create_csv_table("employee", "data/employee.csv", csv_conf).unwrap();
+    assert_eq!(db.query("DELETE FROM employee WHERE id = 2 OR id = 5").unwrap(), rows(&[&["2"]]));
+    let remaining = db.query("SELECT id, name FROM employee ORDER BY id").unwrap();
+    assert_eq!(remaining, rows(&[&["1", "vee"], &["3", "Alex"], &["4", "jack"]]));
+
+    // 删除后剩余的行仍然可以按 batch 内的行号删除
+    assert_eq!(db.query("DELETE FROM employee WHERE name = 'jack'").unwrap(), rows(&[&["1"]]));
+    assert_eq!(db.query("SELECT id FROM employee ORDER BY id").unwrap(), rows(&[&["1"], &["3"]]));
+}
+
+#[test]
+fn delete_rows_whose_key_is_in_a_subquery() {
+    let mut db = db();
+    let deleted = db
+        .query("DELETE FROM employee WHERE department_id IN (SELECT id FROM department WHERE department_name = 'IT')")
+        .unwrap();
+    assert_eq!(deleted, rows(&[&["2"]]));
+    assert_eq!(db.query("SELECT id FROM employee ORDER BY id").unwrap(), rows(&[&["3"], &["4"], &["5"]]));
+
+    let deleted = db
+        .query("DELETE FROM employee WHERE department_id NOT IN (SELECT id FROM department WHERE id > 2)")
+        .unwrap();
+    assert_eq!(deleted, rows(&[&["2"]]));
+    assert_eq!(db.query("SELECT id FROM employee").unwrap(), rows(&[&["5"]]));
+}
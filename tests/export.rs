@@ -0,0 +1,44 @@
+mod common;
+
+use std::fs;
+
+use simple_db::{CsvConfig, SimpleDB};
+
+use common::{db, rows, temp_path};
+
+#[test]
+fn feather_export_can_be_registered_again() {
+    let mut db = db();
+    let path = temp_path("employee.feather");
+    db.export_query_to_feather("SELECT id, name FROM employee WHERE rank > 0 ORDER BY id", &path)
+        .unwrap();
+    db.create_feather_table("exported", &path).unwrap();
+
+    let result = db.query("SELECT id, name FROM exported").unwrap();
+    assert_eq!(result, rows(&[&["1", "vee"], &["4", "jack"], &["5", "mike"]]));
+    let err = db.reload_csv_table("exported").unwrap_err();
+    assert!(format!("{:?}", err).contains("exported does not support RELOAD"));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn csv_export_quotes_delimiters_quotes_and_newlines() {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE t (id int, note varchar)").unwrap();
+    db.run_sql("INSERT INTO t VALUES (1, 'a, b'), (2, 'say \"hi\"'), (3, 'two\nlines'), (4, 'plain')")
+        .unwrap();
+    let path = temp_path("quoted.csv");
+    db.export_query_to_csv("SELECT id, note FROM t ORDER BY id", &path).unwrap();
+    let content = fs::read_to_string(&path).unwrap();
+    assert!(content.contains("\"a, b\""), "{}", content);
+    assert!(content.contains("\"say \"\"hi\"\"\""), "{}", content);
+
+    // 重新读取后每个值保持不变
+    db.create_csv_table("reimported", &path, CsvConfig::default()).unwrap();
+    let result = db.query("SELECT id, note FROM reimported ORDER BY id").unwrap();
+    assert_eq!(
+        result,
+        rows(&[&["1", "a, b"], &["2", "say \"hi\""], &["3", "two\nlines"], &["4", "plain"]])
+    );
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use simple_db::{CsvConfig, SimpleDB};
+
+use common::rows;
+
+#[test]
+fn substring_clamps_out_of_range_bounds() {
+    let mut db = SimpleDB::default();
+    // start 从 1 开始：start 为 0 或负数时 [start, start + len) 中第一个字符之前的部分被截掉
+    let result = db
+        .query("SELECT substring('hello', 0, 3), substring('hello', -1, 4), substring('hello', 9, 2), substring('hello', 2, -1)")
+        .unwrap();
+    assert_eq!(result, rows(&[&["he", "he", "", ""]]));
+    let result = db.query("SELECT substring('hello', 2), substring('hello' FROM 2 FOR 3)").unwrap();
+    assert_eq!(result, rows(&[&["ello", "ell"]]));
+}
+
+#[test]
+fn substring_arguments_can_contain_parentheses_and_commas() {
+    let mut db = SimpleDB::default();
+    db.create_csv_table("person", "data/person.csv", CsvConfig::default()).unwrap();
+    let result = db
+        .query("SELECT substring(upper(trim('  hello  ')), (1 + 1), length(('ab')))")
+        .unwrap();
+    assert_eq!(result, rows(&[&["EL"]]));
+    let result = db
+        .query("SELECT name FROM person WHERE substring(name, (length(name) - 1), 2) = 'ao'")
+        .unwrap();
+    assert_eq!(result, rows(&[&["xiao"]]));
+}
+
+#[test]
+fn trim_functions_remove_leading_and_trailing_spaces() {
+    let mut db = SimpleDB::default();
+    let result = db
+        .query("SELECT trim('  a b  '), ltrim('  a '), rtrim(' a  '), trim(''), trim(NULL)")
+        .unwrap();
+    assert_eq!(result, rows(&[&["a b", "a ", " a", "", "NULL"]]));
+}
+
+#[test]
+fn math_functions_return_null_for_null_arguments() {
+    let mut db = SimpleDB::default();
+    let result = db
+        .query("SELECT abs(NULL), round(NULL), round(1.5, NULL), ceil(NULL), floor(NULL), sqrt(NULL)")
+        .unwrap();
+    assert_eq!(result, rows(&[&["NULL", "NULL", "NULL", "NULL", "NULL", "NULL"]]));
+
+    db.run_sql("CREATE TABLE t (x float)").unwrap();
+    db.run_sql("INSERT INTO t VALUES (-2.5), (NULL)").unwrap();
+    let result = db.query("SELECT abs(x), round(x), ceil(x), floor(x) FROM t").unwrap();
+    assert_eq!(result, rows(&[&["2.5", "-3", "-2", "-3"], &["NULL", "NULL", "NULL", "NULL"]]));
+}
+
+#[test]
+fn math_functions_handle_out_of_domain_arguments() {
+    let mut db = SimpleDB::default();
+    // 负数的平方根为 NULL 而不是 NaN
+    let result = db.query("SELECT sqrt(-1), sqrt(-0.5), sqrt(0), sqrt(4)").unwrap();
+    assert_eq!(result, rows(&[&["NULL", "NULL", "0", "2"]]));
+    // Int64 的最小值取绝对值会溢出
+    let err = db.query("SELECT abs(-9223372036854775807 - 1)").unwrap_err();
+    assert!(format!("{:?}", err).contains("abs(-9223372036854775808) overflows Int64"));
+    let err = db.query("SELECT sqrt('a')").unwrap_err();
+    assert!(format!("{:?}", err).contains("sqrt expects a number as argument 1, found Utf8"));
+}
+
+#[test]
+fn length_counts_characters_not_bytes() {
+    let mut db = SimpleDB::default();
+    let result = db
+        .query("SELECT length('héllo'), length('日本語'), length('a😀b'), length(''), length(NULL)")
+        .unwrap();
+    assert_eq!(result, rows(&[&["5", "3", "3", "0", "NULL"]]));
+
+    db.run_sql("CREATE TABLE t (name varchar)").unwrap();
+    db.run_sql("INSERT INTO t VALUES ('数据库'), (NULL)").unwrap();
+    let result = db.query("SELECT length(name) FROM t").unwrap();
+    assert_eq!(result, rows(&[&["3"], &["NULL"]]));
+}
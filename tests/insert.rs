@@ -0,0 +1,146 @@
+mod common;
+
+use std::fs;
+
+use simple_db::SimpleDB;
+
+use common::{db, rows, temp_path};
+
+fn table() -> SimpleDB {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE t (id int, name varchar, price decimal(10,2))").unwrap();
+    db
+}
+
+#[test]
+fn insert_maps_values_to_reordered_and_partial_column_lists() {
+    let mut db = table();
+    db.run_sql("INSERT INTO t (name, id) VALUES ('b', 2)").unwrap();
+    // 没有列出的 Decimal 列填充为 NULL
+    db.run_sql("INSERT INTO t (id, name) VALUES (1, 'a')").unwrap();
+    db.run_sql("INSERT INTO t (price, id) VALUES (3, 3)").unwrap();
+    let result = db.query("SELECT id, name, price FROM t ORDER BY id").unwrap();
+    assert_eq!(
+        result,
+        rows(&[&["1", "a", "NULL"], &["2", "b", "NULL"], &["3", "NULL", "3.00"]])
+    );
+
+    let err = db.run_sql("INSERT INTO t (id, nope) VALUES (4, 'x')").unwrap_err();
+    assert!(format!("{:?}", err).starts_with("PlanError"), "{:?}", err);
+}
+
+#[test]
+fn insert_rejects_rows_with_the_wrong_number_of_values() {
+    let mut db = table();
+    let err = db.run_sql("INSERT INTO t VALUES (1, 'a')").unwrap_err();
+    assert!(format!("{:?}", err).contains("INSERT row 1 has 2 values, expected 3"), "{:?}", err);
+    let err = db.run_sql("INSERT INTO t (id) VALUES (1), (2, 'b')").unwrap_err();
+    assert!(format!("{:?}", err).contains("INSERT row 2 has 2 values, expected 1"), "{:?}", err);
+    let err = db.run_sql("INSERT INTO t VALUES (1, 'a', 1), ()").unwrap_err();
+    assert!(format!("{:?}", err).contains("INSERT row 2 has no values"), "{:?}", err);
+    // 出错时不插入任何行
+    assert!(db.query("SELECT id FROM t").unwrap().is_empty());
+}
+
+#[test]
+fn insert_requires_at_least_one_non_empty_values_row() {
+    let mut db = table();
+    for sql in ["INSERT INTO t VALUES", "INSERT INTO t VALUES;"] {
+        let err = db.run_sql(sql).unwrap_err();
+        assert_eq!(format!("{:?}", err), r#"PlanError("INSERT requires at least one VALUES row")"#, "{}", sql);
+    }
+    for sql in ["INSERT INTO t VALUES ()", "INSERT INTO t (id) VALUES ()", "INSERT INTO t VALUES (), (1, 'a', 1)"] {
+        let err = db.run_sql(sql).unwrap_err();
+        assert_eq!(format!("{:?}", err), r#"PlanError("INSERT row 1 has no values")"#, "{}", sql);
+    }
+    assert!(db.query("SELECT id FROM t").unwrap().is_empty());
+}
+
+#[test]
+fn string_literals_with_quotes_and_unicode_round_trip() {
+    let mut db = table();
+    db.run_sql("INSERT INTO t (id, name) VALUES (1, 'O''Brien'), (2, N'Zoë'), (3, '東京')").unwrap();
+    let apostrophe = db.query("SELECT id, name FROM t WHERE name = 'O''Brien'").unwrap();
+    assert_eq!(apostrophe, rows(&[&["1", "O'Brien"]]));
+    let unicode = db
+        .query("SELECT id, upper(name) FROM t WHERE name = N'Zoë' OR name = '東京' ORDER BY id")
+        .unwrap();
+    assert_eq!(unicode, rows(&[&["2", "ZOË"], &["3", "東京"]]));
+}
+
+#[test]
+fn memory_and_csv_tables_are_mutated_through_the_same_statements() {
+    let mut db = db();
+    // employee 是 CSV 表 m 是 CREATE TABLE 创建的内存表 内容相同
+    db.run_sql("CREATE TABLE m (id int, name varchar, department_id int, rank int)").unwrap();
+    db.run_sql("INSERT INTO m VALUES (1, 'vee', 1, 1), (2, 'lynne', 1, 0), (3, 'Alex', 2, 0), (4, 'jack', 2, 1), (5, 'mike', 3, 2)")
+        .unwrap();
+    for table in ["employee", "m"] {
+        db.run_sql(&format!("INSERT INTO {} VALUES (6, 'ann', 3, 0)", table)).unwrap();
+        db.run_sql(&format!("UPDATE {} SET rank = rank + 1 WHERE department_id = 2", table)).unwrap();
+        let deleted = db.query(&format!("DELETE FROM {} WHERE rank = 0", table)).unwrap();
+        assert_eq!(deleted, rows(&[&["2"]]), "{}", table);
+    }
+    let sql = |table: &str| format!("SELECT id, name, rank FROM {} ORDER BY id", table);
+    let expected = rows(&[&["1", "vee", "1"], &["3", "Alex", "1"], &["4", "jack", "2"], &["5", "mike", "2"]]);
+    assert_eq!(db.query(&sql("employee")).unwrap(), expected);
+    assert_eq!(db.query(&sql("m")).unwrap(), expected);
+}
+
+#[test]
+fn execute_file_runs_every_statement_of_a_script() {
+    let path = temp_path("script.sql");
+    let script = "CREATE TABLE t (id int, name varchar);\n\
+                  -- 引号中的分号不是语句的结尾\n\
+                  INSERT INTO t VALUES (1, 'a;b');\n\
+                  INSERT INTO t VALUES (2, 'it''s');\n\
+                  \n\
+                  INSERT INTO t VALUES (3, 'c')";
+    fs::write(&path, script).unwrap();
+    let mut db = SimpleDB::default();
+    db.execute_file(&path).unwrap();
+    let result = db.query("SELECT id, name FROM t ORDER BY id").unwrap();
+    assert_eq!(result, rows(&[&["1", "a;b"], &["2", "it's"], &["3", "c"]]));
+
+    // 遇到错误时停止 报告语句所在的行号
+    fs::write(&path, "INSERT INTO t VALUES (4, 'd');\nINSERT INTO nope VALUES (5);\nINSERT INTO t VALUES (6, 'f');").unwrap();
+    let err = db.execute_file(&path).unwrap_err();
+    assert!(format!("{:?}", err).contains("error at line 2"), "{:?}", err);
+    assert_eq!(db.query("SELECT id FROM t WHERE id > 3").unwrap(), rows(&[&["4"]]));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn unique_column_rejects_duplicate_inserts() {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE u (id int, name varchar UNIQUE)").unwrap();
+    db.run_sql("INSERT INTO u VALUES (1, 'a'), (2, 'b')").unwrap();
+    let err = db.run_sql("INSERT INTO u VALUES (3, 'a')").unwrap_err();
+    assert!(format!("{:?}", err).contains("unique constraint violation"), "{:?}", err);
+    // 同一条 INSERT 内部的重复值也会被拒绝，整条语句不生效
+    assert!(db.run_sql("INSERT INTO u VALUES (3, 'c'), (4, 'c')").is_err());
+    db.run_sql("INSERT INTO u VALUES (3, 'c')").unwrap();
+    assert_eq!(
+        db.query("SELECT id, name FROM u ORDER BY id").unwrap(),
+        rows(&[&["1", "a"], &["2", "b"], &["3", "c"]])
+    );
+}
+
+#[test]
+fn null_into_a_not_null_column_is_an_error() {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE n (id int NOT NULL, name varchar)").unwrap();
+    for (sql, row) in [
+        ("INSERT INTO n VALUES (NULL, 'a')", 1),
+        ("INSERT INTO n (name) VALUES ('a')", 1),
+        ("INSERT INTO n VALUES (1, 'a'), (NULL + 1, 'b')", 2),
+    ] {
+        let err = db.run_sql(sql).unwrap_err();
+        let expected = format!("LogicalError(\"column `id` is declared NOT NULL but INSERT row {} is null\")", row);
+        assert_eq!(format!("{:?}", err), expected, "{}", sql);
+    }
+    // 没有 NOT NULL 约束的列可以为空
+    db.run_sql("INSERT INTO n (id) VALUES (1)").unwrap();
+    db.run_sql("INSERT INTO n VALUES (2, NULL)").unwrap();
+    assert_eq!(db.query("SELECT id, name FROM n ORDER BY id").unwrap(), rows(&[&["1", "NULL"], &["2", "NULL"]]));
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use simple_db::SimpleDB;
+
+use common::rows;
+
+/// a(id, x) 和 b(id, y) 两张表 两边都有一个 NULL 的 id
+fn tables() -> SimpleDB {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE a (id int, x int)").unwrap();
+    db.run_sql("INSERT INTO a VALUES (1, 10), (2, 20), (NULL, 30)").unwrap();
+    db.run_sql("CREATE TABLE b (id int, y int)").unwrap();
+    db.run_sql("INSERT INTO b VALUES (1, 15), (3, 5), (NULL, 25)").unwrap();
+    db
+}
+
+#[test]
+fn left_join_pads_unmatched_left_rows_with_nulls() {
+    let mut db = tables();
+    // NULL 的连接键与任何值都不相等 包括另一边的 NULL
+    let result = db
+        .query("SELECT a.id, a.x, b.id, b.y FROM a LEFT JOIN b ON a.id = b.id ORDER BY a.x")
+        .unwrap();
+    assert_eq!(
+        result,
+        rows(&[
+            &["1", "10", "1", "15"],
+            &["2", "20", "NULL", "NULL"],
+            &["NULL", "30", "NULL", "NULL"],
+        ])
+    );
+}
+
+#[test]
+fn inequality_join_returns_every_matching_pair() {
+    let mut db = tables();
+    // 没有等值条件 由 NestedLoopJoinPlan 对每一对行计算 ON 条件
+    let result = db
+        .query("SELECT a.x, b.y FROM a JOIN b ON a.x < b.y ORDER BY a.x, b.y")
+        .unwrap();
+    assert_eq!(result, rows(&[&["10", "15"], &["10", "25"], &["20", "25"]]));
+}
+
+#[test]
+fn outer_joins_keep_rows_that_fail_the_residual_condition() {
+    let mut db = tables();
+    // a.id = 1 的行满足连接键 但不满足 b.y > 20 仍然保留并补 NULL
+    let result = db
+        .query("SELECT a.x, b.id, b.y FROM a LEFT JOIN b ON a.id = b.id AND b.y > 20 ORDER BY a.x")
+        .unwrap();
+    assert_eq!(
+        result,
+        rows(&[&["10", "NULL", "NULL"], &["20", "NULL", "NULL"], &["30", "NULL", "NULL"]])
+    );
+    let result = db
+        .query("SELECT a.x, b.id, b.y FROM a LEFT JOIN b ON a.id = b.id AND b.y > 10 ORDER BY a.x")
+        .unwrap();
+    assert_eq!(
+        result,
+        rows(&[&["10", "1", "15"], &["20", "NULL", "NULL"], &["30", "NULL", "NULL"]])
+    );
+
+    // RIGHT JOIN 保留右边的每一行
+    let result = db
+        .query("SELECT a.x, b.id, b.y FROM a RIGHT JOIN b ON a.id = b.id AND a.x > 10 ORDER BY b.y")
+        .unwrap();
+    assert_eq!(
+        result,
+        rows(&[&["NULL", "3", "5"], &["NULL", "1", "15"], &["NULL", "NULL", "25"]])
+    );
+}
+
+#[test]
+fn where_with_only_an_inequality_filters_the_cross_join() {
+    let mut db = tables();
+    let sql = "SELECT a.x, b.y FROM a, b WHERE a.x > b.y ORDER BY a.x, b.y";
+    let plan = db.query(&format!("EXPLAIN {}", sql)).unwrap();
+    assert!(plan.iter().any(|row| row[0].trim() == "join_type: Cross"), "{:?}", plan);
+    assert_eq!(
+        db.query(sql).unwrap(),
+        rows(&[&["10", "5"], &["20", "5"], &["20", "15"], &["30", "5"], &["30", "15"], &["30", "25"]])
+    );
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use common::{db, rows};
+
+/// 语法错误或者不支持的语句都应该返回错误 不能 panic
+const BAD_SQL: &[&str] = &[
+    "SELECT",
+    "SELECT FROM",
+    "SELECT * FROM",
+    "SELECT * FROM nope",
+    "SELECT id FROM employee WHERE",
+    "SELECT id FROM employee WHERE id =",
+    "SELECT (((id FROM employee",
+    "SELECT id, FROM employee",
+    "SELECT 'unterminated FROM employee",
+    "SELECT id FROM employee LIMIT 'a'",
+    "SELECT sum(name) FROM employee",
+    "SELECT 9223372036854775807 + 1",
+    "SELECT -9223372036854775807 - 2",
+    "SELECT 99999999999999999999999 + 9223372036854775807 * 2",
+    "SELECT upper(1, 2)",
+    "SELECT DISTINCT ON (id FROM employee",
+    "SELECT id FROM employee FETCH FIRST 'x' ROWS ONLY",
+    "SELECT $1 FROM employee",
+    "INSERT INTO employee VALUES (1)",
+    "INSERT INTO employee VALUES",
+    "INSERT INTO employee VALUES ()",
+    "INSERT INTO employee (nope) VALUES (1)",
+    "INSERT INTO employee VALUES (1, 2, 3, 4)",
+    "INSERT INTO employee VALUES (1.5, 'a', 1, 1)",
+    "INSERT INTO employee VALUES (id, 'a', 1, 1)",
+    "INSERT INTO employee SELECT * FROM employee",
+    "INSERT INTO nope VALUES (1)",
+    "UPDATE employee SET nope = 1",
+    "UPDATE nope SET id = 1",
+    "DELETE FROM nope",
+    "DELETE FROM employee WHERE nope = 1",
+    "CREATE TABLE x (a int",
+    "DROP TABLE nope",
+    "EXPLAIN",
+    "EXPLAIN SELECT nope FROM employee",
+    "CREATE VIEW v AS SELECT nope FROM employee",
+];
+
+#[test]
+fn malformed_and_unsupported_sql_returns_errors_without_panicking() {
+    let panics = Arc::new(AtomicUsize::new(0));
+    let counter = panics.clone();
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    }));
+
+    let mut db = db();
+    let failures = BAD_SQL
+        .iter()
+        .filter(|sql| db.run_sql(sql).is_ok())
+        .collect::<Vec<_>>();
+
+    // 占位符只能出现在 WHERE 中 选择列表中的占位符在执行时规划物理表达式才报错
+    let select_param = db
+        .prepare("SELECT $1 FROM employee")
+        .and_then(|stmt| stmt.execute(&db, &[]))
+        .unwrap_err();
+
+    // VALUES 中可以写常量表达式 负数和计算出的值都会转换为列的类型
+    db.run_sql("INSERT INTO employee VALUES (6, 'x', -1, 2)").unwrap();
+    db.run_sql("INSERT INTO employee VALUES (1 + 1 * 6, upper('y'), 1, 1)").unwrap();
+    let inserted = db.query("SELECT id, name, department_id FROM employee WHERE id > 5").unwrap();
+
+    panic::set_hook(default_hook);
+    assert!(failures.is_empty(), "expected errors for {:?}", failures);
+    assert_eq!(panics.load(Ordering::SeqCst), 0);
+    assert_eq!(
+        format!("{:?}", select_param),
+        "PlanError(\"parameter $1 can only be used in WHERE\")"
+    );
+    assert_eq!(inserted, rows(&[&["6", "x", "-1"], &["7", "Y", "1"]]));
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use simple_db::{ScalarValue, SimpleDB};
+
+use common::db;
+
+#[test]
+fn prepared_statement_binds_parameters_on_each_execution() {
+    let mut db = db();
+    let stmt = db
+        .prepare("SELECT id, name FROM employee WHERE department_id = ? AND rank >= $2 ORDER BY id")
+        .unwrap();
+    assert_eq!(stmt.param_count(), 2);
+
+    let count_rows = |db: &SimpleDB, params: &[ScalarValue]| -> usize {
+        stmt.execute(db, params).unwrap().iter().map(|batch| batch.num_rows()).sum()
+    };
+    assert_eq!(count_rows(&db, &[ScalarValue::Int64(Some(1)), ScalarValue::Int64(Some(0))]), 2);
+    assert_eq!(count_rows(&db, &[ScalarValue::Int64(Some(2)), ScalarValue::Int64(Some(1))]), 1);
+    assert!(stmt.execute(&db, &[ScalarValue::Int64(Some(1))]).is_err());
+
+    // 预处理语句不借用数据库 创建之后仍然可以修改数据库，每次执行读取的是数据库当前的数据
+    db.run_sql("INSERT INTO employee VALUES (6, 'x', 1, 3)").unwrap();
+    assert_eq!(count_rows(&db, &[ScalarValue::Int64(Some(1)), ScalarValue::Int64(Some(0))]), 3);
+    db.run_sql("DELETE FROM employee WHERE department_id = 1").unwrap();
+    assert_eq!(count_rows(&db, &[ScalarValue::Int64(Some(1)), ScalarValue::Int64(Some(0))]), 0);
+    assert_eq!(count_rows(&db, &[ScalarValue::Int64(Some(2)), ScalarValue::Int64(Some(0))]), 2);
+}
+
+#[test]
+fn prepared_statement_sees_a_table_created_after_prepare() {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE t (id int)").unwrap();
+    let stmt = db.prepare("SELECT id FROM t WHERE id > $1").unwrap();
+    let ids = |db: &SimpleDB| {
+        let batches = stmt.execute(db, &[ScalarValue::Int64(Some(1))]).unwrap();
+        batches.iter().map(|batch| batch.num_rows()).sum::<usize>()
+    };
+    assert_eq!(ids(&db), 0);
+    db.run_sql("INSERT INTO t VALUES (1), (2), (3)").unwrap();
+    assert_eq!(ids(&db), 2);
+    // 表被删除后执行返回错误
+    db.run_sql("DROP TABLE t").unwrap();
+    assert!(stmt.execute(&db, &[ScalarValue::Int64(Some(1))]).is_err());
+}
+
+#[test]
+fn placeholders_are_rejected_outside_prepare() {
+    let mut db = db();
+    for sql in [
+        "SELECT id FROM employee WHERE id = ?",
+        "SELECT id FROM employee LIMIT ?",
+    ] {
+        let err = db.query(sql).unwrap_err();
+        assert_eq!(
+            format!("{:?}", err),
+            "NotSupported(\"parameter placeholders are not supported\")"
+        );
+    }
+    assert!(db.prepare("SELECT id FROM employee WHERE id = $0").is_err());
+    // 引号中的 `$1` 是普通的列名 不是占位符
+    assert!(db.prepare("SELECT \"$1\" FROM employee").is_err());
+}
@@ -0,0 +1,620 @@
+mod common;
+
+use std::fs;
+
+use arrow::datatypes::DataType;
+use simple_db::{CsvConfig, SimpleDB};
+
+use common::{db, rows, temp_path};
+
+#[test]
+fn distinct_on_keeps_first_row_of_each_key() {
+    let mut db = db();
+    let result = db
+        .query("SELECT DISTINCT ON (department_id) department_id, name FROM employee ORDER BY department_id, name")
+        .unwrap();
+    assert_eq!(result, rows(&[&["1", "lynne"], &["2", "Alex"], &["3", "mike"]]));
+}
+
+#[test]
+fn distinct_on_inside_cte_and_subquery() {
+    let mut db = db();
+    let expected = rows(&[&["0", "3"], &["1", "4"], &["2", "5"]]);
+    let cte = db
+        .query("WITH t AS (SELECT DISTINCT ON (rank) rank, id FROM employee ORDER BY rank, id DESC) SELECT * FROM t")
+        .unwrap();
+    assert_eq!(cte, expected);
+    let derived = db
+        .query("SELECT * FROM (SELECT DISTINCT ON (rank) rank, id FROM employee ORDER BY rank, id DESC) s")
+        .unwrap();
+    assert_eq!(derived, expected);
+    let in_subquery = db
+        .query("SELECT id FROM employee WHERE id IN (SELECT DISTINCT ON (rank) id FROM employee ORDER BY rank, id) ORDER BY id")
+        .unwrap();
+    assert_eq!(in_subquery, rows(&[&["1"], &["2"], &["5"]]));
+}
+
+#[test]
+fn order_by_column_not_in_projection() {
+    let mut db = db();
+    let result = db.query("SELECT name FROM employee ORDER BY rank DESC, id").unwrap();
+    assert_eq!(result, rows(&[&["mike"], &["vee"], &["jack"], &["lynne"], &["Alex"]]));
+}
+
+#[test]
+fn empty_in_list_is_false_and_empty_not_in_is_true() {
+    let mut db = db();
+    let empty_in = db.query("SELECT id FROM employee WHERE id IN ()").unwrap();
+    assert!(empty_in.is_empty());
+    let not_in = db
+        .query("SELECT id FROM employee WHERE rank * 2 NOT IN () AND (name IN () OR rank = 1) ORDER BY id")
+        .unwrap();
+    assert_eq!(not_in, rows(&[&["1"], &["4"]]));
+    let projected = db.query("SELECT id IN (), id NOT IN () FROM employee WHERE id = 1").unwrap();
+    assert_eq!(projected, rows(&[&["false", "true"]]));
+}
+
+#[test]
+fn offset_rows_with_fetch_first() {
+    let mut db = db();
+    let result = db
+        .query("SELECT id FROM employee ORDER BY id OFFSET 2 ROWS FETCH FIRST 3 ROWS ONLY")
+        .unwrap();
+    assert_eq!(result, rows(&[&["3"], &["4"], &["5"]]));
+    let next = db
+        .query("SELECT id FROM employee ORDER BY id OFFSET 1 ROW FETCH NEXT 1 ROW ONLY")
+        .unwrap();
+    assert_eq!(next, rows(&[&["2"]]));
+}
+
+#[test]
+fn where_compares_a_function_result() {
+    let mut db = db();
+    db.run_sql("INSERT INTO employee VALUES (6, 'alice', 1, 0)").unwrap();
+    let result = db.query("SELECT id, name FROM employee WHERE UPPER(name) = 'ALICE'").unwrap();
+    assert_eq!(result, rows(&[&["6", "alice"]]));
+    // 函数在右侧
+    let right = db.query("SELECT id FROM employee WHERE 'ALEX' = UPPER(name)").unwrap();
+    assert_eq!(right, rows(&[&["3"]]));
+}
+
+#[test]
+fn query_stringifies_every_cell() {
+    let mut db = db();
+    let result = db
+        .query("SELECT id, name, rank / 2, rank > 0, NULL FROM employee WHERE id < 3 ORDER BY id")
+        .unwrap();
+    assert_eq!(
+        result,
+        rows(&[&["1", "vee", "0.5", "true", "NULL"], &["2", "lynne", "0", "false", "NULL"]])
+    );
+}
+
+#[test]
+fn unqualified_duplicate_column_after_join_is_ambiguous() {
+    let mut db = db();
+    let sql = "SELECT id FROM employee e JOIN department d ON e.department_id = d.id";
+    let err = db.query(sql).unwrap_err();
+    assert!(format!("{:?}", err).to_lowercase().contains("ambiguous"), "{:?}", err);
+    // 加上限定名后可以区分
+    let qualified = db
+        .query("SELECT e.id, d.id FROM employee e JOIN department d ON e.department_id = d.id WHERE e.id = 3")
+        .unwrap();
+    assert_eq!(qualified, rows(&[&["3", "2"]]));
+}
+
+#[test]
+fn duplicate_column_of_an_aliased_derived_table_is_ambiguous() {
+    let mut db = db();
+    let derived = "(SELECT e.id, d.id, e.name FROM employee e JOIN department d ON e.department_id = d.id) x";
+    for column in ["x.id", "id"] {
+        let err = db.query(&format!("SELECT {} FROM {}", column, derived)).unwrap_err();
+        assert!(format!("{:?}", err).contains("ambiguous"), "{:?}", err);
+    }
+    // 同一个表出现两次时别名下的同名列也无法区分
+    let err = db.query("SELECT e.id FROM employee e JOIN employee e ON e.id = e.rank").unwrap_err();
+    assert!(format!("{:?}", err).contains("ambiguous"), "{:?}", err);
+    // 不重名的列仍然可以通过别名引用
+    let result = db.query(&format!("SELECT x.name FROM {} WHERE x.name = 'vee'", derived)).unwrap();
+    assert_eq!(result, rows(&[&["vee"]]));
+}
+
+#[test]
+fn filter_is_evaluated_for_every_batch() {
+    let mut db = SimpleDB::default();
+    // 每个 batch 两行 五行数据分成三个 batch
+    let csv_conf = CsvConfig {
+        batch_size: 2,
+        ..CsvConfig::default()
+    };
+    db.create_csv_table("employee", "data/employee.csv", csv_conf).unwrap();
+    assert_eq!(db.run_sql("SELECT * FROM employee").unwrap().len(), 3);
+
+    let result = db.query("SELECT id, name FROM employee WHERE rank <> 0").unwrap();
+    assert_eq!(result, rows(&[&["1", "vee"], &["4", "jack"], &["5", "mike"]]));
+    let last_batch_only = db.query("SELECT id FROM employee WHERE id = 5").unwrap();
+    assert_eq!(last_batch_only, rows(&[&["5"]]));
+}
+
+#[test]
+fn like_and_ilike_match_wildcards_and_skip_nulls() {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE t (id int, name varchar)").unwrap();
+    db.run_sql("INSERT INTO t VALUES (1, 'Ann'), (2, NULL), (3, 'bob'), (4, 'Abby'), (5, 'an')").unwrap();
+
+    let ids = |db: &mut SimpleDB, predicate: &str| {
+        db.query(&format!("SELECT id FROM t WHERE {} ORDER BY id", predicate))
+            .unwrap()
+            .into_iter()
+            .map(|row| row[0].clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&mut db, "name LIKE 'A%'"), ["1", "4"]);
+    assert_eq!(ids(&mut db, "name LIKE '_n%'"), ["1", "5"]);
+    assert_eq!(ids(&mut db, "name LIKE 'b_b'"), ["3"]);
+    // NULL 的行既不满足 LIKE 也不满足 NOT LIKE
+    assert_eq!(ids(&mut db, "name NOT LIKE 'A%'"), ["3", "5"]);
+    assert!(ids(&mut db, "name LIKE NULL").is_empty());
+    assert!(ids(&mut db, "NULL NOT LIKE 'A%'").is_empty());
+    // ILIKE 两侧都转为小写后匹配
+    assert_eq!(ids(&mut db, "name ILIKE 'a%'"), ["1", "4", "5"]);
+    assert_eq!(ids(&mut db, "name NOT ILIKE 'A%' AND id > 1"), ["3"]);
+    assert_eq!(ids(&mut db, "id = 3 OR upper(name) ILIKE '_BB_'"), ["3", "4"]);
+
+    let result = db.query("SELECT id, name LIKE 'A%', name ILIKE 'a%' FROM t WHERE id < 4").unwrap();
+    assert_eq!(
+        result,
+        rows(&[&["1", "true", "true"], &["2", "NULL", "NULL"], &["3", "false", "false"]])
+    );
+    let err = db.query("SELECT id FROM t WHERE ILIKE 'a'").unwrap_err();
+    assert!(format!("{:?}", err).starts_with("ParserError"), "{:?}", err);
+}
+
+#[test]
+fn explain_json_writes_expressions_as_json_objects() {
+    let mut db = db();
+    let result = db.query("EXPLAIN (FORMAT JSON) SELECT id FROM employee WHERE id > 1").unwrap();
+    assert_eq!(result.len(), 1);
+    let id = r#"{"column":{"table":null,"name":"id"}}"#;
+    let expected = format!(
+        concat!(
+            r#"{{"node":"Projection","exprs":[{id}],"inputs":[{{"node":"Filter","#,
+            r#""predicate":{{"binary":{{"op":">","left":{id},"right":{{"literal":1}}}}}},"#,
+            r#""inputs":[{{"node":"TableScan","source":"employee","projection":null,"inputs":[]}}]}}]}}"#
+        ),
+        id = id
+    );
+    assert_eq!(result[0][0], expected);
+}
+
+#[test]
+fn integer_division_is_configurable() {
+    let mut db = db();
+    let sql = "SELECT 7 / 2, id / 2, 7 % 2, 7.0 / 2 FROM employee WHERE id = 5";
+    // 默认为浮点数除法
+    assert_eq!(db.query(sql).unwrap(), rows(&[&["3.5", "2.5", "1", "3.5"]]));
+    db.set_integer_division(true);
+    // 两个整数之间的 `/` 截断为整数 有浮点数时仍是浮点数除法
+    assert_eq!(db.query(sql).unwrap(), rows(&[&["3", "2", "1", "3.5"]]));
+    db.set_integer_division(false);
+    assert_eq!(db.query("SELECT 7 / 2 FROM employee WHERE id = 1").unwrap(), rows(&[&["3.5"]]));
+}
+
+#[test]
+fn query_schema_of_a_join_and_projection() {
+    let db = db();
+    let schema = db
+        .query_schema(
+            "SELECT e.name, d.department_name AS dept, e.id + 1 FROM employee e \
+             JOIN department d ON e.department_id = d.id",
+        )
+        .unwrap();
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| (field.qualified_name(), field.data_type().clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        fields,
+        [
+            ("e.name".to_string(), DataType::Utf8),
+            ("dept".to_string(), DataType::Utf8),
+            ("id + 1".to_string(), DataType::Int64),
+        ]
+    );
+}
+
+#[test]
+fn three_way_join_matches_keys_against_all_joined_tables() {
+    let mut db = db();
+    let expected = rows(&[
+        &["yuni", "xiao", "2002"],
+        &["xiao", "xueqiu", "2003"],
+        &["xueqiu", "dcx", "2004"],
+        &["yuni", "xueqiu", "2005"],
+    ]);
+    // p1 和 p2 之间没有连接条件 knows 需要与前面两个表连接后的结果匹配
+    let result = db
+        .query(
+            "SELECT p1.name, p2.name, k.year FROM person p1, person p2, knows k \
+             WHERE p1.id = k.id1 AND k.id2 = p2.id ORDER BY k.year",
+        )
+        .unwrap();
+    assert_eq!(result, expected);
+    let result = db
+        .query(
+            "SELECT p1.name, p2.name, k.year FROM person p1 JOIN knows k ON p1.id = k.id1 \
+             JOIN person p2 ON k.id2 = p2.id WHERE p2.age > 21 ORDER BY k.year",
+        )
+        .unwrap();
+    assert_eq!(result, expected[1..].to_vec());
+}
+
+#[test]
+fn multiple_ctes_can_reference_earlier_ones() {
+    let mut db = db();
+    let result = db
+        .query(
+            "WITH ranked AS (SELECT id, name FROM employee WHERE rank > 0), \
+             later AS (SELECT id FROM ranked WHERE id > 1) \
+             SELECT ranked.name FROM ranked JOIN later ON ranked.id = later.id ORDER BY ranked.name",
+        )
+        .unwrap();
+    assert_eq!(result, rows(&[&["jack"], &["mike"]]));
+    // CTE 的名字优先于同名的表
+    let shadowed = db
+        .query("WITH employee AS (SELECT id FROM employee WHERE id = 3) SELECT id FROM employee")
+        .unwrap();
+    assert_eq!(shadowed, rows(&[&["3"]]));
+}
+
+#[test]
+fn null_constant_in_a_projection_is_a_typed_column() {
+    let mut db = db();
+    let sql = "SELECT id, NULL AS note FROM employee WHERE id < 3";
+    let batches = db.run_sql(sql).unwrap();
+    let schema = batches[0].schema();
+    let note = schema.field(1);
+    assert_eq!((note.name().as_str(), note.data_type()), ("note", &DataType::Utf8));
+    assert!(note.is_nullable());
+    assert_eq!(batches[0].column(1).data_type(), &DataType::Utf8);
+    assert_eq!(batches[0].column(1).null_count(), 2);
+    assert_eq!(db.query(sql).unwrap(), rows(&[&["1", "NULL"], &["2", "NULL"]]));
+}
+
+#[test]
+fn order_by_a_column_that_is_not_selected() {
+    let mut db = db();
+    let result = db.query("SELECT name FROM employee ORDER BY rank DESC, id").unwrap();
+    assert_eq!(result, rows(&[&["mike"], &["vee"], &["jack"], &["lynne"], &["Alex"]]));
+    let batches = db.run_sql("SELECT name FROM employee ORDER BY rank DESC, id").unwrap();
+    assert_eq!(batches[0].num_columns(), 1);
+    // 排序键也可以是没有选出的列组成的表达式
+    let result = db
+        .query("SELECT name FROM employee WHERE id < 5 ORDER BY department_id * 10 - id")
+        .unwrap();
+    assert_eq!(result, rows(&[&["lynne"], &["vee"], &["jack"], &["Alex"]]));
+}
+
+#[test]
+fn selecting_a_column_twice_gives_distinct_output_columns() {
+    let mut db = db();
+    let batches = db.run_sql("SELECT id, id, name FROM employee WHERE id < 3").unwrap();
+    let schema = batches[0].schema();
+    let names = schema.fields().iter().map(|field| field.name().as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["employee.id", "employee.id_1", "employee.name"]);
+    let result = db.query("SELECT id, id, name FROM employee WHERE id < 3").unwrap();
+    assert_eq!(result, rows(&[&["1", "1", "vee"], &["2", "2", "lynne"]]));
+    // 每一列独立计算
+    let result = db.query("SELECT id, id + 1, id FROM employee WHERE id = 4").unwrap();
+    assert_eq!(result, rows(&[&["4", "5", "4"]]));
+}
+
+#[test]
+fn between_on_date_and_string_columns() {
+    let path = temp_path("hires.csv");
+    fs::write(&path, "id,name,hire_date\n1,ann,2019-12-31\n2,bob,2020-01-01\n3,carl,2020-06-15\n4,nina,2021-01-01\n")
+        .unwrap();
+    let mut db = SimpleDB::default();
+    db.create_csv_table("hires", &path, CsvConfig::default()).unwrap();
+    let ids = |db: &mut SimpleDB, predicate: &str| {
+        db.query(&format!("SELECT id FROM hires WHERE {} ORDER BY id", predicate))
+            .unwrap()
+            .into_iter()
+            .map(|row| row[0].clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&mut db, "hire_date BETWEEN '2020-01-01' AND '2020-12-31'"), ["2", "3"]);
+    assert_eq!(ids(&mut db, "hire_date NOT BETWEEN '2020-01-01' AND '2020-12-31'"), ["1", "4"]);
+    assert_eq!(ids(&mut db, "name BETWEEN 'b' AND 'm'"), ["2", "3"]);
+    assert_eq!(ids(&mut db, "name NOT BETWEEN 'b' AND 'm'"), ["1", "4"]);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn projected_join_columns_keep_their_qualifiers() {
+    let mut db = db();
+    let inner = "SELECT e.name, d.id FROM employee e JOIN department d ON e.department_id = d.id";
+    // 没有别名的子查询保留列原本的限定名
+    let result = db
+        .query(&format!("SELECT e.name, d.id FROM ({}) WHERE d.id = 3", inner))
+        .unwrap();
+    assert_eq!(result, rows(&[&["mike", "3"]]));
+    let result = db
+        .query(&format!("SELECT x.name FROM ({}) x WHERE x.id = 2 ORDER BY x.name", inner))
+        .unwrap();
+    assert_eq!(result, rows(&[&["Alex"], &["jack"]]));
+}
+
+#[test]
+fn and_or_follow_three_valued_logic() {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE b (id int, x boolean, y boolean)").unwrap();
+    db.run_sql(
+        "INSERT INTO b VALUES (1, true, true), (2, true, false), (3, true, NULL), (4, false, false), \
+         (5, false, NULL), (6, NULL, NULL)",
+    )
+    .unwrap();
+    let result = db.query("SELECT id, x AND y, y AND x, x OR y, y OR x FROM b ORDER BY id").unwrap();
+    assert_eq!(
+        result,
+        rows(&[
+            &["1", "true", "true", "true", "true"],
+            &["2", "false", "false", "true", "true"],
+            &["3", "NULL", "NULL", "true", "true"],
+            &["4", "false", "false", "false", "false"],
+            &["5", "false", "false", "NULL", "NULL"],
+            &["6", "NULL", "NULL", "NULL", "NULL"],
+        ])
+    );
+    // 结果为 NULL 的行不满足 WHERE
+    assert_eq!(db.query("SELECT id FROM b WHERE x OR y ORDER BY id").unwrap(), rows(&[&["1"], &["2"], &["3"]]));
+    assert_eq!(db.query("SELECT id FROM b WHERE NOT (x AND y) ORDER BY id").unwrap(), rows(&[&["2"], &["4"], &["5"]]));
+}
+
+#[test]
+fn in_and_not_in_lists() {
+    let mut db = db();
+    let names = |db: &mut SimpleDB, predicate: &str| {
+        db.query(&format!("SELECT name FROM employee WHERE {} ORDER BY id", predicate))
+            .unwrap()
+            .into_iter()
+            .map(|row| row[0].clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&mut db, "department_id IN (1, 3)"), ["vee", "lynne", "mike"]);
+    assert_eq!(names(&mut db, "department_id NOT IN (1, 3)"), ["Alex", "jack"]);
+    assert_eq!(names(&mut db, "name IN ('jack', 'vee', 'nobody')"), ["vee", "jack"]);
+    assert_eq!(names(&mut db, "name NOT IN ('jack', 'vee')"), ["lynne", "Alex", "mike"]);
+
+    // 左侧为 NULL 时 IN 和 NOT IN 都不成立
+    db.run_sql("CREATE TABLE t (id int, v int)").unwrap();
+    db.run_sql("INSERT INTO t VALUES (1, 1), (2, NULL), (3, 3)").unwrap();
+    let ids = |db: &mut SimpleDB, predicate: &str| {
+        db.query(&format!("SELECT id FROM t WHERE {} ORDER BY id", predicate)).unwrap()
+    };
+    assert_eq!(ids(&mut db, "v IN (1, 2)"), rows(&[&["1"]]));
+    assert_eq!(ids(&mut db, "v NOT IN (1, 2)"), rows(&[&["3"]]));
+}
+
+#[test]
+fn same_column_equality_is_a_filter_not_a_join_key() {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE t (id int, x int)").unwrap();
+    db.run_sql("INSERT INTO t VALUES (1, 10), (2, NULL), (3, 30)").unwrap();
+    // x = x 对非空的行成立 NULL 的行不满足
+    assert_eq!(db.query("SELECT id FROM t WHERE x = x ORDER BY id").unwrap(), rows(&[&["1"], &["3"]]));
+    assert_eq!(db.query("SELECT id FROM t WHERE x = x AND id > 1").unwrap(), rows(&[&["3"]]));
+    db.create_csv_table("employee", "data/employee.csv", CsvConfig::default()).unwrap();
+    let result = db.query("SELECT id FROM employee WHERE employee.id = employee.id AND id > 3").unwrap();
+    assert_eq!(result, rows(&[&["4"], &["5"]]));
+}
+
+#[test]
+fn is_null_and_is_not_null_on_empty_csv_fields() {
+    let path = temp_path("nulls.csv");
+    fs::write(&path, "id,score,ratio,active\n1,10,0.5,true\n2,,,\n3,30,1.5,false\n").unwrap();
+    let mut db = SimpleDB::default();
+    db.create_csv_table("t", &path, CsvConfig::default()).unwrap();
+    for column in ["score", "ratio", "active"] {
+        let null = db.query(&format!("SELECT id FROM t WHERE {} IS NULL", column)).unwrap();
+        assert_eq!(null, rows(&[&["2"]]), "{}", column);
+        let not_null = db
+            .query(&format!("SELECT id FROM t WHERE {} IS NOT NULL ORDER BY id", column))
+            .unwrap();
+        assert_eq!(not_null, rows(&[&["1"], &["3"]]), "{}", column);
+    }
+    let result = db.query("SELECT id, score IS NULL, active IS NOT NULL FROM t ORDER BY id").unwrap();
+    assert_eq!(
+        result,
+        rows(&[&["1", "false", "true"], &["2", "true", "false"], &["3", "false", "true"]])
+    );
+    fs::remove_file(&path).unwrap();
+
+    // 字符串列中空的字段读取为空字符串 NULL 通过 INSERT 写入
+    db.run_sql("CREATE TABLE s (id int, name varchar)").unwrap();
+    db.run_sql("INSERT INTO s VALUES (1, 'a'), (2, NULL), (3, '')").unwrap();
+    assert_eq!(db.query("SELECT id FROM s WHERE name IS NULL").unwrap(), rows(&[&["2"]]));
+    let not_null = db.query("SELECT id FROM s WHERE name IS NOT NULL ORDER BY id").unwrap();
+    assert_eq!(not_null, rows(&[&["1"], &["3"]]));
+}
+
+#[test]
+fn not_negates_predicates_and_keeps_nulls() {
+    let mut db = db();
+    let ids = |db: &mut SimpleDB, predicate: &str| {
+        db.query(&format!("SELECT id FROM employee WHERE {} ORDER BY id", predicate))
+            .unwrap()
+            .into_iter()
+            .map(|row| row[0].clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&mut db, "NOT (department_id = 2)"), ["1", "2", "5"]);
+    assert_eq!(ids(&mut db, "NOT NOT (department_id = 2)"), ["3", "4"]);
+    assert_eq!(ids(&mut db, "NOT department_id IN (1, 2)"), ["5"]);
+    assert_eq!(ids(&mut db, "NOT (rank > 0 AND department_id = 1)"), ["2", "3", "4", "5"]);
+
+    // NOT NULL 仍然是 NULL
+    let result = db.query("SELECT NOT (id > 1), NOT (NULL = 1) FROM employee WHERE id < 3 ORDER BY id").unwrap();
+    assert_eq!(result, rows(&[&["true", "NULL"], &["false", "NULL"]]));
+}
+
+#[test]
+fn parenthesized_query_with_an_outer_limit() {
+    let mut db = db();
+    let result = db.query("(SELECT id FROM employee ORDER BY id DESC) LIMIT 2").unwrap();
+    assert_eq!(result, rows(&[&["5"], &["4"]]));
+    let result = db
+        .query("((SELECT id, name FROM employee WHERE rank > 0)) ORDER BY id LIMIT 2")
+        .unwrap();
+    assert_eq!(result, rows(&[&["1", "vee"], &["4", "jack"]]));
+}
+
+#[test]
+fn integer_columns_mix_with_float_literals() {
+    let mut db = db();
+    let result = db
+        .query("SELECT id * 1.5, 0.5 + id, id - 0.25, id / 0, id % 0 FROM employee WHERE id * 1.1 > 4 AND 2.5 < id ORDER BY id")
+        .unwrap();
+    // 除数为 0 时结果为 NULL
+    assert_eq!(
+        result,
+        rows(&[&["6", "4.5", "3.75", "NULL", "NULL"], &["7.5", "5.5", "4.75", "NULL", "NULL"]])
+    );
+    let batches = db.run_sql("SELECT id * 1.5, id + 1 FROM employee").unwrap();
+    assert_eq!(batches[0].column(0).data_type(), &DataType::Float64);
+    assert_eq!(batches[0].column(1).data_type(), &DataType::Int64);
+}
+
+#[test]
+fn default_optimizer_pushes_projections_into_the_scan() {
+    let mut db = db();
+    let plan = db.query("EXPLAIN SELECT name, id FROM employee").unwrap();
+    let lines: Vec<_> = plan.iter().map(|row| row[0].trim().to_string()).collect();
+    assert_eq!(lines, vec!["TableScan:", "source: \"employee\"", "projection: Some([1, 0])"]);
+
+    // 位于 Filter 之下的子查询投影同样被下推到表扫描
+    let sql = "SELECT t.name FROM (SELECT name, id FROM employee) t WHERE t.id > 2";
+    let plan = db.query(&format!("EXPLAIN {}", sql)).unwrap();
+    assert!(plan.iter().any(|row| row[0].trim() == "projection: Some([1, 0])"), "{:?}", plan);
+    assert_eq!(db.query(sql).unwrap(), rows(&[&["Alex"], &["jack"], &["mike"]]));
+}
+
+#[test]
+fn in_subquery_compares_values_with_the_binary_coercion_rules() {
+    let mut db = db();
+    db.run_sql("CREATE TABLE s (v varchar, f float)").unwrap();
+    db.run_sql("INSERT INTO s VALUES ('1', 2.0), ('3', 4.5)").unwrap();
+    // 整数与浮点数转换为 Float64 后比较
+    let result = db.query("SELECT id FROM employee WHERE id IN (SELECT f FROM s) ORDER BY id").unwrap();
+    assert_eq!(result, rows(&[&["2"]]));
+    // 整数与字符串不能比较 不会因为都显示为 '1' 而匹配
+    let err = db.query("SELECT id FROM employee WHERE id IN (SELECT v FROM s)").unwrap_err();
+    assert!(format!("{:?}", err).contains("Cannot evaluate IN subquery"), "{:?}", err);
+}
+
+#[test]
+fn trailing_lock_clauses_are_ignored() {
+    let mut db = db();
+    let sql = "SELECT id, name FROM employee WHERE rank > 0 ORDER BY id";
+    let expected = db.query(sql).unwrap();
+    for lock in ["FOR UPDATE", "FOR SHARE", "FOR NO KEY UPDATE OF employee NOWAIT", "FOR UPDATE SKIP LOCKED FOR SHARE;"] {
+        assert_eq!(db.query(&format!("{} {}", sql, lock)).unwrap(), expected, "{}", lock);
+    }
+    // 只去掉语句末尾的行锁子句 子查询中的不支持
+    assert!(db.query("SELECT id FROM (SELECT id FROM employee FOR UPDATE) t").is_err());
+}
+
+#[test]
+fn order_by_keeps_the_input_order_of_equal_keys() {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE t (k int, seq int)").unwrap();
+    // 分多次插入 得到多个 batch；seq 是插入的顺序，与排序键无关
+    let mut inserted = vec![];
+    for chunk in 0..4 {
+        let values = (0..50)
+            .map(|i| {
+                let seq = chunk * 50 + i;
+                let k = (seq * 7) % 3;
+                inserted.push((k, seq));
+                format!("({}, {})", k, seq)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        db.run_sql(&format!("INSERT INTO t VALUES {}", values)).unwrap();
+    }
+
+    for (order, descending) in [("ASC", false), ("DESC", true)] {
+        let mut expected = inserted.clone();
+        // Vec::sort_by_key 是稳定排序
+        if descending {
+            expected.sort_by_key(|(k, _)| std::cmp::Reverse(*k));
+        } else {
+            expected.sort_by_key(|(k, _)| *k);
+        }
+        let expected = expected
+            .iter()
+            .map(|(k, seq)| vec![k.to_string(), seq.to_string()])
+            .collect::<Vec<_>>();
+        let result = db.query(&format!("SELECT k, seq FROM t ORDER BY k {}", order)).unwrap();
+        assert_eq!(result, expected, "{}", order);
+    }
+}
+
+#[test]
+fn non_boolean_where_is_a_planning_error() {
+    let mut db = db();
+    for sql in [
+        "SELECT id FROM employee WHERE id + 1",
+        "DELETE FROM employee WHERE id + 1",
+        "UPDATE employee SET rank = 1 WHERE id + 1",
+    ] {
+        let err = db.query(sql).unwrap_err();
+        assert_eq!(
+            format!("{:?}", err),
+            r#"PlanError("filter predicate must be a boolean, found Int64")"#,
+            "{}",
+            sql
+        );
+    }
+    let err = db.query("SELECT id FROM employee WHERE name").unwrap_err();
+    assert_eq!(format!("{:?}", err), r#"PlanError("filter predicate must be a boolean, found Utf8")"#);
+    // 表没有被修改
+    assert_eq!(db.query("SELECT count(*) FROM employee WHERE rank = 1").unwrap(), rows(&[&["2"]]));
+}
+
+#[test]
+fn boolean_column_compared_with_a_boolean_literal() {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE f (id int, flag boolean, other boolean)").unwrap();
+    db.run_sql("INSERT INTO f VALUES (1, true, true), (2, false, true), (3, NULL, true), (4, true, NULL), (5, false, false)")
+        .unwrap();
+    // flag 为 NULL 时比较的结果也是 NULL 不会被任何一个条件选中
+    assert_eq!(db.query("SELECT id FROM f WHERE flag = true ORDER BY id").unwrap(), rows(&[&["1"], &["4"]]));
+    assert_eq!(db.query("SELECT id FROM f WHERE flag != true ORDER BY id").unwrap(), rows(&[&["2"], &["5"]]));
+    assert_eq!(
+        db.query("SELECT id, flag = true FROM f ORDER BY id").unwrap(),
+        rows(&[&["1", "true"], &["2", "false"], &["3", "NULL"], &["4", "true"], &["5", "false"]])
+    );
+    // 两个布尔列比较 任意一边为 NULL 时不选中
+    assert_eq!(db.query("SELECT id FROM f WHERE flag = other ORDER BY id").unwrap(), rows(&[&["1"], &["5"]]));
+}
+
+#[test]
+fn table_alias_qualifies_columns_in_select_and_where() {
+    let mut db = db();
+    assert_eq!(db.query("SELECT e.name FROM employee e WHERE e.id = 1").unwrap(), rows(&[&["vee"]]));
+    // 起了别名之后原来的表名不能再作为限定名
+    let err = db.query("SELECT employee.name FROM employee e WHERE e.id = 1").unwrap_err();
+    assert!(format!("{:?}", err).starts_with("PlanError"), "{:?}", err);
+}
+
+#[test]
+fn select_literals_without_from_returns_one_row() {
+    let mut db = SimpleDB::default();
+    let sql = "SELECT 1, 'x', true";
+    assert_eq!(db.query(sql).unwrap(), rows(&[&["1", "x", "true"]]));
+    let schema = db.query_schema(sql).unwrap();
+    let types = schema.fields().iter().map(|field| field.data_type().clone()).collect::<Vec<_>>();
+    assert_eq!(types, vec![DataType::Int64, DataType::Utf8, DataType::Boolean]);
+}
@@ -0,0 +1,21 @@
+mod common;
+
+use simple_db::batches_to_strings;
+
+use common::{db, rows};
+
+#[test]
+fn mutations_after_a_snapshot_do_not_change_its_results() {
+    let mut db = db();
+    let snapshot = db.snapshot();
+    let sql = "SELECT id, name FROM employee WHERE department_id = 1 ORDER BY id";
+
+    db.run_sql("INSERT INTO employee VALUES (6, 'new', 1, 0)").unwrap();
+    db.run_sql("DELETE FROM employee WHERE id = 1").unwrap();
+    db.run_sql("UPDATE employee SET name = 'changed' WHERE id = 2").unwrap();
+
+    let live = db.query(sql).unwrap();
+    assert_eq!(live, rows(&[&["2", "changed"], &["6", "new"]]));
+    let before = batches_to_strings(&db.run_sql_on_snapshot(&snapshot, sql).unwrap()).unwrap();
+    assert_eq!(before, rows(&[&["1", "vee"], &["2", "lynne"]]));
+}
@@ -0,0 +1,30 @@
+use simple_db::{CsvConfig, SimpleDB};
+
+const QUERIES: [&str; 4] = [
+    "SELECT id, name, rank * 2 FROM employee WHERE id > 1",
+    "SELECT department_id, count(*), sum(rank), min(name), max(id), avg(rank) FROM employee GROUP BY department_id",
+    "SELECT count(*), sum(id), avg(rank) FROM employee WHERE rank < 2",
+    "SELECT e.name, d.department_name FROM employee e JOIN department d ON e.department_id = d.id WHERE e.rank = 0",
+];
+
+fn run_all(num_threads: usize) -> Vec<Vec<Vec<String>>> {
+    let mut db = SimpleDB::default();
+    // 每个 batch 只有两行 让并行的算子处理多个 batch
+    let csv_conf = CsvConfig {
+        batch_size: 2,
+        ..CsvConfig::default()
+    };
+    db.create_csv_table("employee", "data/employee.csv", csv_conf.clone()).unwrap();
+    db.create_csv_table("department", "data/department.csv", csv_conf).unwrap();
+    db.set_num_threads(num_threads).unwrap();
+    QUERIES.iter().map(|sql| db.query(sql).unwrap()).collect()
+}
+
+#[test]
+fn single_thread_matches_multi_thread() {
+    let single = run_all(1);
+    assert_eq!(single, run_all(4));
+    assert_eq!(single, run_all(0));
+    assert_eq!(single[1].len(), 3);
+    assert_eq!(single[2], vec![vec!["4".to_string(), "10".to_string(), "0.5".to_string()]]);
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use simple_db::SimpleDB;
+
+use common::rows;
+
+fn table() -> SimpleDB {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE t (id int, v int, price decimal(10,2), name varchar)").unwrap();
+    db.run_sql("INSERT INTO t VALUES (1, 10, 1.5, 'a'), (2, 20, 2.5, 'b')").unwrap();
+    db
+}
+
+#[test]
+fn update_uses_the_insert_type_rules() {
+    let mut db = table();
+    // 浮点数不能存入整数列 不会被截断为 2
+    let err = db.run_sql("UPDATE t SET v = 2.9 WHERE id = 1").unwrap_err();
+    assert!(format!("{:?}", err).contains("cannot assign a value of type Float64 to column `v`"), "{:?}", err);
+    let err = db.run_sql("UPDATE t SET name = 1 WHERE id = 2").unwrap_err();
+    assert!(format!("{:?}", err).contains("cannot assign a value of type Int64 to column `name`"), "{:?}", err);
+    assert_eq!(
+        db.query("SELECT id, v, price, name FROM t ORDER BY id").unwrap(),
+        rows(&[&["1", "10", "1.50", "a"], &["2", "20", "2.50", "b"]])
+    );
+
+    // 整数可以存入整数和小数列 浮点数可以存入小数列
+    db.run_sql("UPDATE t SET v = 3, price = 7 WHERE id = 1").unwrap();
+    db.run_sql("UPDATE t SET price = 2.25, name = NULL WHERE id = 2").unwrap();
+    assert_eq!(
+        db.query("SELECT id, v, price, name FROM t ORDER BY id").unwrap(),
+        rows(&[&["1", "3", "7.00", "a"], &["2", "20", "2.25", "NULL"]])
+    );
+}
+
+#[test]
+fn update_copies_another_column_into_the_matching_rows() {
+    let mut db = SimpleDB::default();
+    db.run_sql("CREATE TABLE t (id int, a int, b int)").unwrap();
+    db.run_sql("INSERT INTO t VALUES (1, 10, 100), (2, 20, 200), (3, 30, NULL)").unwrap();
+
+    db.run_sql("UPDATE t SET a = b WHERE id >= 2").unwrap();
+    // 符合条件的行取同一行 b 的值（包括 NULL） 其余的行不变
+    assert_eq!(
+        db.query("SELECT id, a, b FROM t ORDER BY id").unwrap(),
+        rows(&[&["1", "10", "100"], &["2", "200", "200"], &["3", "NULL", "NULL"]])
+    );
+}
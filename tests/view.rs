@@ -0,0 +1,29 @@
+mod common;
+
+use common::{db, rows};
+
+#[test]
+fn select_from_a_view_and_a_filtered_view() {
+    let mut db = db();
+    db.run_sql("CREATE VIEW all_emps AS SELECT id, name FROM employee").unwrap();
+    db.run_sql("CREATE VIEW ranked_emps AS SELECT * FROM employee WHERE rank > 0").unwrap();
+
+    let all = db.query("SELECT count(*) FROM all_emps").unwrap();
+    assert_eq!(all, rows(&[&["5"]]));
+    let ranked = db.query("SELECT id, name FROM ranked_emps ORDER BY id").unwrap();
+    assert_eq!(ranked, rows(&[&["1", "vee"], &["4", "jack"], &["5", "mike"]]));
+    // 外层的过滤条件与视图中的条件同时生效
+    let filtered = db.query("SELECT name FROM ranked_emps WHERE department_id = 2").unwrap();
+    assert_eq!(filtered, rows(&[&["jack"]]));
+
+    // 引用视图时才展开 可以看到之后插入的行
+    db.run_sql("INSERT INTO employee VALUES (6, 'kim', 3, 1)").unwrap();
+    let ranked = db.query("SELECT id FROM ranked_emps WHERE id > 4 ORDER BY id").unwrap();
+    assert_eq!(ranked, rows(&[&["5"], &["6"]]));
+
+    db.run_sql("DROP VIEW ranked_emps").unwrap();
+    let err = db.query("SELECT * FROM ranked_emps").unwrap_err();
+    assert!(format!("{:?}", err).contains("ranked_emps"), "{:?}", err);
+    let err = db.run_sql("CREATE VIEW all_emps AS SELECT id FROM employee").unwrap_err();
+    assert!(format!("{:?}", err).contains("already exists"), "{:?}", err);
+}